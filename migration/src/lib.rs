@@ -6,6 +6,26 @@ mod m20220103_000001_create_user_roles_table;
 mod m20220104_000001_create_refresh_tokens_table;
 mod m20220105_000001_create_files_table;
 mod m20220106_000001_create_users_email_history_table;
+mod m20220107_000001_add_two_factor_to_users_table;
+mod m20220108_000001_add_rotation_to_refresh_tokens_table;
+mod m20220109_000001_hash_refresh_tokens;
+mod m20220110_000001_add_session_details_to_refresh_tokens_table;
+mod m20220111_000001_create_revoked_access_tokens_table;
+mod m20220112_000001_add_tokens_valid_after_to_users_table;
+mod m20220113_000001_create_login_lockouts_table;
+mod m20220114_000001_create_personal_access_tokens_table;
+mod m20220115_000001_create_service_accounts_tables;
+mod m20220116_000001_create_user_identities_table;
+mod m20220117_000001_create_webauthn_credentials_table;
+mod m20220118_000001_add_magic_login_token_to_users_table;
+mod m20220119_000001_create_password_history_table;
+mod m20220120_000001_create_impersonation_logs_table;
+mod m20220121_000001_create_permissions_tables;
+mod m20220122_000001_add_parent_to_roles_table;
+mod m20220123_000001_add_validity_to_user_roles_table;
+mod m20220124_000001_add_mfa_token_to_users_table;
+mod m20220125_000001_add_access_tokens_valid_after_to_users_table;
+mod m20220126_000001_add_tokens_valid_after_to_service_accounts_table;
 
 pub struct Migrator;
 
@@ -19,6 +39,26 @@ impl MigratorTrait for Migrator {
 			Box::new(m20220104_000001_create_refresh_tokens_table::Migration),
 			Box::new(m20220105_000001_create_files_table::Migration),
 			Box::new(m20220106_000001_create_users_email_history_table::Migration),
+			Box::new(m20220107_000001_add_two_factor_to_users_table::Migration),
+			Box::new(m20220108_000001_add_rotation_to_refresh_tokens_table::Migration),
+			Box::new(m20220109_000001_hash_refresh_tokens::Migration),
+			Box::new(m20220110_000001_add_session_details_to_refresh_tokens_table::Migration),
+			Box::new(m20220111_000001_create_revoked_access_tokens_table::Migration),
+			Box::new(m20220112_000001_add_tokens_valid_after_to_users_table::Migration),
+			Box::new(m20220113_000001_create_login_lockouts_table::Migration),
+			Box::new(m20220114_000001_create_personal_access_tokens_table::Migration),
+			Box::new(m20220115_000001_create_service_accounts_tables::Migration),
+			Box::new(m20220116_000001_create_user_identities_table::Migration),
+			Box::new(m20220117_000001_create_webauthn_credentials_table::Migration),
+			Box::new(m20220118_000001_add_magic_login_token_to_users_table::Migration),
+			Box::new(m20220119_000001_create_password_history_table::Migration),
+			Box::new(m20220120_000001_create_impersonation_logs_table::Migration),
+			Box::new(m20220121_000001_create_permissions_tables::Migration),
+			Box::new(m20220122_000001_add_parent_to_roles_table::Migration),
+			Box::new(m20220123_000001_add_validity_to_user_roles_table::Migration),
+			Box::new(m20220124_000001_add_mfa_token_to_users_table::Migration),
+			Box::new(m20220125_000001_add_access_tokens_valid_after_to_users_table::Migration),
+			Box::new(m20220126_000001_add_tokens_valid_after_to_service_accounts_table::Migration),
 		]
 	}
 }
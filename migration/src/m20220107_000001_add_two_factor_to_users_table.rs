@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.add_column(ColumnDef::new(Users::TwoFactorSecret).string().null())
+					.add_column(
+						ColumnDef::new(Users::IsTwoFactorEnabled)
+							.boolean()
+							.not_null()
+							.default(false),
+					)
+					.add_column(ColumnDef::new(Users::TwoFactorRecoveryCodes).json().null())
+					.add_column(ColumnDef::new(Users::TwoFactorLastUsedStep).big_integer().null())
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.drop_column(Users::TwoFactorSecret)
+					.drop_column(Users::IsTwoFactorEnabled)
+					.drop_column(Users::TwoFactorRecoveryCodes)
+					.drop_column(Users::TwoFactorLastUsedStep)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	TwoFactorSecret,
+	IsTwoFactorEnabled,
+	TwoFactorRecoveryCodes,
+	TwoFactorLastUsedStep,
+}
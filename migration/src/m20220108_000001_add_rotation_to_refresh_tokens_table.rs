@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.add_column(ColumnDef::new(RefreshTokens::FamilyId).string().null())
+					.add_column(
+						ColumnDef::new(RefreshTokens::RevokedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.add_column(ColumnDef::new(RefreshTokens::ReplacedByTokenId).integer().null())
+					.to_owned(),
+			)
+			.await?;
+
+		// Every existing token starts its own family
+		manager
+			.get_connection()
+			.execute_unprepared("UPDATE refresh_tokens SET family_id = gen_random_uuid()::text WHERE family_id IS NULL")
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.modify_column(ColumnDef::new(RefreshTokens::FamilyId).string().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_refresh_tokens_family_id")
+					.table(RefreshTokens::Table)
+					.col(RefreshTokens::FamilyId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx_refresh_tokens_family_id")
+					.table(RefreshTokens::Table)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.drop_column(RefreshTokens::FamilyId)
+					.drop_column(RefreshTokens::RevokedAt)
+					.drop_column(RefreshTokens::ReplacedByTokenId)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum RefreshTokens {
+	Table,
+	FamilyId,
+	RevokedAt,
+	ReplacedByTokenId,
+}
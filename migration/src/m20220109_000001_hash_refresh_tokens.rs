@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		// Existing rows hold plaintext tokens and the hashing key is not available here, so they are invalidated
+		manager
+			.get_connection()
+			.execute_unprepared("DELETE FROM refresh_tokens")
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.rename_column(RefreshTokens::Token, RefreshTokens::TokenHash)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_refresh_tokens_token_hash")
+					.table(RefreshTokens::Table)
+					.col(RefreshTokens::TokenHash)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx_refresh_tokens_token_hash")
+					.table(RefreshTokens::Table)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.get_connection()
+			.execute_unprepared("DELETE FROM refresh_tokens")
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.rename_column(RefreshTokens::TokenHash, RefreshTokens::Token)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum RefreshTokens {
+	Table,
+	Token,
+	TokenHash,
+}
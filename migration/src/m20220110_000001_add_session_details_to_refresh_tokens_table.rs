@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.add_column(ColumnDef::new(RefreshTokens::UserAgent).string().null())
+					.add_column(ColumnDef::new(RefreshTokens::IpAddress).string().null())
+					.add_column(ColumnDef::new(RefreshTokens::Label).string().null())
+					.add_column(
+						ColumnDef::new(RefreshTokens::LastUsedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(RefreshTokens::Table)
+					.drop_column(RefreshTokens::UserAgent)
+					.drop_column(RefreshTokens::IpAddress)
+					.drop_column(RefreshTokens::Label)
+					.drop_column(RefreshTokens::LastUsedAt)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum RefreshTokens {
+	Table,
+	UserAgent,
+	IpAddress,
+	Label,
+	LastUsedAt,
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(RevokedAccessTokens::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(RevokedAccessTokens::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(
+						ColumnDef::new(RevokedAccessTokens::Jti)
+							.string()
+							.not_null()
+							.unique_key(),
+					)
+					.col(ColumnDef::new(RevokedAccessTokens::UserId).integer().not_null())
+					.col(
+						ColumnDef::new(RevokedAccessTokens::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(RevokedAccessTokens::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_revoked_access_tokens_user_id")
+							.from(RevokedAccessTokens::Table, RevokedAccessTokens::UserId)
+							.to(Users::Table, Users::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(RevokedAccessTokens::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum RevokedAccessTokens {
+	Table,
+	Id,
+	Jti,
+	UserId,
+	ExpiresAt,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	Id,
+}
@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(LoginLockouts::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(LoginLockouts::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(LoginLockouts::Scope).string().not_null())
+					.col(ColumnDef::new(LoginLockouts::Identifier).string().not_null())
+					.col(
+						ColumnDef::new(LoginLockouts::FailedAttempts)
+							.integer()
+							.not_null()
+							.default(0),
+					)
+					.col(
+						ColumnDef::new(LoginLockouts::WindowStartedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(LoginLockouts::LastFailedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(LoginLockouts::LockedUntil)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(LoginLockouts::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(LoginLockouts::UpdatedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_login_lockouts_scope_identifier")
+					.table(LoginLockouts::Table)
+					.col(LoginLockouts::Scope)
+					.col(LoginLockouts::Identifier)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(LoginLockouts::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum LoginLockouts {
+	Table,
+	Id,
+	Scope,
+	Identifier,
+	FailedAttempts,
+	WindowStartedAt,
+	LastFailedAt,
+	LockedUntil,
+	CreatedAt,
+	UpdatedAt,
+}
@@ -0,0 +1,141 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(ServiceAccounts::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(ServiceAccounts::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(ServiceAccounts::Name).string().not_null())
+					.col(ColumnDef::new(ServiceAccounts::Description).string().null())
+					.col(
+						ColumnDef::new(ServiceAccounts::ClientId)
+							.string()
+							.not_null()
+							.unique_key(),
+					)
+					.col(ColumnDef::new(ServiceAccounts::ClientSecretHash).string().not_null())
+					.col(
+						ColumnDef::new(ServiceAccounts::IsActive)
+							.boolean()
+							.not_null()
+							.default(true),
+					)
+					.col(
+						ColumnDef::new(ServiceAccounts::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(ServiceAccounts::UpdatedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(ServiceAccountRoles::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(ServiceAccountRoles::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(
+						ColumnDef::new(ServiceAccountRoles::ServiceAccountId)
+							.integer()
+							.not_null(),
+					)
+					.col(ColumnDef::new(ServiceAccountRoles::RoleId).integer().not_null())
+					.col(
+						ColumnDef::new(ServiceAccountRoles::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_service_account_roles_service_account_id")
+							.from(ServiceAccountRoles::Table, ServiceAccountRoles::ServiceAccountId)
+							.to(ServiceAccounts::Table, ServiceAccounts::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_service_account_roles_role_id")
+							.from(ServiceAccountRoles::Table, ServiceAccountRoles::RoleId)
+							.to(Roles::Table, Roles::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_service_account_role_unique")
+					.table(ServiceAccountRoles::Table)
+					.col(ServiceAccountRoles::ServiceAccountId)
+					.col(ServiceAccountRoles::RoleId)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ServiceAccountRoles::Table).to_owned())
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(ServiceAccounts::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum ServiceAccounts {
+	Table,
+	Id,
+	Name,
+	Description,
+	ClientId,
+	ClientSecretHash,
+	IsActive,
+	CreatedAt,
+	UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ServiceAccountRoles {
+	Table,
+	Id,
+	ServiceAccountId,
+	RoleId,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Roles {
+	Table,
+	Id,
+}
@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(UserIdentities::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(UserIdentities::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(UserIdentities::UserId).integer().not_null())
+					.col(ColumnDef::new(UserIdentities::Provider).string().not_null())
+					.col(ColumnDef::new(UserIdentities::Subject).string().not_null())
+					.col(ColumnDef::new(UserIdentities::Email).string().null())
+					.col(
+						ColumnDef::new(UserIdentities::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(UserIdentities::LastLoginAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_user_identities_user_id")
+							.from(UserIdentities::Table, UserIdentities::UserId)
+							.to(Users::Table, Users::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_user_identities_provider_subject")
+					.table(UserIdentities::Table)
+					.col(UserIdentities::Provider)
+					.col(UserIdentities::Subject)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(UserIdentities::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum UserIdentities {
+	Table,
+	Id,
+	UserId,
+	Provider,
+	Subject,
+	Email,
+	CreatedAt,
+	LastLoginAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	Id,
+}
@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(WebauthnCredentials::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(WebauthnCredentials::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(WebauthnCredentials::UserId).integer().not_null())
+					.col(ColumnDef::new(WebauthnCredentials::Name).string().not_null())
+					.col(
+						ColumnDef::new(WebauthnCredentials::CredentialId)
+							.string()
+							.not_null()
+							.unique_key(),
+					)
+					.col(ColumnDef::new(WebauthnCredentials::PublicKey).binary().not_null())
+					.col(ColumnDef::new(WebauthnCredentials::SignCount).big_integer().not_null())
+					.col(
+						ColumnDef::new(WebauthnCredentials::LastUsedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(WebauthnCredentials::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_webauthn_credentials_user_id")
+							.from(WebauthnCredentials::Table, WebauthnCredentials::UserId)
+							.to(Users::Table, Users::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(WebauthnCredentials::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum WebauthnCredentials {
+	Table,
+	Id,
+	UserId,
+	Name,
+	CredentialId,
+	PublicKey,
+	SignCount,
+	LastUsedAt,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	Id,
+}
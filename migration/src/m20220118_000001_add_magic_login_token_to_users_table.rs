@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.add_column(ColumnDef::new(Users::MagicLoginTokenJti).string().null())
+					.add_column(
+						ColumnDef::new(Users::MagicLoginTokenExpiry)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.drop_column(Users::MagicLoginTokenJti)
+					.drop_column(Users::MagicLoginTokenExpiry)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	MagicLoginTokenJti,
+	MagicLoginTokenExpiry,
+}
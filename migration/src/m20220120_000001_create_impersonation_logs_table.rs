@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(ImpersonationLogs::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(ImpersonationLogs::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(ImpersonationLogs::ImpersonatorId).integer().not_null())
+					.col(ColumnDef::new(ImpersonationLogs::UserId).integer().not_null())
+					.col(ColumnDef::new(ImpersonationLogs::Reason).string().not_null())
+					.col(ColumnDef::new(ImpersonationLogs::Jti).string().not_null().unique_key())
+					.col(ColumnDef::new(ImpersonationLogs::IpAddress).string().null())
+					.col(
+						ColumnDef::new(ImpersonationLogs::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(ImpersonationLogs::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_impersonation_logs_impersonator_id")
+							.from(ImpersonationLogs::Table, ImpersonationLogs::ImpersonatorId)
+							.to(Users::Table, Users::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_impersonation_logs_user_id")
+							.from(ImpersonationLogs::Table, ImpersonationLogs::UserId)
+							.to(Users::Table, Users::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ImpersonationLogs::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum ImpersonationLogs {
+	Table,
+	Id,
+	ImpersonatorId,
+	UserId,
+	Reason,
+	Jti,
+	IpAddress,
+	ExpiresAt,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	Id,
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Permissions::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(Permissions::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(Permissions::Name).string().not_null().unique_key())
+					.col(ColumnDef::new(Permissions::Description).string().null())
+					.col(
+						ColumnDef::new(Permissions::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(RolePermissions::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(RolePermissions::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(RolePermissions::RoleId).integer().not_null())
+					.col(ColumnDef::new(RolePermissions::PermissionId).integer().not_null())
+					.col(
+						ColumnDef::new(RolePermissions::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_role_permissions_role_id")
+							.from(RolePermissions::Table, RolePermissions::RoleId)
+							.to(Roles::Table, Roles::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk_role_permissions_permission_id")
+							.from(RolePermissions::Table, RolePermissions::PermissionId)
+							.to(Permissions::Table, Permissions::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_role_permission_unique")
+					.table(RolePermissions::Table)
+					.col(RolePermissions::RoleId)
+					.col(RolePermissions::PermissionId)
+					.unique()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(RolePermissions::Table).to_owned())
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(Permissions::Table).to_owned())
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Permissions {
+	Table,
+	Id,
+	Name,
+	Description,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum RolePermissions {
+	Table,
+	Id,
+	RoleId,
+	PermissionId,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Roles {
+	Table,
+	Id,
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Roles::Table)
+					.add_column(ColumnDef::new(Roles::ParentId).integer().null())
+					.add_foreign_key(
+						TableForeignKey::new()
+							.name("fk_roles_parent_id")
+							.from_tbl(Roles::Table)
+							.from_col(Roles::ParentId)
+							.to_tbl(Roles::Table)
+							.to_col(Roles::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		// Already seeded databases get the built-in hierarchy: admin > manager > user
+		manager
+			.get_connection()
+			.execute_unprepared(
+				"UPDATE roles SET parent_id = (SELECT id FROM roles WHERE name = 'manager') WHERE name = 'admin'",
+			)
+			.await?;
+
+		manager
+			.get_connection()
+			.execute_unprepared(
+				"UPDATE roles SET parent_id = (SELECT id FROM roles WHERE name = 'user') WHERE name = 'manager'",
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Roles::Table)
+					.drop_foreign_key(Alias::new("fk_roles_parent_id"))
+					.drop_column(Roles::ParentId)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Roles {
+	Table,
+	Id,
+	ParentId,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(UserRoles::Table)
+					.add_column(ColumnDef::new(UserRoles::ValidFrom).timestamp_with_time_zone().null())
+					.add_column(ColumnDef::new(UserRoles::ValidUntil).timestamp_with_time_zone().null())
+					.add_column(ColumnDef::new(UserRoles::GrantedByUserId).integer().null())
+					.add_foreign_key(
+						TableForeignKey::new()
+							.name("fk_user_roles_granted_by_user_id")
+							.from_tbl(UserRoles::Table)
+							.from_col(UserRoles::GrantedByUserId)
+							.to_tbl(Users::Table)
+							.to_col(Users::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_user_roles_valid_until")
+					.table(UserRoles::Table)
+					.col(UserRoles::ValidUntil)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx_user_roles_valid_until")
+					.table(UserRoles::Table)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(UserRoles::Table)
+					.drop_foreign_key(Alias::new("fk_user_roles_granted_by_user_id"))
+					.drop_column(UserRoles::ValidFrom)
+					.drop_column(UserRoles::ValidUntil)
+					.drop_column(UserRoles::GrantedByUserId)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum UserRoles {
+	Table,
+	ValidFrom,
+	ValidUntil,
+	GrantedByUserId,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	Id,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.add_column(ColumnDef::new(Users::MfaTokenJti).string().null())
+					.add_column(ColumnDef::new(Users::MfaFailedAttempts).integer().not_null().default(0))
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Users::Table)
+					.drop_column(Users::MfaTokenJti)
+					.drop_column(Users::MfaFailedAttempts)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum Users {
+	Table,
+	MfaTokenJti,
+	MfaFailedAttempts,
+}
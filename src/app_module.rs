@@ -1,9 +1,11 @@
 use crate::auth::auth_module;
-use crate::auth::middleware::jwt_secret_middleware::jwt_secret_middleware;
 use crate::common::middleware::i18n_middleware::i18n_middleware;
+use crate::common::middleware::rate_limit_middleware::rate_limit_middleware;
 use crate::config::app_config::AppConfig;
 use crate::di::module::AppModule;
 use crate::files::files_module;
+use crate::roles::roles_module;
+use crate::service_accounts::service_accounts_module;
 use crate::users::users_module;
 use axum::{Extension, Router, middleware::from_fn};
 use std::sync::Arc;
@@ -11,13 +13,15 @@ use tower_cookies::CookieManagerLayer;
 use tower_http::trace::TraceLayer;
 
 pub async fn configure(config: Arc<AppConfig>, di_module: Arc<AppModule>) -> Router {
-	let jwt_access_token_secret = config.security.tokens.jwt_access_token.secret.clone();
-
 	Router::new()
 		// Add all modules
 		.merge(users_module::configure())
 		.merge(auth_module::configure())
 		.merge(files_module::configure())
+		.merge(service_accounts_module::configure())
+		.merge(roles_module::configure())
+		// Runs inside the extension layers below, which provide the services it needs
+		.layer(from_fn(rate_limit_middleware))
 		.layer(TraceLayer::new_for_http())
 		.layer(CookieManagerLayer::new())
 		// Add important dependencies and configurations to the app
@@ -30,10 +34,18 @@ pub async fn configure(config: Arc<AppConfig>, di_module: Arc<AppModule>) -> Rou
 		.layer(Extension(di_module.confirmation_token_service.clone()))
 		.layer(Extension(di_module.files_service.clone()))
 		.layer(Extension(di_module.roles_service.clone()))
+		.layer(Extension(di_module.permissions_service.clone()))
+		.layer(Extension(di_module.two_factor_service.clone()))
+		.layer(Extension(di_module.jwt_keys_service.clone()))
+		.layer(Extension(di_module.token_revocation_service.clone()))
+		.layer(Extension(di_module.login_protection_service.clone()))
+		.layer(Extension(di_module.rate_limit_service.clone()))
+		.layer(Extension(di_module.personal_access_token_service.clone()))
+		.layer(Extension(di_module.service_accounts_service.clone()))
+		.layer(Extension(di_module.oidc_service.clone()))
+		.layer(Extension(di_module.webauthn_service.clone()))
+		.layer(Extension(di_module.magic_link_service.clone()))
+		.layer(Extension(di_module.impersonation_service.clone()))
 		.layer(Extension(di_module.db_connection.clone()))
 		.layer(from_fn(i18n_middleware))
-		.layer(from_fn(move |req, next| {
-			let jwt_secret = jwt_access_token_secret.clone();
-			jwt_secret_middleware(jwt_secret, req, next)
-		}))
 }
@@ -1,7 +1,18 @@
 use axum::Router;
 
-use crate::auth::controllers::auth_controller;
+use crate::auth::controllers::{
+	auth_controller, impersonation_controller, jwks_controller, lockouts_controller, oidc_controller,
+	personal_access_tokens_controller, sessions_controller, webauthn_controller,
+};
 
 pub fn configure() -> Router {
-	Router::new().nest("/api/auth", auth_controller::routes())
+	Router::new()
+		.nest("/api/auth/sessions", sessions_controller::routes())
+		.nest("/api/auth/lockouts", lockouts_controller::routes())
+		.nest("/api/auth/impersonation", impersonation_controller::routes())
+		.nest("/api/auth/webauthn", webauthn_controller::routes())
+		.nest("/api/auth/oidc", oidc_controller::routes())
+		.nest("/api/auth/tokens", personal_access_tokens_controller::routes())
+		.nest("/api/auth", auth_controller::routes())
+		.nest("/.well-known", jwks_controller::routes())
 }
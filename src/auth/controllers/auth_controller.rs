@@ -1,22 +1,48 @@
 use crate::auth::dto::change_email_dto::ChangeEmailDto;
 use crate::auth::dto::change_password_dto::ChangePasswordDto;
+use crate::auth::dto::client_credentials_dto::ClientCredentialsDto;
 use crate::auth::dto::forgot_password_dto::ForgotPasswordDto;
 use crate::auth::dto::login_dto::LoginDto;
+use crate::auth::dto::magic_link_dto::MagicLinkDto;
+use crate::auth::dto::magic_link_verify_dto::MagicLinkVerifyDto;
+use crate::auth::dto::reauthenticate_dto::ReauthenticateDto;
+use crate::auth::dto::refresh_token_dto::RefreshTokenDto;
 use crate::auth::dto::register_dto::RegisterDto;
+use crate::auth::dto::resend_confirmation_dto::ResendConfirmationDto;
 use crate::auth::dto::reset_password_dto::ResetPasswordDto;
-use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
-use crate::auth::services::auth_service::{AuthResponse, AuthServiceTrait};
+use crate::auth::dto::two_factor_code_dto::TwoFactorCodeDto;
+use crate::auth::dto::two_factor_login_dto::TwoFactorLoginDto;
+use crate::auth::extractor::jwt_auth_extractor::{DirectUserAuth, UserAuth};
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
+use crate::auth::services::auth_service::{
+	AuthResponse, AuthServiceTrait, LoginResult, RegisterResult, TwoFactorRequiredResponse,
+};
+use crate::auth::services::login_protection_service::LoginProtectionServiceTrait;
+use crate::auth::services::magic_link_service::MagicLinkServiceTrait;
 use crate::auth::services::refresh_token_service::RefreshTokenServiceTrait;
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
+use crate::auth::services::two_factor_service::TwoFactorServiceTrait;
 use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use crate::common::services::rate_limit_service::RateLimitServiceTrait;
 use crate::config::app_config::AppConfig;
 use crate::i18n::setup::translate;
+use crate::service_accounts::services::service_accounts_service::{
+	ClientCredentialsTokenResponse, ServiceAccountsServiceTrait,
+};
 use crate::users::services::users_service::UsersServiceTrait;
-use axum::response::IntoResponse;
+use axum::http::{
+	HeaderMap, StatusCode,
+	header::{AUTHORIZATION, CACHE_CONTROL, REFERRER_POLICY},
+};
+use axum::response::{Html, IntoResponse, Response};
 use axum::{
-	Json, Router,
+	Form, Json, Router,
 	extract::{Extension, Query},
 	routing::{get, post},
 };
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use serde::Deserialize;
 use std::sync::Arc;
 use time::{Duration as TimeDuration, OffsetDateTime};
@@ -33,31 +59,58 @@ struct EmailChangeConfirmationQuery {
 	token: String,
 }
 
+#[derive(Deserialize)]
+struct AccountUnlockQuery {
+	token: String,
+}
+
+#[derive(Deserialize)]
+struct MagicLinkQuery {
+	token: String,
+}
+
 pub fn routes() -> Router {
 	Router::new()
 		.route("/register", post(register))
 		.route("/login", post(login))
+		.route("/login/2fa", post(login_with_two_factor))
+		.route("/reauthenticate", post(reauthenticate))
+		.route("/magic-link", post(request_magic_link))
+		.route("/magic-link/verify", get(confirm_magic_link).post(verify_magic_link))
 		.route("/refresh-token", post(refresh_token))
+		.route("/token", post(issue_client_credentials_token))
 		.route("/logout", post(logout))
 		.route("/logout-all", post(logout_all_devices))
 		.route("/confirm-email", get(confirm_email))
+		.route("/confirm-email/resend", post(resend_confirmation_email))
 		.route("/password/change", post(change_password))
 		.route("/password/reset", post(request_reset_password))
 		.route("/confirm-password-reset", post(confirm_reset_password))
 		.route("/email/change", post(request_email_change))
 		.route("/confirm-email-change", get(confirm_email_change))
+		.route("/unlock-account", get(unlock_account))
+		.route("/2fa/enroll", post(enroll_two_factor))
+		.route("/2fa/confirm", post(confirm_two_factor))
+		.route("/2fa/disable", post(disable_two_factor))
+		.route("/2fa/recovery-codes", post(regenerate_recovery_codes))
 }
 
 async fn register(
 	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
 	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
 	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
 	cookies: Cookies,
 	Json(dto): Json<RegisterDto>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Response, AppError> {
 	dto.validate()?;
 
-	let (user, access_token, refresh_token) = auth_service.register(dto, &refresh_token_service).await?;
+	let (user, access_token, refresh_token) =
+		match auth_service.register(dto, &refresh_token_service, &client_info).await? {
+			RegisterResult::Registered(user, access_token, refresh_token) => (*user, access_token, refresh_token),
+			// Same response whether the account was created or the email was already taken
+			RegisterResult::Pending => return Ok(StatusCode::ACCEPTED.into_response()),
+		};
 
 	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
 	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
@@ -65,19 +118,54 @@ async fn register(
 
 	let response = AuthResponse { user, access_token };
 
-	Ok(Json(response))
+	Ok(Json(response).into_response())
 }
 
 async fn login(
 	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
 	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
 	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
 	cookies: Cookies,
 	Json(dto): Json<LoginDto>,
+) -> Result<Response, AppError> {
+	dto.validate()?;
+
+	let (user, access_token, refresh_token) =
+		match auth_service.login(dto, &refresh_token_service, &client_info).await? {
+			LoginResult::Authenticated(user, access_token, refresh_token) => (*user, access_token, refresh_token),
+			LoginResult::TwoFactorRequired(mfa_token) => {
+				let response = TwoFactorRequiredResponse {
+					two_factor_required: true,
+					mfa_token,
+				};
+
+				return Ok(Json(response).into_response());
+			}
+		};
+
+	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
+	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
+	cookies.add(cookie);
+
+	let response = AuthResponse { user, access_token };
+
+	Ok(Json(response).into_response())
+}
+
+async fn login_with_two_factor(
+	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
+	cookies: Cookies,
+	Json(dto): Json<TwoFactorLoginDto>,
 ) -> Result<impl IntoResponse, AppError> {
 	dto.validate()?;
 
-	let (user, access_token, refresh_token) = auth_service.login(dto, &refresh_token_service).await?;
+	let (user, access_token, refresh_token) = auth_service
+		.login_with_two_factor(dto, &refresh_token_service, &client_info)
+		.await?;
 
 	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
 	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
@@ -88,27 +176,138 @@ async fn login(
 	Ok(Json(response))
 }
 
+async fn reauthenticate(
+	DirectUserAuth(claims): DirectUserAuth,
+	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
+	client_info: ClientInfo,
+	Json(dto): Json<ReauthenticateDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+
+	let (user, access_token) = auth_service.reauthenticate(claims.sub, dto, &client_info).await?;
+	let response = AuthResponse { user, access_token };
+
+	Ok(Json(response))
+}
+
+async fn request_magic_link(
+	Extension(magic_link_service): Extension<Arc<dyn MagicLinkServiceTrait>>,
+	Json(dto): Json<MagicLinkDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+	magic_link_service.request_magic_link(dto).await?;
+	Ok(())
+}
+
+/// Link scanners and previews follow the link from the email, so opening it only shows a form and the token is
+/// consumed by the POST it submits.
+async fn confirm_magic_link(Query(query): Query<MagicLinkQuery>) -> impl IntoResponse {
+	let page = format!(
+		r#"<!DOCTYPE html>
+<html>
+<head><meta charset="UTF-8"><title>{title}</title></head>
+<body>
+<p>{confirm}</p>
+<form method="post">
+<input type="hidden" name="token" value="{token}">
+<button type="submit">{button}</button>
+</form>
+</body>
+</html>"#,
+		title = tera::escape_html(&translate("auth.magic_link.title")),
+		confirm = tera::escape_html(&translate("auth.magic_link.confirm")),
+		button = tera::escape_html(&translate("auth.magic_link.button")),
+		token = tera::escape_html(&query.token),
+	);
+
+	(
+		[(CACHE_CONTROL, "no-store"), (REFERRER_POLICY, "no-referrer")],
+		Html(page),
+	)
+}
+
+async fn verify_magic_link(
+	Extension(magic_link_service): Extension<Arc<dyn MagicLinkServiceTrait>>,
+	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
+	cookies: Cookies,
+	Form(dto): Form<MagicLinkVerifyDto>,
+) -> Result<Response, AppError> {
+	dto.validate()?;
+	let user = magic_link_service.consume_magic_link(&dto.token).await?;
+
+	let (user, access_token, refresh_token) = match auth_service
+		.login_with_user(user, &refresh_token_service, &client_info)
+		.await?
+	{
+		LoginResult::Authenticated(user, access_token, refresh_token) => (*user, access_token, refresh_token),
+		LoginResult::TwoFactorRequired(mfa_token) => {
+			let response = TwoFactorRequiredResponse {
+				two_factor_required: true,
+				mfa_token,
+			};
+
+			return Ok(Json(response).into_response());
+		}
+	};
+
+	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
+	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
+	cookies.add(cookie);
+
+	let response = AuthResponse { user, access_token };
+
+	Ok(Json(response).into_response())
+}
+
 async fn refresh_token(
-	JwtAuth(claims): JwtAuth,
 	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
 	cookies: Cookies,
+	dto: Option<Json<RefreshTokenDto>>,
 ) -> Result<impl IntoResponse, AppError> {
-	let refresh_token = cookies
-		.get("refresh_token")
-		.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.missing_refresh_token")))?
-		.value()
-		.to_string();
+	// Non-browser clients send the refresh token in the body and get the rotated one back the same way
+	let (refresh_token, from_body) = match dto {
+		Some(Json(dto)) => {
+			dto.validate()?;
+			(dto.refresh_token, true)
+		}
+		None => {
+			let refresh_token = cookies
+				.get("refresh_token")
+				.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.missing_refresh_token")))?
+				.value()
+				.to_string();
+			(refresh_token, false)
+		}
+	};
+
+	let (mut response, new_refresh_token) = refresh_token_service.refresh_token(refresh_token, &client_info).await?;
+
+	if from_body {
+		response.refresh_token = Some(new_refresh_token.clone());
+	}
 
-	let response = refresh_token_service.refresh_token(claims.sub, refresh_token).await?;
+	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
+	let cookie = create_refresh_token_cookie(new_refresh_token, jwt_refresh_token_expires_in);
+	cookies.add(cookie);
 
 	Ok(Json(response))
 }
 
 async fn logout(
 	cookies: Cookies,
-	JwtAuth(claims): JwtAuth,
+	UserAuth(claims): UserAuth,
 	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(token_revocation_service): Extension<Arc<dyn TokenRevocationServiceTrait>>,
 ) -> Result<impl IntoResponse, AppError> {
+	token_revocation_service
+		.revoke_access_token(&claims.jti, claims.sub, claims.exp)
+		.await?;
+
 	let refresh_token = cookies.get("refresh_token").map(|cookie| cookie.value().to_string());
 
 	if let Some(token) = refresh_token {
@@ -130,11 +329,13 @@ async fn logout(
 }
 
 async fn logout_all_devices(
-	JwtAuth(claims): JwtAuth,
+	RecentAuth(claims): RecentAuth<300>,
 	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(token_revocation_service): Extension<Arc<dyn TokenRevocationServiceTrait>>,
 	cookies: Cookies,
 ) -> Result<impl IntoResponse, AppError> {
 	refresh_token_service.invalidate_all_user_tokens(claims.sub).await?;
+	token_revocation_service.revoke_all_user_tokens(claims.sub).await?;
 
 	let mut cookie = Cookie::new("refresh_token", "");
 	cookie.set_path("/");
@@ -148,6 +349,39 @@ async fn logout_all_devices(
 	Ok(())
 }
 
+async fn issue_client_credentials_token(
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	headers: HeaderMap,
+	Form(dto): Form<ClientCredentialsDto>,
+) -> Result<Json<ClientCredentialsTokenResponse>, AppError> {
+	if dto.grant_type != "client_credentials" {
+		return Err(AppError::BadRequest(translate("auth.errors.unsupported_grant_type")));
+	}
+
+	// Clients may authenticate with HTTP Basic instead of sending the credentials in the body
+	let basic_credentials = headers
+		.get(AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Basic "))
+		.and_then(|value| STANDARD.decode(value).ok())
+		.and_then(|value| String::from_utf8(value).ok())
+		.and_then(|value| {
+			value
+				.split_once(':')
+				.map(|(client_id, client_secret)| (client_id.to_string(), client_secret.to_string()))
+		});
+
+	let (client_id, client_secret) = basic_credentials
+		.or(dto.client_id.zip(dto.client_secret))
+		.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.invalid_client")))?;
+
+	let response = service_accounts_service
+		.issue_token(&client_id, &client_secret, dto.scope.as_deref())
+		.await?;
+
+	Ok(Json(response))
+}
+
 async fn confirm_email(
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Query(query): Query<EmailConfirmationQuery>,
@@ -156,6 +390,17 @@ async fn confirm_email(
 	Ok(())
 }
 
+async fn resend_confirmation_email(
+	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
+	Extension(rate_limit_service): Extension<Arc<dyn RateLimitServiceTrait>>,
+	Json(dto): Json<ResendConfirmationDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+	rate_limit_service.check_email(&dto.email).await?;
+	users_service.resend_confirmation_email(dto).await?;
+	Ok(())
+}
+
 async fn request_reset_password(
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Json(dto): Json<ForgotPasswordDto>,
@@ -175,7 +420,7 @@ async fn confirm_reset_password(
 }
 
 async fn change_password(
-	JwtAuth(claims): JwtAuth,
+	RecentAuth(claims): RecentAuth<300>,
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Json(dto): Json<ChangePasswordDto>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -185,7 +430,7 @@ async fn change_password(
 }
 
 async fn request_email_change(
-	JwtAuth(claims): JwtAuth,
+	RecentAuth(claims): RecentAuth<300>,
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Json(dto): Json<ChangeEmailDto>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -202,7 +447,55 @@ async fn confirm_email_change(
 	Ok(())
 }
 
-fn create_refresh_token_cookie(token: String, expires_in: i64) -> Cookie<'static> {
+async fn unlock_account(
+	Extension(login_protection_service): Extension<Arc<dyn LoginProtectionServiceTrait>>,
+	Query(query): Query<AccountUnlockQuery>,
+) -> Result<impl IntoResponse, AppError> {
+	login_protection_service.unlock_account(&query.token).await?;
+	Ok(())
+}
+
+async fn enroll_two_factor(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(two_factor_service): Extension<Arc<dyn TwoFactorServiceTrait>>,
+) -> Result<impl IntoResponse, AppError> {
+	let response = two_factor_service.enroll(claims.sub).await?;
+	Ok(Json(response))
+}
+
+async fn confirm_two_factor(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(two_factor_service): Extension<Arc<dyn TwoFactorServiceTrait>>,
+	Json(dto): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+	let response = two_factor_service.confirm_enrollment(claims.sub, &dto.code).await?;
+	Ok(Json(response))
+}
+
+async fn disable_two_factor(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(two_factor_service): Extension<Arc<dyn TwoFactorServiceTrait>>,
+	Json(dto): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+	two_factor_service.disable(claims.sub, &dto.code).await?;
+	Ok(())
+}
+
+async fn regenerate_recovery_codes(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(two_factor_service): Extension<Arc<dyn TwoFactorServiceTrait>>,
+	Json(dto): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+	let response = two_factor_service
+		.regenerate_recovery_codes(claims.sub, &dto.code)
+		.await?;
+	Ok(Json(response))
+}
+
+pub(crate) fn create_refresh_token_cookie(token: String, expires_in: i64) -> Cookie<'static> {
 	let expiration = OffsetDateTime::now_utc() + TimeDuration::seconds(expires_in);
 
 	let mut cookie = Cookie::new("refresh_token", token);
@@ -0,0 +1,45 @@
+use crate::auth::dto::impersonate_dto::ImpersonateDto;
+use crate::auth::entities::impersonation_logs;
+use crate::auth::extractor::jwt_auth_extractor::{DirectUserAuth, JwtAuth};
+use crate::auth::extractor::permission_extractor::{RequirePermission, UsersImpersonate};
+use crate::auth::services::impersonation_service::{ImpersonationResponse, ImpersonationServiceTrait};
+use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use axum::{
+	Json, Router,
+	extract::{Extension, Path},
+	routing::{get, post},
+};
+use std::sync::Arc;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(get_impersonations))
+		.route("/{user_id}", post(impersonate))
+}
+
+async fn get_impersonations(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<UsersImpersonate>,
+	Extension(impersonation_service): Extension<Arc<dyn ImpersonationServiceTrait>>,
+) -> Result<Json<Vec<impersonation_logs::Model>>, AppError> {
+	let impersonations = impersonation_service.find_all().await?;
+	Ok(Json(impersonations))
+}
+
+// The admin is recorded as `impersonator_id`, which has to be a user id
+async fn impersonate(
+	DirectUserAuth(claims): DirectUserAuth,
+	_permission: RequirePermission<UsersImpersonate>,
+	Extension(impersonation_service): Extension<Arc<dyn ImpersonationServiceTrait>>,
+	client_info: ClientInfo,
+	Path(user_id): Path<i32>,
+	Json(dto): Json<ImpersonateDto>,
+) -> Result<Json<ImpersonationResponse>, AppError> {
+	dto.validate()?;
+	let response = impersonation_service
+		.impersonate(&claims, user_id, dto, client_info.ip_address)
+		.await?;
+	Ok(Json(response))
+}
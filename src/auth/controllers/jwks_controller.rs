@@ -0,0 +1,12 @@
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
+use axum::{Json, Router, extract::Extension, routing::get};
+use jsonwebtoken::jwk::JwkSet;
+use std::sync::Arc;
+
+pub fn routes() -> Router {
+	Router::new().route("/jwks.json", get(jwks))
+}
+
+async fn jwks(Extension(jwt_keys_service): Extension<Arc<dyn JwtKeysServiceTrait>>) -> Json<JwkSet> {
+	Json(jwt_keys_service.jwks().clone())
+}
@@ -0,0 +1,35 @@
+use crate::auth::entities::login_lockouts;
+use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
+use crate::auth::extractor::permission_extractor::{LockoutsManage, RequirePermission};
+use crate::auth::services::login_protection_service::LoginProtectionServiceTrait;
+use crate::common::error::app_error::AppError;
+use axum::{
+	Json, Router,
+	extract::{Extension, Path},
+	routing::{delete, get},
+};
+use std::sync::Arc;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(get_lockouts))
+		.route("/{id}", delete(clear_lockout))
+}
+
+async fn get_lockouts(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<LockoutsManage>,
+	Extension(login_protection_service): Extension<Arc<dyn LoginProtectionServiceTrait>>,
+) -> Result<Json<Vec<login_lockouts::Model>>, AppError> {
+	let lockouts = login_protection_service.find_all().await?;
+	Ok(Json(lockouts))
+}
+
+async fn clear_lockout(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<LockoutsManage>,
+	Extension(login_protection_service): Extension<Arc<dyn LoginProtectionServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<(), AppError> {
+	login_protection_service.clear(id).await
+}
@@ -1 +1,8 @@
 pub mod auth_controller;
+pub mod impersonation_controller;
+pub mod jwks_controller;
+pub mod lockouts_controller;
+pub mod oidc_controller;
+pub mod personal_access_tokens_controller;
+pub mod sessions_controller;
+pub mod webauthn_controller;
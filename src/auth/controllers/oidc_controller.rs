@@ -0,0 +1,128 @@
+use crate::auth::controllers::auth_controller::create_refresh_token_cookie;
+use crate::auth::services::auth_service::{AuthResponse, AuthServiceTrait, LoginResult, TwoFactorRequiredResponse};
+use crate::auth::services::oidc_service::OidcServiceTrait;
+use crate::auth::services::refresh_token_service::RefreshTokenServiceTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use crate::config::app_config::AppConfig;
+use crate::i18n::setup::translate;
+use axum::extract::{Path, Query};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::{Extension, Json, Router, routing::get};
+use serde::Deserialize;
+use std::sync::Arc;
+use time::Duration as TimeDuration;
+use tower_cookies::{Cookie, Cookies};
+
+const STATE_COOKIE: &str = "oidc_state";
+const NONCE_COOKIE: &str = "oidc_nonce";
+const CODE_VERIFIER_COOKIE: &str = "oidc_code_verifier";
+const COOKIE_PATH: &str = "/api/auth/oidc";
+const COOKIE_MAX_AGE: i64 = 600;
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+	code: String,
+	state: String,
+}
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/{provider}/authorize", get(authorize))
+		.route("/{provider}/callback", get(callback))
+}
+
+async fn authorize(
+	Extension(oidc_service): Extension<Arc<dyn OidcServiceTrait>>,
+	cookies: Cookies,
+	Path(provider): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+	let authorization_request = oidc_service.authorization_request(&provider).await?;
+
+	cookies.add(create_flow_cookie(STATE_COOKIE, authorization_request.state));
+	cookies.add(create_flow_cookie(NONCE_COOKIE, authorization_request.nonce));
+	cookies.add(create_flow_cookie(
+		CODE_VERIFIER_COOKIE,
+		authorization_request.code_verifier,
+	));
+
+	Ok(Redirect::to(&authorization_request.authorization_url))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn callback(
+	Extension(oidc_service): Extension<Arc<dyn OidcServiceTrait>>,
+	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
+	cookies: Cookies,
+	Path(provider): Path<String>,
+	Query(query): Query<CallbackQuery>,
+) -> Result<Response, AppError> {
+	let state = take_flow_cookie(&cookies, STATE_COOKIE);
+	let nonce = take_flow_cookie(&cookies, NONCE_COOKIE);
+	let code_verifier = take_flow_cookie(&cookies, CODE_VERIFIER_COOKIE);
+
+	let (Some(state), Some(nonce), Some(code_verifier)) = (state, nonce, code_verifier) else {
+		return Err(AppError::AuthenticationError(translate(
+			"auth.errors.invalid_oidc_state",
+		)));
+	};
+
+	if state != query.state {
+		return Err(AppError::AuthenticationError(translate(
+			"auth.errors.invalid_oidc_state",
+		)));
+	}
+
+	let user = oidc_service
+		.authenticate(&provider, &query.code, &code_verifier, &nonce)
+		.await?;
+
+	let (user, access_token, refresh_token) = match auth_service
+		.login_with_user(user, &refresh_token_service, &client_info)
+		.await?
+	{
+		LoginResult::Authenticated(user, access_token, refresh_token) => (*user, access_token, refresh_token),
+		LoginResult::TwoFactorRequired(mfa_token) => {
+			let response = TwoFactorRequiredResponse {
+				two_factor_required: true,
+				mfa_token,
+			};
+
+			return Ok(Json(response).into_response());
+		}
+	};
+
+	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
+	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
+	cookies.add(cookie);
+
+	let response = AuthResponse { user, access_token };
+
+	Ok(Json(response).into_response())
+}
+
+fn create_flow_cookie(name: &'static str, value: String) -> Cookie<'static> {
+	let mut cookie = Cookie::new(name, value);
+	cookie.set_path(COOKIE_PATH);
+	cookie.set_secure(true);
+	cookie.set_http_only(true);
+	// Lax, so the cookies are sent on the redirect back from the provider
+	cookie.set_same_site(tower_cookies::cookie::SameSite::Lax);
+	cookie.set_max_age(TimeDuration::seconds(COOKIE_MAX_AGE));
+
+	cookie
+}
+
+/// Reads a cookie of the authorization flow and removes it, so the values can only be used once.
+fn take_flow_cookie(cookies: &Cookies, name: &'static str) -> Option<String> {
+	let value = cookies.get(name).map(|cookie| cookie.value().to_string());
+
+	let mut removal = Cookie::from(name);
+	removal.set_path(COOKIE_PATH);
+	cookies.remove(removal);
+
+	value
+}
@@ -0,0 +1,46 @@
+use crate::auth::dto::create_personal_access_token_dto::CreatePersonalAccessTokenDto;
+use crate::auth::extractor::jwt_auth_extractor::UserAuth;
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
+use crate::auth::services::personal_access_token_service::{
+	CreatedPersonalAccessTokenResponse, PersonalAccessTokenResponse, PersonalAccessTokenServiceTrait,
+};
+use crate::common::error::app_error::AppError;
+use axum::{
+	Json, Router,
+	extract::{Extension, Path},
+	routing::{delete, get},
+};
+use std::sync::Arc;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(list_tokens).post(create_token))
+		.route("/{id}", delete(revoke_token))
+}
+
+async fn list_tokens(
+	UserAuth(claims): UserAuth,
+	Extension(personal_access_token_service): Extension<Arc<dyn PersonalAccessTokenServiceTrait>>,
+) -> Result<Json<Vec<PersonalAccessTokenResponse>>, AppError> {
+	let tokens = personal_access_token_service.find_all(claims.sub).await?;
+	Ok(Json(tokens))
+}
+
+async fn create_token(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(personal_access_token_service): Extension<Arc<dyn PersonalAccessTokenServiceTrait>>,
+	Json(dto): Json<CreatePersonalAccessTokenDto>,
+) -> Result<Json<CreatedPersonalAccessTokenResponse>, AppError> {
+	dto.validate()?;
+	let token = personal_access_token_service.create(&claims, dto).await?;
+	Ok(Json(token))
+}
+
+async fn revoke_token(
+	UserAuth(claims): UserAuth,
+	Extension(personal_access_token_service): Extension<Arc<dyn PersonalAccessTokenServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<(), AppError> {
+	personal_access_token_service.revoke(claims.sub, id).await
+}
@@ -0,0 +1,51 @@
+use crate::auth::dto::update_session_dto::UpdateSessionDto;
+use crate::auth::extractor::jwt_auth_extractor::UserAuth;
+use crate::auth::services::refresh_token_service::{RefreshTokenServiceTrait, SessionResponse};
+use crate::common::error::app_error::AppError;
+use axum::{
+	Json, Router,
+	extract::{Extension, Path},
+	routing::{get, patch},
+};
+use std::sync::Arc;
+use tower_cookies::Cookies;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(list_sessions))
+		.route("/{id}", patch(rename_session).delete(revoke_session))
+}
+
+async fn list_sessions(
+	UserAuth(claims): UserAuth,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	cookies: Cookies,
+) -> Result<Json<Vec<SessionResponse>>, AppError> {
+	// The refresh token cookie identifies the session making the request
+	let current_refresh_token = cookies.get("refresh_token").map(|cookie| cookie.value().to_string());
+
+	let sessions = refresh_token_service
+		.list_sessions(claims.sub, current_refresh_token)
+		.await?;
+
+	Ok(Json(sessions))
+}
+
+async fn rename_session(
+	UserAuth(claims): UserAuth,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Path(id): Path<String>,
+	Json(dto): Json<UpdateSessionDto>,
+) -> Result<(), AppError> {
+	dto.validate()?;
+	refresh_token_service.rename_session(claims.sub, &id, &dto.label).await
+}
+
+async fn revoke_session(
+	UserAuth(claims): UserAuth,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Path(id): Path<String>,
+) -> Result<(), AppError> {
+	refresh_token_service.revoke_session(claims.sub, &id).await
+}
@@ -0,0 +1,103 @@
+use crate::auth::controllers::auth_controller::create_refresh_token_cookie;
+use crate::auth::dto::webauthn_login_dto::WebauthnLoginDto;
+use crate::auth::dto::webauthn_login_start_dto::WebauthnLoginStartDto;
+use crate::auth::dto::webauthn_registration_dto::WebauthnRegistrationDto;
+use crate::auth::entities::webauthn_credentials::Model as WebauthnCredentialModel;
+use crate::auth::extractor::jwt_auth_extractor::UserAuth;
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
+use crate::auth::services::auth_service::{AuthResponse, AuthServiceTrait};
+use crate::auth::services::refresh_token_service::RefreshTokenServiceTrait;
+use crate::auth::services::webauthn_service::{
+	PublicKeyCredentialCreationOptions, PublicKeyCredentialRequestOptions, WebauthnServiceTrait,
+};
+use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use crate::config::app_config::AppConfig;
+use axum::{
+	Json, Router,
+	extract::{Extension, Path},
+	response::IntoResponse,
+	routing::{delete, get, post},
+};
+use std::sync::Arc;
+use tower_cookies::Cookies;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/register/start", post(start_registration))
+		.route("/register/finish", post(finish_registration))
+		.route("/login/start", post(start_login))
+		.route("/login/finish", post(finish_login))
+		.route("/credentials", get(list_credentials))
+		.route("/credentials/{id}", delete(delete_credential))
+}
+
+async fn start_registration(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+) -> Result<Json<PublicKeyCredentialCreationOptions>, AppError> {
+	let options = webauthn_service.start_registration(claims.sub).await?;
+	Ok(Json(options))
+}
+
+async fn finish_registration(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+	Json(dto): Json<WebauthnRegistrationDto>,
+) -> Result<Json<WebauthnCredentialModel>, AppError> {
+	dto.validate()?;
+	let credential = webauthn_service.finish_registration(claims.sub, dto).await?;
+	Ok(Json(credential))
+}
+
+async fn start_login(
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+	Json(dto): Json<WebauthnLoginStartDto>,
+) -> Result<Json<PublicKeyCredentialRequestOptions>, AppError> {
+	dto.validate()?;
+	let options = webauthn_service.start_authentication(dto).await?;
+	Ok(Json(options))
+}
+
+async fn finish_login(
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+	Extension(auth_service): Extension<Arc<dyn AuthServiceTrait>>,
+	Extension(refresh_token_service): Extension<Arc<dyn RefreshTokenServiceTrait>>,
+	Extension(config): Extension<Arc<AppConfig>>,
+	client_info: ClientInfo,
+	cookies: Cookies,
+	Json(dto): Json<WebauthnLoginDto>,
+) -> Result<impl IntoResponse, AppError> {
+	dto.validate()?;
+
+	// A passkey with user verification is a second factor on its own, so TOTP is not asked for
+	let user = webauthn_service.finish_authentication(dto).await?;
+	let (user, access_token, refresh_token) = auth_service
+		.issue_tokens(user, &refresh_token_service, &client_info)
+		.await?;
+
+	let jwt_refresh_token_expires_in = config.security.tokens.jwt_refresh_token.expires_in;
+	let cookie = create_refresh_token_cookie(refresh_token, jwt_refresh_token_expires_in);
+	cookies.add(cookie);
+
+	let response = AuthResponse { user, access_token };
+
+	Ok(Json(response))
+}
+
+async fn list_credentials(
+	UserAuth(claims): UserAuth,
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+) -> Result<Json<Vec<WebauthnCredentialModel>>, AppError> {
+	let credentials = webauthn_service.find_all(claims.sub).await?;
+	Ok(Json(credentials))
+}
+
+async fn delete_credential(
+	RecentAuth(claims): RecentAuth<300>,
+	Extension(webauthn_service): Extension<Arc<dyn WebauthnServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<(), AppError> {
+	webauthn_service.delete(id, claims.sub).await
+}
@@ -4,4 +4,7 @@ use validator::Validate;
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct AccessTokenDto {
 	pub access_token: String,
+	pub roles: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub refresh_token: Option<String>,
 }
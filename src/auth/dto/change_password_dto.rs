@@ -3,9 +3,10 @@ use validator::Validate;
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct ChangePasswordDto {
-	#[validate(length(min = 8))]
+	#[validate(length(min = 1))]
 	pub current_password: String,
 
-	#[validate(length(min = 8))]
+	// Checked against the password policy by the service
+	#[validate(length(min = 1))]
 	pub new_password: String,
 }
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Form body of the OAuth2 token request. The client credentials may be sent here or with HTTP Basic
+/// authentication.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClientCredentialsDto {
+	pub grant_type: String,
+	pub client_id: Option<String>,
+	pub client_secret: Option<String>,
+	pub scope: Option<String>,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CreatePersonalAccessTokenDto {
+	#[validate(length(min = 1, max = 100))]
+	pub name: String,
+	#[validate(length(min = 1))]
+	/// Role names the token is limited to
+	pub scopes: Vec<String>,
+	/// Lifetime in days, the token never expires when omitted
+	#[validate(range(min = 1, max = 365))]
+	pub expires_in_days: Option<i64>,
+}
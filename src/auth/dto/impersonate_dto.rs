@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ImpersonateDto {
+	/// Why the account is being accessed, kept in the audit trail
+	#[validate(length(min = 1, max = 500))]
+	pub reason: String,
+}
@@ -1,8 +1,21 @@
 pub mod access_token_dto;
 pub mod change_email_dto;
 pub mod change_password_dto;
+pub mod client_credentials_dto;
+pub mod create_personal_access_token_dto;
 pub mod forgot_password_dto;
+pub mod impersonate_dto;
 pub mod login_dto;
+pub mod magic_link_dto;
+pub mod magic_link_verify_dto;
+pub mod reauthenticate_dto;
 pub mod refresh_token_dto;
 pub mod register_dto;
+pub mod resend_confirmation_dto;
 pub mod reset_password_dto;
+pub mod two_factor_code_dto;
+pub mod two_factor_login_dto;
+pub mod update_session_dto;
+pub mod webauthn_login_dto;
+pub mod webauthn_login_start_dto;
+pub mod webauthn_registration_dto;
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ReauthenticateDto {
+	#[validate(length(min = 1))]
+	pub password: String,
+
+	/// Required when two-factor authentication is enabled
+	#[validate(length(min = 1))]
+	pub code: Option<String>,
+}
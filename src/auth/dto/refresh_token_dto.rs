@@ -3,5 +3,6 @@ use validator::Validate;
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct RefreshTokenDto {
+	#[validate(length(min = 1))]
 	pub refresh_token: String,
 }
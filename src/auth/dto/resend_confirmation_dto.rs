@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ResendConfirmationDto {
+	#[validate(email)]
+	pub email: String,
+}
@@ -5,6 +5,7 @@ use validator::Validate;
 pub struct ResetPasswordDto {
 	pub token: String,
 
-	#[validate(length(min = 8))]
+	// Checked against the password policy by the service
+	#[validate(length(min = 1))]
 	pub password: String,
 }
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct TwoFactorCodeDto {
+	#[validate(length(min = 1))]
+	pub code: String,
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct TwoFactorLoginDto {
+	#[validate(length(min = 1))]
+	pub mfa_token: String,
+
+	#[validate(length(min = 1))]
+	pub code: String,
+}
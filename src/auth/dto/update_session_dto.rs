@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateSessionDto {
+	#[validate(length(min = 1, max = 100))]
+	pub label: String,
+}
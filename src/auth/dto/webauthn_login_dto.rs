@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// `PublicKeyCredential` returned by `navigator.credentials.get()`, serialized with `toJSON()`.
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct WebauthnLoginDto {
+	#[validate(length(min = 1))]
+	pub id: String,
+	pub response: WebauthnAssertionResponseDto,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebauthnAssertionResponseDto {
+	#[serde(rename = "clientDataJSON")]
+	pub client_data_json: String,
+	pub authenticator_data: String,
+	pub signature: String,
+	pub user_handle: Option<String>,
+}
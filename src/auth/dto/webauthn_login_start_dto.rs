@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct WebauthnLoginStartDto {
+	/// Restricts the allowed credentials to this user, discoverable credentials are used when omitted
+	#[validate(length(min = 1))]
+	pub username: Option<String>,
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct WebauthnRegistrationDto {
+	#[validate(length(min = 1, max = 100))]
+	pub name: String,
+	pub credential: WebauthnAttestationCredentialDto,
+}
+
+/// `PublicKeyCredential` returned by `navigator.credentials.create()`, serialized with `toJSON()`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WebauthnAttestationCredentialDto {
+	pub id: String,
+	pub response: WebauthnAttestationResponseDto,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebauthnAttestationResponseDto {
+	#[serde(rename = "clientDataJSON")]
+	pub client_data_json: String,
+	pub attestation_object: String,
+}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "impersonation_logs")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub impersonator_id: i32,
+	pub user_id: i32,
+	pub reason: String,
+	pub jti: String,
+	pub ip_address: Option<String>,
+	pub expires_at: DateTimeWithTimeZone,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "crate::users::entities::users::Entity",
+		from = "Column::ImpersonatorId",
+		to = "crate::users::entities::users::Column::Id"
+	)]
+	Impersonator,
+	#[sea_orm(
+		belongs_to = "crate::users::entities::users::Entity",
+		from = "Column::UserId",
+		to = "crate::users::entities::users::Column::Id"
+	)]
+	User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
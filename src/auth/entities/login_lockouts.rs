@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_lockouts")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub scope: String,
+	pub identifier: String,
+	pub failed_attempts: i32,
+	pub window_started_at: DateTimeWithTimeZone,
+	pub last_failed_at: Option<DateTimeWithTimeZone>,
+	pub locked_until: Option<DateTimeWithTimeZone>,
+	pub created_at: DateTimeWithTimeZone,
+	pub updated_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1 +1,7 @@
+pub mod impersonation_logs;
+pub mod login_lockouts;
+pub mod personal_access_tokens;
 pub mod refresh_tokens;
+pub mod revoked_access_tokens;
+pub mod user_identities;
+pub mod webauthn_credentials;
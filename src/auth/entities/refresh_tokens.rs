@@ -6,11 +6,19 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
 	#[sea_orm(primary_key)]
 	pub id: i32,
-	pub token: String,
+	#[sea_orm(unique)]
+	pub token_hash: String,
 	pub expires_at: DateTimeWithTimeZone,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: Option<DateTimeWithTimeZone>,
 	pub user_id: i32,
+	pub family_id: String,
+	pub revoked_at: Option<DateTimeWithTimeZone>,
+	pub replaced_by_token_id: Option<i32>,
+	pub user_agent: Option<String>,
+	pub ip_address: Option<String>,
+	pub label: Option<String>,
+	pub last_used_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "revoked_access_tokens")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	#[sea_orm(unique)]
+	pub jti: String,
+	pub user_id: i32,
+	pub expires_at: DateTimeWithTimeZone,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "crate::users::entities::users::Entity",
+		from = "Column::UserId",
+		to = "crate::users::entities::users::Column::Id"
+	)]
+	User,
+}
+
+impl Related<crate::users::entities::users::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::User.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
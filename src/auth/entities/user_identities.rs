@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_identities")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub user_id: i32,
+	pub provider: String,
+	pub subject: String,
+	pub email: Option<String>,
+	pub created_at: DateTimeWithTimeZone,
+	pub last_login_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "crate::users::entities::users::Entity",
+		from = "Column::UserId",
+		to = "crate::users::entities::users::Column::Id"
+	)]
+	User,
+}
+
+impl Related<crate::users::entities::users::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::User.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webauthn_credentials")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub user_id: i32,
+	pub name: String,
+	#[sea_orm(unique)]
+	pub credential_id: String,
+	#[serde(skip_serializing)]
+	pub public_key: Vec<u8>,
+	#[serde(skip_serializing)]
+	pub sign_count: i64,
+	pub last_used_at: Option<DateTimeWithTimeZone>,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "crate::users::entities::users::Entity",
+		from = "Column::UserId",
+		to = "crate::users::entities::users::Column::Id"
+	)]
+	User,
+}
+
+impl Related<crate::users::entities::users::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::User.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,82 +1,194 @@
-use crate::auth::services::auth_service::Claims;
+use crate::auth::services::auth_service::{Actor, Claims};
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
+use crate::auth::services::personal_access_token_service::{
+	PERSONAL_ACCESS_TOKEN_PREFIX, PersonalAccessTokenServiceTrait,
+};
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
 use crate::common::error::app_error::AppError;
 use crate::di::DatabaseConnectionTrait;
 use crate::i18n::setup::translate;
+use crate::service_accounts::entities::service_accounts::{self, Entity as ServiceAccount};
 use crate::users::entities::users::Column;
 use crate::users::entities::users::Entity as User;
 use axum::{extract::FromRequestParts, http::request::Parts};
-use jsonwebtoken::{DecodingKey, Validation, decode};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use std::future::Future;
 use std::sync::Arc;
 
-#[derive(Clone)]
-pub struct JwtSecret(pub String);
-
 pub struct JwtAuth(pub Claims);
 
+impl JwtAuth {
+	/// The admin acting as the user, when the token was issued through impersonation.
+	pub fn impersonator(&self) -> Option<&Actor> {
+		self.0.act.as_ref()
+	}
+}
+
 impl<S> FromRequestParts<S> for JwtAuth
 where
 	S: Send + Sync,
 {
 	type Rejection = AppError;
 
-	fn from_request_parts(parts: &mut Parts, _state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
-		async move {
-			// Get JWT secret from extensions (added by middleware)
-			let jwt_secret = parts
-				.extensions
-				.get::<JwtSecret>()
-				.ok_or_else(|| AppError::InternalError)?;
-
-			let token = parts
-				.headers
-				.get("Authorization")
-				.and_then(|auth_header| auth_header.to_str().ok())
-				.and_then(|auth_value| {
-					if auth_value.starts_with("Bearer ") {
-						Some(auth_value[7..].to_owned())
-					} else {
-						None
-					}
-				})
-				.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.missing_token")))?;
-
-			let token_data = decode::<Claims>(
-				&token,
-				&DecodingKey::from_secret(jwt_secret.0.as_bytes()),
-				&Validation::default(),
-			)
-			.map_err(|_| AppError::AuthenticationError(translate("auth.errors.invalid_token")))?;
-
-			let claims = token_data.claims;
-
-			// Get database connection from request extensions
-			let db = parts
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		// Get JWT keys from request extensions
+		let jwt_keys_service = parts
+			.extensions
+			.get::<Arc<dyn JwtKeysServiceTrait>>()
+			.ok_or(AppError::InternalError)?
+			.clone();
+
+		let token = parts
+			.headers
+			.get("Authorization")
+			.and_then(|auth_header| auth_header.to_str().ok())
+			.and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+			.map(|token| token.to_owned())
+			.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.missing_token")))?;
+
+		// Personal access tokens are opaque, their claims are resolved from the database
+		if token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX) {
+			let personal_access_token_service = parts
 				.extensions
-				.get::<Arc<dyn DatabaseConnectionTrait>>()
-				.ok_or_else(|| AppError::InternalError)?
-				.get_connection();
+				.get::<Arc<dyn PersonalAccessTokenServiceTrait>>()
+				.ok_or(AppError::InternalError)?;
+
+			let claims = personal_access_token_service.authenticate(&token).await?;
+			return Ok(JwtAuth(claims));
+		}
+
+		let claims = jwt_keys_service.decode(&token)?;
+
+		let token_revocation_service = parts
+			.extensions
+			.get::<Arc<dyn TokenRevocationServiceTrait>>()
+			.ok_or(AppError::InternalError)?;
+
+		if token_revocation_service.is_revoked(&claims.jti).await? {
+			return Err(AppError::AuthenticationError(translate("auth.errors.token_revoked")));
+		}
 
-			// Verify user is active and email confirmed
-			let user = User::find()
-				.filter(Column::Id.eq(claims.sub))
+		// Get database connection from request extensions
+		let db = parts
+			.extensions
+			.get::<Arc<dyn DatabaseConnectionTrait>>()
+			.ok_or(AppError::InternalError)?
+			.get_connection();
+
+		// Service accounts have no user row, it is enough that the account is still active
+		if let Some(client_id) = &claims.client_id {
+			let service_account = ServiceAccount::find()
+				.filter(service_accounts::Column::ClientId.eq(client_id))
 				.one(db)
 				.await
-				.map_err(|_| AppError::AuthenticationError(translate("auth.errors.invalid_user")))?
-				.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.user_not_found")))?;
+				.map_err(|_| AppError::AuthenticationError(translate("auth.errors.invalid_client")))?
+				.filter(|service_account| service_account.is_active)
+				.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.invalid_client")))?;
 
-			if !user.is_active {
-				return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+			// Tokens issued before the secret was rotated are no longer accepted
+			if service_account
+				.tokens_valid_after
+				.is_some_and(|valid_after| claims.iat <= valid_after.timestamp())
+			{
+				return Err(AppError::AuthenticationError(translate("auth.errors.token_revoked")));
 			}
 
-			if !user.is_email_confirmed {
-				return Err(AppError::AuthenticationError(translate(
-					"auth.errors.email_not_confirmed",
-				)));
-			}
+			return Ok(JwtAuth(claims));
+		}
+
+		// Verify user is active and email confirmed
+		let user = User::find()
+			.filter(Column::Id.eq(claims.sub))
+			.one(db)
+			.await
+			.map_err(|_| AppError::AuthenticationError(translate("auth.errors.invalid_user")))?
+			.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.user_not_found")))?;
 
-			Ok(JwtAuth(claims))
+		// Tokens issued before e.g. a password change, "log out everywhere" or a role change are no longer
+		// accepted. `iat` has whole seconds, so a token from the same second as the revocation is rejected as well
+		if [user.tokens_valid_after, user.access_tokens_valid_after]
+			.into_iter()
+			.flatten()
+			.any(|valid_after| claims.iat <= valid_after.timestamp())
+		{
+			return Err(AppError::AuthenticationError(translate("auth.errors.token_revoked")));
 		}
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		if !user.is_email_confirmed {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.email_not_confirmed",
+			)));
+		}
+
+		let auth = JwtAuth(claims);
+
+		// Requests made with an impersonation token are attributed to the admin behind it
+		if let Some(actor) = auth.impersonator() {
+			tracing::info!(
+				"User {} acting as user {} (token {}): {} {}",
+				actor.sub,
+				auth.0.sub,
+				auth.0.jti,
+				parts.method,
+				parts.uri.path()
+			);
+		}
+
+		Ok(auth)
+	}
+}
+
+/// Like `JwtAuth`, but rejects service accounts. Used by endpoints acting on the caller's own user
+/// account, where `sub` must be a user id.
+pub struct UserAuth(pub Claims);
+
+impl<S> FromRequestParts<S> for UserAuth
+where
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let JwtAuth(claims) = JwtAuth::from_request_parts(parts, state).await?;
+
+		if claims.is_service_account() {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.user_account_required",
+			)));
+		}
+
+		Ok(UserAuth(claims))
+	}
+}
+
+/// Like `UserAuth`, but also rejects impersonation tokens and personal access tokens. Used by endpoints
+/// changing credentials or other security settings, which only the account owner may do.
+pub struct DirectUserAuth(pub Claims);
+
+impl<S> FromRequestParts<S> for DirectUserAuth
+where
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let UserAuth(claims) = UserAuth::from_request_parts(parts, state).await?;
+
+		if claims.is_impersonated() {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.impersonation_forbidden",
+			)));
+		}
+
+		if claims.is_personal_access_token() {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.personal_access_token_forbidden",
+			)));
+		}
+
+		Ok(DirectUserAuth(claims))
 	}
 }
@@ -1,2 +1,4 @@
 pub mod jwt_auth_extractor;
+pub mod permission_extractor;
+pub mod recent_auth_extractor;
 pub mod role_extractor;
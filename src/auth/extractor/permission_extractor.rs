@@ -0,0 +1,64 @@
+use crate::auth::extractor::role_extractor::RoleGuard;
+use crate::common::enums::permission_enum::PermissionEnum;
+use crate::common::error::app_error::AppError;
+use crate::i18n::setup::translate;
+use crate::roles::services::permissions_service::PermissionsServiceTrait;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Names a permission at the type level, for use with `RequirePermission`.
+pub trait Permission: Send + Sync {
+	const PERMISSION: PermissionEnum;
+}
+
+macro_rules! permissions {
+	($($name:ident),* $(,)?) => {
+		$(
+			pub struct $name;
+
+			impl Permission for $name {
+				const PERMISSION: PermissionEnum = PermissionEnum::$name;
+			}
+		)*
+	};
+}
+
+permissions!(
+	UsersWrite,
+	UsersDelete,
+	UsersImpersonate,
+	RolesManage,
+	FilesWrite,
+	FilesDelete,
+	ServiceAccountsManage,
+	LockoutsManage,
+);
+
+/// Rejects the request unless one of the caller's effective roles grants the permission `P`.
+pub struct RequirePermission<P: Permission>(PhantomData<P>);
+
+impl<S, P> FromRequestParts<S> for RequirePermission<P>
+where
+	S: Send + Sync,
+	P: Permission,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let RoleGuard(roles) = RoleGuard::from_request_parts(parts, state).await?;
+
+		let permissions_service = parts
+			.extensions
+			.get::<Arc<dyn PermissionsServiceTrait>>()
+			.ok_or(AppError::InternalError)?;
+
+		if !permissions_service.has_permission(&roles, P::PERMISSION).await? {
+			return Err(AppError::AuthorizationError(
+				translate("auth.errors.permission_required").replace("%{permission}", P::PERMISSION.as_str()),
+			));
+		}
+
+		Ok(RequirePermission(PhantomData))
+	}
+}
@@ -0,0 +1,31 @@
+use crate::auth::extractor::jwt_auth_extractor::DirectUserAuth;
+use crate::auth::services::auth_service::Claims;
+use crate::common::error::app_error::AppError;
+use crate::i18n::setup::translate;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::Utc;
+
+/// Like `DirectUserAuth`, but also requires the user to have entered their credentials within the
+/// last `MAX_AGE` seconds. Tokens from a refresh carry no `auth_time`, so a fresh one is obtained
+/// from `/api/auth/reauthenticate`.
+pub struct RecentAuth<const MAX_AGE: i64>(pub Claims);
+
+impl<S, const MAX_AGE: i64> FromRequestParts<S> for RecentAuth<MAX_AGE>
+where
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let DirectUserAuth(claims) = DirectUserAuth::from_request_parts(parts, state).await?;
+
+		let now = Utc::now().timestamp();
+		if claims.auth_time.is_none_or(|auth_time| now - auth_time > MAX_AGE) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.reauthentication_required",
+			)));
+		}
+
+		Ok(RecentAuth(claims))
+	}
+}
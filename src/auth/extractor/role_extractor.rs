@@ -2,20 +2,63 @@ use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
 use crate::common::enums::role_enum::RoleEnum;
 use crate::common::error::app_error::AppError;
 use crate::i18n::setup::translate;
+use crate::roles::services::user_roles_service::UserRolesServiceTrait;
 use axum::{extract::FromRequestParts, http::request::Parts};
-use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The caller's effective roles: the roles in the token and every role they inherit.
+pub struct RoleGuard(pub Vec<String>);
+
+impl RoleGuard {
+	pub fn has_role(&self, role_name: &str) -> bool {
+		self.0.iter().any(|role| role == role_name)
+	}
+}
 
-pub struct RoleGuard(pub Vec<RoleEnum>);
 impl<S> FromRequestParts<S> for RoleGuard
 where
 	S: Send + Sync,
 {
 	type Rejection = AppError;
-	fn from_request_parts(parts: &mut Parts, state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
-		async move {
-			let JwtAuth(claims) = JwtAuth::from_request_parts(parts, state).await?;
-			Ok(RoleGuard(claims.roles))
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let JwtAuth(claims) = JwtAuth::from_request_parts(parts, state).await?;
+
+		let user_roles_service = parts
+			.extensions
+			.get::<Arc<dyn UserRolesServiceTrait>>()
+			.ok_or(AppError::InternalError)?;
+
+		let effective_roles = user_roles_service.resolve_effective_role_names(&claims.roles).await?;
+		Ok(RoleGuard(effective_roles))
+	}
+}
+
+/// Names a role at the type level, for use with `RequireRole`. Roles created through the roles API
+/// can be required by declaring a marker for them, e.g. `struct Auditor;` with `NAME = "auditor"`.
+pub trait Role: Send + Sync {
+	const NAME: &'static str;
+}
+
+/// Rejects the request unless the caller holds the role `R`.
+pub struct RequireRole<R: Role>(PhantomData<R>);
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+	S: Send + Sync,
+	R: Role,
+{
+	type Rejection = AppError;
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let role_guard = RoleGuard::from_request_parts(parts, state).await?;
+
+		if !role_guard.has_role(R::NAME) {
+			return Err(AppError::AuthorizationError(
+				translate("auth.errors.role_required").replace("%{role}", R::NAME),
+			));
 		}
+
+		Ok(RequireRole(PhantomData))
 	}
 }
 
@@ -25,18 +68,16 @@ where
 	S: Send + Sync,
 {
 	type Rejection = AppError;
-	fn from_request_parts(parts: &mut Parts, state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
-		async move {
-			let RoleGuard(roles) = RoleGuard::from_request_parts(parts, state).await?;
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let role_guard = RoleGuard::from_request_parts(parts, state).await?;
 
-			if !roles.contains(&RoleEnum::Admin) {
-				return Err(AppError::AuthorizationError(translate(
-					"auth.errors.admin_role_required",
-				)));
-			}
-
-			Ok(AdminRole)
+		if !role_guard.has_role(RoleEnum::Admin.as_str()) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.admin_role_required",
+			)));
 		}
+
+		Ok(AdminRole)
 	}
 }
 
@@ -46,18 +87,16 @@ where
 	S: Send + Sync,
 {
 	type Rejection = AppError;
-	fn from_request_parts(parts: &mut Parts, state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
-		async move {
-			let RoleGuard(roles) = RoleGuard::from_request_parts(parts, state).await?;
-
-			if !roles.contains(&RoleEnum::Manager) {
-				return Err(AppError::AuthorizationError(translate(
-					"auth.errors.manager_role_required",
-				)));
-			}
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let role_guard = RoleGuard::from_request_parts(parts, state).await?;
 
-			Ok(ManagerRole)
+		if !role_guard.has_role(RoleEnum::Manager.as_str()) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.manager_role_required",
+			)));
 		}
+
+		Ok(ManagerRole)
 	}
 }
 
@@ -67,17 +106,15 @@ where
 	S: Send + Sync,
 {
 	type Rejection = AppError;
-	fn from_request_parts(parts: &mut Parts, state: &S) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
-		async move {
-			let RoleGuard(roles) = RoleGuard::from_request_parts(parts, state).await?;
-
-			if !roles.contains(&RoleEnum::User) {
-				return Err(AppError::AuthorizationError(translate(
-					"auth.errors.user_role_required",
-				)));
-			}
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let role_guard = RoleGuard::from_request_parts(parts, state).await?;
 
-			Ok(UserRole)
+		if !role_guard.has_role(RoleEnum::User.as_str()) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.user_role_required",
+			)));
 		}
+
+		Ok(UserRole)
 	}
 }
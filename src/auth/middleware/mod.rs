@@ -1 +0,0 @@
-pub mod jwt_secret_middleware;
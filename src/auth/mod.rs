@@ -3,6 +3,5 @@ pub mod controllers;
 pub mod dto;
 pub mod entities;
 pub mod extractor;
-pub mod middleware;
 pub mod repositories;
 pub mod services;
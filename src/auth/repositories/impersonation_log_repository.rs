@@ -0,0 +1,76 @@
+use crate::auth::entities::impersonation_logs::{
+	self, ActiveModel as ImpersonationLogActiveModel, Entity as ImpersonationLog, Model as ImpersonationLogModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, QueryOrder, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ImpersonationLogRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl ImpersonationLogRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait ImpersonationLogRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn create(
+		&self,
+		impersonator_id: i32,
+		user_id: i32,
+		reason: String,
+		jti: &str,
+		ip_address: Option<String>,
+		expires_at: DateTime<Utc>,
+	) -> Result<ImpersonationLogModel, AppError>;
+	async fn find_all(&self) -> Result<Vec<ImpersonationLogModel>, AppError>;
+}
+
+#[async_trait]
+impl ImpersonationLogRepositoryTrait for ImpersonationLogRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn create(
+		&self,
+		impersonator_id: i32,
+		user_id: i32,
+		reason: String,
+		jti: &str,
+		ip_address: Option<String>,
+		expires_at: DateTime<Utc>,
+	) -> Result<ImpersonationLogModel, AppError> {
+		let impersonation_log = ImpersonationLogActiveModel {
+			id: ActiveValue::NotSet,
+			impersonator_id: Set(impersonator_id),
+			user_id: Set(user_id),
+			reason: Set(reason),
+			jti: Set(jti.to_string()),
+			ip_address: Set(ip_address),
+			expires_at: Set(expires_at.into()),
+			created_at: Set(Utc::now().into()),
+		};
+
+		impersonation_log
+			.insert(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_all(&self) -> Result<Vec<ImpersonationLogModel>, AppError> {
+		ImpersonationLog::find()
+			.order_by_desc(impersonation_logs::Column::CreatedAt)
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+}
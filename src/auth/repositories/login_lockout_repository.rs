@@ -0,0 +1,190 @@
+use crate::auth::entities::login_lockouts::{
+	self, ActiveModel as LoginLockoutActiveModel, Entity as LoginLockout, Model as LoginLockoutModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct LoginLockoutRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl LoginLockoutRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait LoginLockoutRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn find_all(&self) -> Result<Vec<LoginLockoutModel>, AppError>;
+	async fn find_by_id(&self, id: i32) -> Result<LoginLockoutModel, AppError>;
+	async fn find_by_scope_and_identifier(
+		&self,
+		scope: &str,
+		identifier: &str,
+	) -> Result<Option<LoginLockoutModel>, AppError>;
+	async fn record_failure(
+		&self,
+		scope: &str,
+		identifier: &str,
+		window_started_after: DateTime<Utc>,
+	) -> Result<LoginLockoutModel, AppError>;
+	async fn lock(&self, id: i32, locked_until: DateTime<Utc>) -> Result<(), AppError>;
+	async fn delete_by_id(&self, id: i32) -> Result<(), AppError>;
+	async fn delete_by_scope_and_identifier(&self, scope: &str, identifier: &str) -> Result<(), AppError>;
+	async fn delete_expired(&self, window_started_before: DateTime<Utc>) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl LoginLockoutRepositoryTrait for LoginLockoutRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn find_all(&self) -> Result<Vec<LoginLockoutModel>, AppError> {
+		LoginLockout::find()
+			.order_by_desc(login_lockouts::Column::LastFailedAt)
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_id(&self, id: i32) -> Result<LoginLockoutModel, AppError> {
+		LoginLockout::find_by_id(id)
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?
+			.ok_or(AppError::NotFound)
+	}
+
+	async fn find_by_scope_and_identifier(
+		&self,
+		scope: &str,
+		identifier: &str,
+	) -> Result<Option<LoginLockoutModel>, AppError> {
+		LoginLockout::find()
+			.filter(login_lockouts::Column::Scope.eq(scope))
+			.filter(login_lockouts::Column::Identifier.eq(identifier))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn record_failure(
+		&self,
+		scope: &str,
+		identifier: &str,
+		window_started_after: DateTime<Utc>,
+	) -> Result<LoginLockoutModel, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+		let window_started_after: sea_orm::prelude::DateTimeWithTimeZone = window_started_after.into();
+
+		let login_lockout = LoginLockoutActiveModel {
+			id: ActiveValue::NotSet,
+			scope: Set(scope.to_string()),
+			identifier: Set(identifier.to_string()),
+			failed_attempts: Set(1),
+			window_started_at: Set(now),
+			last_failed_at: Set(Some(now)),
+			locked_until: Set(None),
+			created_at: Set(now),
+			updated_at: Set(None),
+		};
+
+		// Failures older than the window are forgotten and counting starts over
+		let window_expired =
+			|| Expr::col((LoginLockout, login_lockouts::Column::WindowStartedAt)).lt(window_started_after);
+
+		// A single upsert, so concurrent failures cannot overwrite each other's count
+		LoginLockout::insert(login_lockout)
+			.on_conflict(
+				OnConflict::columns([login_lockouts::Column::Scope, login_lockouts::Column::Identifier])
+					.value(
+						login_lockouts::Column::FailedAttempts,
+						Expr::case(window_expired(), 1)
+							.finally(Expr::col((LoginLockout, login_lockouts::Column::FailedAttempts)).add(1)),
+					)
+					.value(
+						login_lockouts::Column::WindowStartedAt,
+						Expr::case(window_expired(), now)
+							.finally(Expr::col((LoginLockout, login_lockouts::Column::WindowStartedAt))),
+					)
+					.value(
+						login_lockouts::Column::LockedUntil,
+						Expr::case(window_expired(), Expr::value(Option::<DateTime<Utc>>::None))
+							.finally(Expr::col((LoginLockout, login_lockouts::Column::LockedUntil))),
+					)
+					.value(login_lockouts::Column::LastFailedAt, now)
+					.value(login_lockouts::Column::UpdatedAt, now)
+					.to_owned(),
+			)
+			.exec_with_returning(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn lock(&self, id: i32, locked_until: DateTime<Utc>) -> Result<(), AppError> {
+		let login_lockout = LoginLockoutActiveModel {
+			id: Set(id),
+			locked_until: Set(Some(locked_until.into())),
+			updated_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+
+		login_lockout
+			.update(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
+	async fn delete_by_id(&self, id: i32) -> Result<(), AppError> {
+		let result = LoginLockout::delete_by_id(id)
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		if result.rows_affected == 0 {
+			return Err(AppError::NotFound);
+		}
+
+		Ok(())
+	}
+
+	async fn delete_by_scope_and_identifier(&self, scope: &str, identifier: &str) -> Result<(), AppError> {
+		LoginLockout::delete_many()
+			.filter(login_lockouts::Column::Scope.eq(scope))
+			.filter(login_lockouts::Column::Identifier.eq(identifier))
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
+	async fn delete_expired(&self, window_started_before: DateTime<Utc>) -> Result<(), AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+		let window_started_before: sea_orm::prelude::DateTimeWithTimeZone = window_started_before.into();
+
+		LoginLockout::delete_many()
+			.filter(login_lockouts::Column::WindowStartedAt.lt(window_started_before))
+			.filter(
+				Condition::any()
+					.add(login_lockouts::Column::LockedUntil.is_null())
+					.add(login_lockouts::Column::LockedUntil.lt(now)),
+			)
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+}
@@ -1 +1,7 @@
+pub mod impersonation_log_repository;
+pub mod login_lockout_repository;
+pub mod personal_access_token_repository;
 pub mod refresh_token_repository;
+pub mod revoked_access_token_repository;
+pub mod user_identity_repository;
+pub mod webauthn_credential_repository;
@@ -0,0 +1,118 @@
+use crate::auth::entities::personal_access_tokens::{
+	self, ActiveModel as PersonalAccessTokenActiveModel, Entity as PersonalAccessToken,
+	Model as PersonalAccessTokenModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::prelude::Json;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct PersonalAccessTokenRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl PersonalAccessTokenRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait PersonalAccessTokenRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn create(
+		&self,
+		user_id: i32,
+		name: &str,
+		token_hash: &str,
+		scopes: Json,
+		expires_at: Option<DateTime<Utc>>,
+	) -> Result<PersonalAccessTokenModel, AppError>;
+	async fn find_by_user_id(&self, user_id: i32) -> Result<Vec<PersonalAccessTokenModel>, AppError>;
+	async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<PersonalAccessTokenModel>, AppError>;
+	async fn update_last_used_at(&self, id: i32) -> Result<(), AppError>;
+	async fn delete_by_id_and_user_id(&self, id: i32, user_id: i32) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl PersonalAccessTokenRepositoryTrait for PersonalAccessTokenRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn create(
+		&self,
+		user_id: i32,
+		name: &str,
+		token_hash: &str,
+		scopes: Json,
+		expires_at: Option<DateTime<Utc>>,
+	) -> Result<PersonalAccessTokenModel, AppError> {
+		let personal_access_token = PersonalAccessTokenActiveModel {
+			id: ActiveValue::NotSet,
+			user_id: Set(user_id),
+			name: Set(name.to_string()),
+			token_hash: Set(token_hash.to_string()),
+			scopes: Set(scopes),
+			expires_at: Set(expires_at.map(Into::into)),
+			last_used_at: Set(None),
+			created_at: Set(Utc::now().into()),
+		};
+
+		personal_access_token
+			.insert(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_user_id(&self, user_id: i32) -> Result<Vec<PersonalAccessTokenModel>, AppError> {
+		PersonalAccessToken::find()
+			.filter(personal_access_tokens::Column::UserId.eq(user_id))
+			.order_by_desc(personal_access_tokens::Column::CreatedAt)
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<PersonalAccessTokenModel>, AppError> {
+		PersonalAccessToken::find()
+			.filter(personal_access_tokens::Column::TokenHash.eq(token_hash))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn update_last_used_at(&self, id: i32) -> Result<(), AppError> {
+		let personal_access_token = PersonalAccessTokenActiveModel {
+			id: Set(id),
+			last_used_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+
+		personal_access_token
+			.update(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
+	async fn delete_by_id_and_user_id(&self, id: i32, user_id: i32) -> Result<(), AppError> {
+		let result = PersonalAccessToken::delete_many()
+			.filter(personal_access_tokens::Column::Id.eq(id))
+			.filter(personal_access_tokens::Column::UserId.eq(user_id))
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		if result.rows_affected == 0 {
+			return Err(AppError::NotFound);
+		}
+
+		Ok(())
+	}
+}
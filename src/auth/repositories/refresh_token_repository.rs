@@ -2,13 +2,17 @@ use crate::auth::entities::refresh_tokens::{
 	self, ActiveModel as RefreshTokenActiveModel, Entity as RefreshToken, Model as RefreshTokenModel,
 };
 use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
 use crate::di::DatabaseConnectionTrait;
 use crate::users::entities::users::{Entity as User, Model as UserModel};
 use async_trait::async_trait;
 use chrono::{Duration, Utc};
-use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, Set};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+	ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait, QueryFilter,
+	QueryOrder, Set,
+};
 use std::sync::Arc;
-use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct RefreshTokenRepository {
@@ -19,24 +23,101 @@ impl RefreshTokenRepository {
 	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
 		Self { db_connection }
 	}
+
+	fn new_token(
+		user_id: i32,
+		family_id: &str,
+		token_hash: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> RefreshTokenActiveModel {
+		let now = Utc::now();
+		let expires_at = now + Duration::seconds(expires_in);
+
+		let expires_at_db: sea_orm::prelude::DateTimeWithTimeZone = expires_at.into();
+		let now_db: sea_orm::prelude::DateTimeWithTimeZone = now.into();
+
+		RefreshTokenActiveModel {
+			id: ActiveValue::NotSet,
+			token_hash: Set(token_hash.to_string()),
+			expires_at: Set(expires_at_db),
+			created_at: Set(now_db),
+			updated_at: Set(Some(now_db)),
+			user_id: Set(user_id),
+			family_id: Set(family_id.to_string()),
+			revoked_at: Set(None),
+			replaced_by_token_id: Set(None),
+			user_agent: Set(client_info.user_agent.clone()),
+			ip_address: Set(client_info.ip_address.clone()),
+			label: Set(None),
+			last_used_at: Set(Some(now_db)),
+		}
+	}
+
+	async fn insert<C: ConnectionTrait>(
+		refresh_token: RefreshTokenActiveModel,
+		db: &C,
+	) -> Result<RefreshTokenModel, AppError> {
+		refresh_token.insert(db).await.map_err(|err| {
+			tracing::error!("Error inserting refresh token: {}", err);
+			AppError::InternalError
+		})
+	}
 }
 
 #[async_trait]
 pub trait RefreshTokenRepositoryTrait: Send + Sync {
 	fn get_db(&self) -> &sea_orm::DatabaseConnection;
-	async fn create(&self, user_id: i32, expires_in: i64) -> Result<(RefreshTokenModel, String), AppError>;
+	async fn create(
+		&self,
+		user_id: i32,
+		token_hash: &str,
+		family_id: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError>;
 	async fn create_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
 		user_id: i32,
+		token_hash: &str,
+		family_id: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError>;
+	async fn create_rotated_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		previous: &RefreshTokenModel,
+		token_hash: &str,
 		expires_in: i64,
-	) -> Result<(RefreshTokenModel, String), AppError>;
-	async fn find_by_token_and_user_id(&self, token: &str, user_id: i32) -> Result<RefreshTokenModel, AppError>;
-	async fn delete_by_token_and_user_id(&self, token: &str, user_id: i32) -> Result<(), AppError>;
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError>;
+	async fn find_active_by_user_id(&self, user_id: i32) -> Result<Vec<RefreshTokenModel>, AppError>;
+	async fn find_active_by_family_id_and_user_id(
+		&self,
+		family_id: &str,
+		user_id: i32,
+	) -> Result<Option<RefreshTokenModel>, AppError>;
+	async fn update_family_label(&self, family_id: &str, user_id: i32, label: &str) -> Result<(), AppError>;
+	async fn find_by_token_hash(&self, token_hash: &str) -> Result<RefreshTokenModel, AppError>;
+	async fn delete_by_token_hash_and_user_id(&self, token_hash: &str, user_id: i32) -> Result<(), AppError>;
 	async fn delete_all_by_user_id(&self, user_id: i32) -> Result<(), AppError>;
+	async fn mark_rotated_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		id: i32,
+		replaced_by_token_id: i32,
+	) -> Result<bool, AppError>;
+	async fn revoke_family(&self, family_id: &str) -> Result<(), AppError>;
 	async fn delete_expired(&self) -> Result<(), AppError>;
 	async fn is_token_valid(&self, token_model: &RefreshTokenModel) -> bool;
 	async fn find_user_by_id(&self, user_id: i32) -> Result<UserModel, AppError>;
+	async fn find_user_by_id_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+	) -> Result<UserModel, AppError>;
 }
 
 #[async_trait]
@@ -45,65 +126,102 @@ impl RefreshTokenRepositoryTrait for RefreshTokenRepository {
 		self.db_connection.get_connection()
 	}
 
-	async fn create(&self, user_id: i32, expires_in: i64) -> Result<(RefreshTokenModel, String), AppError> {
-		let token = Uuid::new_v4().to_string();
-		let now = Utc::now();
-		let expires_at = now + Duration::seconds(expires_in);
+	async fn create(
+		&self,
+		user_id: i32,
+		token_hash: &str,
+		family_id: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError> {
+		let refresh_token = Self::new_token(user_id, family_id, token_hash, expires_in, client_info);
+		Self::insert(refresh_token, self.get_db()).await
+	}
 
-		let expires_at_db: sea_orm::prelude::DateTimeWithTimeZone = expires_at.into();
-		let now_db: sea_orm::prelude::DateTimeWithTimeZone = now.into();
+	async fn create_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		token_hash: &str,
+		family_id: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError> {
+		let refresh_token = Self::new_token(user_id, family_id, token_hash, expires_in, client_info);
+		Self::insert(refresh_token, transaction).await
+	}
 
-		let refresh_token = RefreshTokenActiveModel {
-			id: ActiveValue::NotSet,
-			token: Set(token.clone()),
-			expires_at: Set(expires_at_db),
-			created_at: Set(now_db),
-			updated_at: Set(Some(now_db)),
-			user_id: Set(user_id),
-		};
+	async fn create_rotated_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		previous: &RefreshTokenModel,
+		token_hash: &str,
+		expires_in: i64,
+		client_info: &ClientInfo,
+	) -> Result<RefreshTokenModel, AppError> {
+		let mut refresh_token = Self::new_token(
+			previous.user_id,
+			&previous.family_id,
+			token_hash,
+			expires_in,
+			client_info,
+		);
 
-		let model = refresh_token.insert(self.get_db()).await.map_err(|err| {
-			eprintln!("Error inserting refresh token: {}", err);
-			AppError::InternalError
-		})?;
+		// The new token continues the session, so it keeps the session's creation time and label
+		refresh_token.created_at = Set(previous.created_at);
+		refresh_token.label = Set(previous.label.clone());
 
-		Ok((model, token))
+		Self::insert(refresh_token, transaction).await
 	}
 
-	async fn create_in_transaction(
+	async fn find_active_by_user_id(&self, user_id: i32) -> Result<Vec<RefreshTokenModel>, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		RefreshToken::find()
+			.filter(refresh_tokens::Column::UserId.eq(user_id))
+			.filter(refresh_tokens::Column::RevokedAt.is_null())
+			.filter(refresh_tokens::Column::ExpiresAt.gte(now))
+			.order_by_desc(refresh_tokens::Column::LastUsedAt)
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_active_by_family_id_and_user_id(
 		&self,
-		transaction: &DatabaseTransaction,
+		family_id: &str,
 		user_id: i32,
-		expires_in: i64,
-	) -> Result<(RefreshTokenModel, String), AppError> {
-		let token = Uuid::new_v4().to_string();
-		let now = Utc::now();
-		let expires_at = now + Duration::seconds(expires_in);
+	) -> Result<Option<RefreshTokenModel>, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
 
-		let expires_at_db: sea_orm::prelude::DateTimeWithTimeZone = expires_at.into();
-		let now_db: sea_orm::prelude::DateTimeWithTimeZone = now.into();
+		RefreshToken::find()
+			.filter(refresh_tokens::Column::FamilyId.eq(family_id))
+			.filter(refresh_tokens::Column::UserId.eq(user_id))
+			.filter(refresh_tokens::Column::RevokedAt.is_null())
+			.filter(refresh_tokens::Column::ExpiresAt.gte(now))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
 
-		let refresh_token = RefreshTokenActiveModel {
-			id: ActiveValue::NotSet,
-			token: Set(token.clone()),
-			expires_at: Set(expires_at_db),
-			created_at: Set(now_db),
-			updated_at: Set(Some(now_db)),
-			user_id: Set(user_id),
-		};
+	async fn update_family_label(&self, family_id: &str, user_id: i32, label: &str) -> Result<(), AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
 
-		let model = refresh_token.insert(transaction).await.map_err(|err| {
-			eprintln!("Error inserting refresh token: {}", err);
-			AppError::InternalError
-		})?;
+		RefreshToken::update_many()
+			.col_expr(refresh_tokens::Column::Label, Expr::value(label))
+			.col_expr(refresh_tokens::Column::UpdatedAt, Expr::value(now))
+			.filter(refresh_tokens::Column::FamilyId.eq(family_id))
+			.filter(refresh_tokens::Column::UserId.eq(user_id))
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
 
-		Ok((model, token))
+		Ok(())
 	}
 
-	async fn find_by_token_and_user_id(&self, token: &str, user_id: i32) -> Result<RefreshTokenModel, AppError> {
+	async fn find_by_token_hash(&self, token_hash: &str) -> Result<RefreshTokenModel, AppError> {
 		let refresh_token = RefreshToken::find()
-			.filter(refresh_tokens::Column::Token.eq(token))
-			.filter(refresh_tokens::Column::UserId.eq(user_id))
+			.filter(refresh_tokens::Column::TokenHash.eq(token_hash))
 			.one(self.get_db())
 			.await
 			.map_err(|_| AppError::InternalError)?
@@ -112,9 +230,9 @@ impl RefreshTokenRepositoryTrait for RefreshTokenRepository {
 		Ok(refresh_token)
 	}
 
-	async fn delete_by_token_and_user_id(&self, token: &str, user_id: i32) -> Result<(), AppError> {
+	async fn delete_by_token_hash_and_user_id(&self, token_hash: &str, user_id: i32) -> Result<(), AppError> {
 		RefreshToken::delete_many()
-			.filter(refresh_tokens::Column::Token.eq(token))
+			.filter(refresh_tokens::Column::TokenHash.eq(token_hash))
 			.filter(refresh_tokens::Column::UserId.eq(user_id))
 			.exec(self.get_db())
 			.await
@@ -133,6 +251,46 @@ impl RefreshTokenRepositoryTrait for RefreshTokenRepository {
 		Ok(())
 	}
 
+	async fn mark_rotated_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		id: i32,
+		replaced_by_token_id: i32,
+	) -> Result<bool, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		// Only a token that has not been revoked yet can be rotated, so concurrent refreshes cannot both succeed
+		let result = RefreshToken::update_many()
+			.col_expr(refresh_tokens::Column::RevokedAt, Expr::value(now))
+			.col_expr(
+				refresh_tokens::Column::ReplacedByTokenId,
+				Expr::value(replaced_by_token_id),
+			)
+			.col_expr(refresh_tokens::Column::UpdatedAt, Expr::value(now))
+			.filter(refresh_tokens::Column::Id.eq(id))
+			.filter(refresh_tokens::Column::RevokedAt.is_null())
+			.exec(transaction)
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(result.rows_affected == 1)
+	}
+
+	async fn revoke_family(&self, family_id: &str) -> Result<(), AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		RefreshToken::update_many()
+			.col_expr(refresh_tokens::Column::RevokedAt, Expr::value(now))
+			.col_expr(refresh_tokens::Column::UpdatedAt, Expr::value(now))
+			.filter(refresh_tokens::Column::FamilyId.eq(family_id))
+			.filter(refresh_tokens::Column::RevokedAt.is_null())
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
 	async fn delete_expired(&self) -> Result<(), AppError> {
 		let now = Utc::now();
 		let now_db: sea_orm::prelude::DateTimeWithTimeZone = now.into();
@@ -148,7 +306,7 @@ impl RefreshTokenRepositoryTrait for RefreshTokenRepository {
 
 	async fn is_token_valid(&self, token_model: &RefreshTokenModel) -> bool {
 		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
-		token_model.expires_at >= now
+		token_model.revoked_at.is_none() && token_model.expires_at >= now
 	}
 
 	async fn find_user_by_id(&self, user_id: i32) -> Result<UserModel, AppError> {
@@ -156,6 +314,18 @@ impl RefreshTokenRepositoryTrait for RefreshTokenRepository {
 			.one(self.get_db())
 			.await
 			.map_err(|_| AppError::InternalError)?
-			.ok_or_else(|| AppError::NotFound)
+			.ok_or(AppError::NotFound)
+	}
+
+	async fn find_user_by_id_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+	) -> Result<UserModel, AppError> {
+		User::find_by_id(user_id)
+			.one(transaction)
+			.await
+			.map_err(|_| AppError::InternalError)?
+			.ok_or(AppError::NotFound)
 	}
 }
@@ -0,0 +1,93 @@
+use crate::auth::entities::revoked_access_tokens::{
+	self, ActiveModel as RevokedAccessTokenActiveModel, Entity as RevokedAccessToken, Model as RevokedAccessTokenModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveValue, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct RevokedAccessTokenRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl RevokedAccessTokenRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait RevokedAccessTokenRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn create(&self, jti: &str, user_id: i32, expires_at: DateTime<Utc>) -> Result<(), AppError>;
+	async fn find_active(&self) -> Result<Vec<RevokedAccessTokenModel>, AppError>;
+	async fn find_by_jti(&self, jti: &str) -> Result<Option<RevokedAccessTokenModel>, AppError>;
+	async fn delete_expired(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl RevokedAccessTokenRepositoryTrait for RevokedAccessTokenRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn create(&self, jti: &str, user_id: i32, expires_at: DateTime<Utc>) -> Result<(), AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		let revoked_access_token = RevokedAccessTokenActiveModel {
+			id: ActiveValue::NotSet,
+			jti: Set(jti.to_string()),
+			user_id: Set(user_id),
+			expires_at: Set(expires_at.into()),
+			created_at: Set(now),
+		};
+
+		// Revoking the same token twice is not an error
+		RevokedAccessToken::insert(revoked_access_token)
+			.on_conflict(
+				OnConflict::column(revoked_access_tokens::Column::Jti)
+					.do_nothing()
+					.to_owned(),
+			)
+			.do_nothing()
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
+	async fn find_active(&self) -> Result<Vec<RevokedAccessTokenModel>, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		RevokedAccessToken::find()
+			.filter(revoked_access_tokens::Column::ExpiresAt.gte(now))
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_jti(&self, jti: &str) -> Result<Option<RevokedAccessTokenModel>, AppError> {
+		RevokedAccessToken::find()
+			.filter(revoked_access_tokens::Column::Jti.eq(jti))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn delete_expired(&self) -> Result<(), AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		RevokedAccessToken::delete_many()
+			.filter(revoked_access_tokens::Column::ExpiresAt.lt(now))
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+}
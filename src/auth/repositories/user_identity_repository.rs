@@ -0,0 +1,100 @@
+use crate::auth::entities::user_identities::{
+	self, ActiveModel as UserIdentityActiveModel, Entity as UserIdentity, Model as UserIdentityModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct UserIdentityRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl UserIdentityRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait UserIdentityRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn find_by_provider_and_subject(
+		&self,
+		provider: &str,
+		subject: &str,
+	) -> Result<Option<UserIdentityModel>, AppError>;
+	async fn create_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		provider: &str,
+		subject: &str,
+		email: Option<String>,
+	) -> Result<UserIdentityModel, AppError>;
+	async fn update_last_login_at(&self, id: i32) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl UserIdentityRepositoryTrait for UserIdentityRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn find_by_provider_and_subject(
+		&self,
+		provider: &str,
+		subject: &str,
+	) -> Result<Option<UserIdentityModel>, AppError> {
+		UserIdentity::find()
+			.filter(user_identities::Column::Provider.eq(provider))
+			.filter(user_identities::Column::Subject.eq(subject))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn create_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		provider: &str,
+		subject: &str,
+		email: Option<String>,
+	) -> Result<UserIdentityModel, AppError> {
+		let now: sea_orm::prelude::DateTimeWithTimeZone = Utc::now().into();
+
+		let user_identity = UserIdentityActiveModel {
+			id: ActiveValue::NotSet,
+			user_id: Set(user_id),
+			provider: Set(provider.to_string()),
+			subject: Set(subject.to_string()),
+			email: Set(email),
+			created_at: Set(now),
+			last_login_at: Set(Some(now)),
+		};
+
+		user_identity
+			.insert(transaction)
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn update_last_login_at(&self, id: i32) -> Result<(), AppError> {
+		let user_identity = UserIdentityActiveModel {
+			id: Set(id),
+			last_login_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+
+		user_identity
+			.update(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+}
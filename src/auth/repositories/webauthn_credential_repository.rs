@@ -0,0 +1,117 @@
+use crate::auth::entities::webauthn_credentials::{
+	self, ActiveModel as WebauthnCredentialActiveModel, Entity as WebauthnCredential, Model as WebauthnCredentialModel,
+};
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct WebauthnCredentialRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl WebauthnCredentialRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait WebauthnCredentialRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection;
+	async fn create(
+		&self,
+		user_id: i32,
+		name: &str,
+		credential_id: &str,
+		public_key: Vec<u8>,
+		sign_count: i64,
+	) -> Result<WebauthnCredentialModel, AppError>;
+	async fn find_by_user_id(&self, user_id: i32) -> Result<Vec<WebauthnCredentialModel>, AppError>;
+	async fn find_by_credential_id(&self, credential_id: &str) -> Result<Option<WebauthnCredentialModel>, AppError>;
+	async fn update_sign_count(&self, id: i32, sign_count: i64) -> Result<(), AppError>;
+	async fn delete_by_id_and_user_id(&self, id: i32, user_id: i32) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl WebauthnCredentialRepositoryTrait for WebauthnCredentialRepository {
+	fn get_db(&self) -> &sea_orm::DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn create(
+		&self,
+		user_id: i32,
+		name: &str,
+		credential_id: &str,
+		public_key: Vec<u8>,
+		sign_count: i64,
+	) -> Result<WebauthnCredentialModel, AppError> {
+		let webauthn_credential = WebauthnCredentialActiveModel {
+			id: ActiveValue::NotSet,
+			user_id: Set(user_id),
+			name: Set(name.to_string()),
+			credential_id: Set(credential_id.to_string()),
+			public_key: Set(public_key),
+			sign_count: Set(sign_count),
+			last_used_at: Set(None),
+			created_at: Set(Utc::now().into()),
+		};
+
+		webauthn_credential
+			.insert(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_user_id(&self, user_id: i32) -> Result<Vec<WebauthnCredentialModel>, AppError> {
+		WebauthnCredential::find()
+			.filter(webauthn_credentials::Column::UserId.eq(user_id))
+			.order_by_desc(webauthn_credentials::Column::CreatedAt)
+			.all(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn find_by_credential_id(&self, credential_id: &str) -> Result<Option<WebauthnCredentialModel>, AppError> {
+		WebauthnCredential::find()
+			.filter(webauthn_credentials::Column::CredentialId.eq(credential_id))
+			.one(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)
+	}
+
+	async fn update_sign_count(&self, id: i32, sign_count: i64) -> Result<(), AppError> {
+		let webauthn_credential = WebauthnCredentialActiveModel {
+			id: Set(id),
+			sign_count: Set(sign_count),
+			last_used_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+
+		webauthn_credential
+			.update(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		Ok(())
+	}
+
+	async fn delete_by_id_and_user_id(&self, id: i32, user_id: i32) -> Result<(), AppError> {
+		let result = WebauthnCredential::delete_many()
+			.filter(webauthn_credentials::Column::Id.eq(id))
+			.filter(webauthn_credentials::Column::UserId.eq(user_id))
+			.exec(self.get_db())
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		if result.rows_affected == 0 {
+			return Err(AppError::NotFound);
+		}
+
+		Ok(())
+	}
+}
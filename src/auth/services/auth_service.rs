@@ -1,27 +1,40 @@
 use crate::auth::dto::login_dto::LoginDto;
+use crate::auth::dto::reauthenticate_dto::ReauthenticateDto;
 use crate::auth::dto::register_dto::RegisterDto;
+use crate::auth::dto::two_factor_login_dto::TwoFactorLoginDto;
+use crate::auth::services::confirmation_token_service::{ConfirmationTokenServiceTrait, TokenType};
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
+use crate::auth::services::login_protection_service::LoginProtectionServiceTrait;
+use crate::auth::services::personal_access_token_service::PERSONAL_ACCESS_TOKEN_PREFIX;
 use crate::auth::services::refresh_token_service::RefreshTokenServiceTrait;
-use crate::common::enums::role_enum::RoleEnum;
+use crate::auth::services::two_factor_service::TwoFactorServiceTrait;
 use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
 use crate::common::r#struct::app_state::AppState;
 use crate::config::app_config::AppConfig;
 use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
 use crate::roles::services::user_roles_service::{UserRolesService, UserRolesServiceTrait};
 use crate::users::entities::users::Model as User;
+use crate::users::services::password_policy_service::PasswordPolicyServiceTrait;
 use crate::users::services::users_service::{UsersService, UsersServiceTrait};
 use async_trait::async_trait;
 use chrono::{Duration, Utc};
-use jsonwebtoken::{EncodingKey, Header, encode};
 use sea_orm::DatabaseTransaction;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct AuthService {
 	users_service: Arc<dyn UsersServiceTrait>,
 	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	two_factor_service: Arc<dyn TwoFactorServiceTrait>,
+	confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+	jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
+	password_policy_service: Arc<dyn PasswordPolicyServiceTrait>,
+	login_protection_service: Arc<dyn LoginProtectionServiceTrait>,
 	app_config: Arc<dyn AppConfigTrait>,
-	jwt_access_token_secret: String,
 	jwt_access_token_expires_in: i64,
 }
 
@@ -30,9 +43,41 @@ pub struct Claims {
 	pub sub: i32,
 	pub username: String,
 	pub email: String,
-	pub roles: Vec<RoleEnum>,
+	/// Names of the roles held by the subject, built-in or created through the roles API
+	pub roles: Vec<String>,
 	pub exp: i64,
 	pub iat: i64,
+	pub jti: String,
+	/// Set on tokens issued to a service account through the client credentials grant, `sub` is then
+	/// the service account id
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub client_id: Option<String>,
+	/// Set on tokens an admin obtained to act as the user, identifies that admin (RFC 8693 `act`)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub act: Option<Actor>,
+	/// When the user last entered their credentials, absent on refreshed and impersonation tokens
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub auth_time: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Actor {
+	pub sub: i32,
+	pub username: String,
+}
+
+impl Claims {
+	pub fn is_service_account(&self) -> bool {
+		self.client_id.is_some()
+	}
+
+	pub fn is_impersonated(&self) -> bool {
+		self.act.is_some()
+	}
+
+	pub fn is_personal_access_token(&self) -> bool {
+		self.jti.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX)
+	}
 }
 
 #[derive(Debug, Serialize)]
@@ -41,22 +86,82 @@ pub struct AuthResponse {
 	pub access_token: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct TwoFactorRequiredResponse {
+	pub two_factor_required: bool,
+	pub mfa_token: String,
+}
+
+pub enum RegisterResult {
+	Registered(Box<User>, String, String),
+	/// Returned in enumeration-safe mode, the account can be used after confirming the email
+	Pending,
+}
+
+pub enum LoginResult {
+	Authenticated(Box<User>, String, String),
+	TwoFactorRequired(String),
+}
+
 impl AuthService {
+	#[allow(clippy::too_many_arguments)]
 	pub fn new(
 		users_service: Arc<dyn UsersServiceTrait>,
 		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		two_factor_service: Arc<dyn TwoFactorServiceTrait>,
+		confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+		jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
+		password_policy_service: Arc<dyn PasswordPolicyServiceTrait>,
+		login_protection_service: Arc<dyn LoginProtectionServiceTrait>,
 		app_config: Arc<dyn AppConfigTrait>,
 	) -> Self {
-		let jwt_access_token_secret = app_config.get_config().security.tokens.jwt_access_token.secret.clone();
 		let jwt_access_token_expires_in = app_config.get_config().security.tokens.jwt_access_token.expires_in;
 		Self {
 			users_service,
 			user_roles_service,
+			two_factor_service,
+			confirmation_token_service,
+			jwt_keys_service,
+			password_policy_service,
+			login_protection_service,
 			app_config,
-			jwt_access_token_secret,
 			jwt_access_token_expires_in,
 		}
 	}
+
+	fn is_already_taken(errors: &validator::ValidationErrors) -> bool {
+		errors
+			.field_errors()
+			.values()
+			.any(|field_errors| field_errors.iter().any(|error| error.code == "already_exists"))
+	}
+
+	async fn build_claims(
+		&self,
+		user: &User,
+		expires_in: i64,
+		act: Option<Actor>,
+		authenticated: bool,
+	) -> Result<Claims, AppError> {
+		let now = Utc::now();
+		let expires_at = now + Duration::seconds(expires_in);
+
+		let user_roles = self.user_roles_service.get_user_roles(user.id).await?;
+		let role_names: Vec<String> = user_roles.into_iter().map(|role| role.name).collect();
+
+		Ok(Claims {
+			sub: user.id,
+			username: user.username.clone(),
+			email: user.email.clone(),
+			roles: role_names,
+			exp: expires_at.timestamp(),
+			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+			client_id: None,
+			act,
+			auth_time: authenticated.then_some(now.timestamp()),
+		})
+	}
 }
 
 #[async_trait]
@@ -65,19 +170,60 @@ pub trait AuthServiceTrait: Send + Sync {
 		&self,
 		dto: RegisterDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
-	) -> Result<(User, String, String), AppError>;
+		client_info: &ClientInfo,
+	) -> Result<RegisterResult, AppError>;
 	async fn login(
 		&self,
 		dto: LoginDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<LoginResult, AppError>;
+	async fn login_with_user(
+		&self,
+		user: User,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<LoginResult, AppError>;
+	async fn login_with_two_factor(
+		&self,
+		dto: TwoFactorLoginDto,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<(User, String, String), AppError>;
+	async fn issue_tokens(
+		&self,
+		user: User,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
 	) -> Result<(User, String, String), AppError>;
 	async fn generate_token(&self, user: &User) -> Result<String, AppError>;
+	/// Issues a new access token with a current `auth_time` after checking the password, and the
+	/// two-factor code when enabled.
+	async fn reauthenticate(
+		&self,
+		user_id: i32,
+		dto: ReauthenticateDto,
+		client_info: &ClientInfo,
+	) -> Result<(User, String), AppError>;
+	/// Access token for `user` carrying the admin acting as them, no refresh token is issued.
+	async fn generate_impersonation_token(
+		&self,
+		user: &User,
+		actor: Actor,
+		expires_in: i64,
+	) -> Result<(String, Claims), AppError>;
 	async fn register_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
 		dto: RegisterDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
 	) -> Result<(User, String, String), AppError>;
+	async fn create_account_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		dto: RegisterDto,
+	) -> Result<User, AppError>;
 }
 
 #[async_trait]
@@ -86,12 +232,38 @@ impl AuthServiceTrait for AuthService {
 		&self,
 		dto: RegisterDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
-	) -> Result<(User, String, String), AppError> {
+		client_info: &ClientInfo,
+	) -> Result<RegisterResult, AppError> {
+		let enumeration_safe = self.app_config.get_config().security.enumeration_safe;
+
+		// Checked before anything else, the outcome must not depend on whether the email is taken
+		self.password_policy_service
+			.validate("password", &dto.password, &dto.username, &dto.email)
+			.await?;
+
+		// The owner of the address is told about the attempt instead of the caller
+		if enumeration_safe && self.users_service.notify_registration_attempt(&dto.email).await? {
+			return Ok(RegisterResult::Pending);
+		}
+
 		let transaction = self.users_service.begin_transaction().await?;
 
-		let result = self
-			.register_in_transaction(&transaction, dto, refresh_token_service)
-			.await;
+		let result = if enumeration_safe {
+			match self.create_account_in_transaction(&transaction, dto).await {
+				Ok(_) => Ok(RegisterResult::Pending),
+				// A taken username must not be told apart from a successful registration either
+				Err(AppError::ValidationError(errors)) if Self::is_already_taken(&errors) => {
+					Ok(RegisterResult::Pending)
+				}
+				Err(e) => Err(e),
+			}
+		} else {
+			self.register_in_transaction(&transaction, dto, refresh_token_service, client_info)
+				.await
+				.map(|(user, access_token, refresh_token)| {
+					RegisterResult::Registered(Box::new(user), access_token, refresh_token)
+				})
+		};
 
 		match result {
 			Ok(response) => {
@@ -109,41 +281,152 @@ impl AuthServiceTrait for AuthService {
 		&self,
 		dto: LoginDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<LoginResult, AppError> {
+		let user = self
+			.users_service
+			.login(&dto.username, &dto.password, client_info.ip_address.as_deref())
+			.await?;
+
+		self.login_with_user(user, refresh_token_service, client_info).await
+	}
+
+	async fn login_with_user(
+		&self,
+		user: User,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<LoginResult, AppError> {
+		if user.is_two_factor_enabled {
+			let (mfa_token, claims) = self
+				.confirmation_token_service
+				.generate_mfa_token(user.id, &user.email)
+				.await?;
+			self.two_factor_service.start_challenge(user.id, &claims.jti).await?;
+
+			return Ok(LoginResult::TwoFactorRequired(mfa_token));
+		}
+
+		let (user, access_token, refresh_token) = self.issue_tokens(user, refresh_token_service, client_info).await?;
+
+		Ok(LoginResult::Authenticated(Box::new(user), access_token, refresh_token))
+	}
+
+	async fn login_with_two_factor(
+		&self,
+		dto: TwoFactorLoginDto,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
+	) -> Result<(User, String, String), AppError> {
+		let claims = self.confirmation_token_service.validate_token(&dto.mfa_token).await?;
+
+		if !matches!(claims.token_type, TokenType::MfaPending) {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.invalid_token_type",
+			)));
+		}
+
+		let user = self.users_service.find_by_id(claims.sub).await?;
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		// Used, replaced and exhausted tokens are no longer stored
+		if user.mfa_token_jti.as_deref() != Some(claims.jti.as_str()) {
+			return Err(AppError::AuthenticationError(translate("auth.errors.invalid_token")));
+		}
+
+		let ip_address = client_info.ip_address.as_deref();
+		self.login_protection_service
+			.check_login_allowed(&user.username, ip_address)
+			.await?;
+
+		if !self.two_factor_service.verify_code(&user, &dto.code).await? {
+			self.two_factor_service
+				.record_challenge_failure(user.id, &claims.jti)
+				.await?;
+			self.login_protection_service
+				.record_failed_login(&user.username, ip_address)
+				.await?;
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.invalid_two_factor_code",
+			)));
+		}
+
+		self.two_factor_service.complete_challenge(user.id, &claims.jti).await?;
+		self.login_protection_service
+			.record_successful_login(&user.username)
+			.await?;
+
+		self.issue_tokens(user, refresh_token_service, client_info).await
+	}
+
+	async fn issue_tokens(
+		&self,
+		user: User,
+		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
 	) -> Result<(User, String, String), AppError> {
-		let user = self.users_service.login(&dto.username, &dto.password).await?;
 		let access_token = self.generate_token(&user).await?;
-		let refresh_token = refresh_token_service.generate_refresh_token(user.id).await?;
+		let refresh_token = refresh_token_service
+			.generate_refresh_token(user.id, client_info)
+			.await?;
 
 		Ok((user, access_token, refresh_token))
 	}
 
 	async fn generate_token(&self, user: &User) -> Result<String, AppError> {
-		let now = Utc::now();
-		let expires_at = now + Duration::seconds(self.jwt_access_token_expires_in);
+		let claims = self
+			.build_claims(user, self.jwt_access_token_expires_in, None, true)
+			.await?;
+		self.jwt_keys_service.encode(&claims)
+	}
 
-		let user_roles = self.user_roles_service.get_user_roles(user.id).await?;
-		let role_enums: Vec<RoleEnum> = user_roles
-			.into_iter()
-			.filter_map(|role| RoleEnum::from_str(&role.name))
-			.collect();
+	async fn reauthenticate(
+		&self,
+		user_id: i32,
+		dto: ReauthenticateDto,
+		client_info: &ClientInfo,
+	) -> Result<(User, String), AppError> {
+		let user = self.users_service.find_by_id(user_id).await?;
 
-		let claims = Claims {
-			sub: user.id,
-			username: user.username.clone(),
-			email: user.email.clone(),
-			roles: role_enums,
-			exp: expires_at.timestamp(),
-			iat: now.timestamp(),
-		};
+		// Goes through the regular login checks, so failed attempts count towards the lockout
+		let user = self
+			.users_service
+			.login(&user.username, &dto.password, client_info.ip_address.as_deref())
+			.await?;
 
-		let token = encode(
-			&Header::default(),
-			&claims,
-			&EncodingKey::from_secret(self.jwt_access_token_secret.as_bytes()),
-		)
-		.map_err(|_| AppError::InternalError)?;
+		if user.is_two_factor_enabled {
+			let code = dto.code.as_deref().unwrap_or_default();
+			if !self.two_factor_service.verify_code(&user, code).await? {
+				self.login_protection_service
+					.record_failed_login(&user.username, client_info.ip_address.as_deref())
+					.await?;
+				return Err(AppError::AuthenticationError(translate(
+					"auth.errors.invalid_two_factor_code",
+				)));
+			}
+
+			self.login_protection_service
+				.record_successful_login(&user.username)
+				.await?;
+		}
+
+		let access_token = self.generate_token(&user).await?;
+
+		Ok((user, access_token))
+	}
 
-		Ok(token)
+	async fn generate_impersonation_token(
+		&self,
+		user: &User,
+		actor: Actor,
+		expires_in: i64,
+	) -> Result<(String, Claims), AppError> {
+		let claims = self.build_claims(user, expires_in, Some(actor), false).await?;
+		let token = self.jwt_keys_service.encode(&claims)?;
+		Ok((token, claims))
 	}
 
 	async fn register_in_transaction(
@@ -151,7 +434,23 @@ impl AuthServiceTrait for AuthService {
 		transaction: &DatabaseTransaction,
 		dto: RegisterDto,
 		refresh_token_service: &Arc<dyn RefreshTokenServiceTrait>,
+		client_info: &ClientInfo,
 	) -> Result<(User, String, String), AppError> {
+		let user = self.create_account_in_transaction(transaction, dto).await?;
+
+		let access_token = self.generate_token(&user).await?;
+		let refresh_token = refresh_token_service
+			.generate_refresh_token_in_transaction(transaction, user.id, client_info)
+			.await?;
+
+		Ok((user, access_token, refresh_token))
+	}
+
+	async fn create_account_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		dto: RegisterDto,
+	) -> Result<User, AppError> {
 		let create_user_dto = crate::users::dto::create_user_dto::CreateUserDto {
 			username: dto.username.clone(),
 			email: dto.email.clone(),
@@ -175,11 +474,6 @@ impl AuthServiceTrait for AuthService {
 				AppError::InternalError
 			})?;
 
-		let access_token = self.generate_token(&user).await?;
-		let refresh_token = refresh_token_service
-			.generate_refresh_token_in_transaction(transaction, user.id)
-			.await?;
-
-		Ok((user, access_token, refresh_token))
+		Ok(user)
 	}
 }
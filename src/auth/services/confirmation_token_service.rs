@@ -13,6 +13,9 @@ pub enum TokenType {
 	EmailConfirmation,
 	EmailChange,
 	PasswordReset,
+	MfaPending,
+	AccountUnlock,
+	MagicLogin,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,6 +34,8 @@ pub struct ConfirmationTokenService {
 	app_config: Arc<dyn AppConfigTrait>,
 	confirmation_token_secret: String,
 	confirmation_token_expires_in: i64,
+	mfa_token_expires_in: i64,
+	magic_login_token_expires_in: i64,
 }
 
 impl ConfirmationTokenService {
@@ -43,10 +48,14 @@ impl ConfirmationTokenService {
 			.secret
 			.clone();
 		let confirmation_token_expires_in = app_config.get_config().security.tokens.confirmation_token.expires_in;
+		let mfa_token_expires_in = app_config.get_config().security.tokens.mfa_token.expires_in;
+		let magic_login_token_expires_in = app_config.get_config().security.tokens.magic_login_token.expires_in;
 		Self {
 			app_config,
 			confirmation_token_secret,
 			confirmation_token_expires_in,
+			mfa_token_expires_in,
+			magic_login_token_expires_in,
 		}
 	}
 }
@@ -61,6 +70,13 @@ pub trait ConfirmationTokenServiceTrait: Send + Sync {
 		new_email: &str,
 	) -> Result<String, AppError>;
 	async fn generate_password_reset_token(&self, user_id: i32, email: &str) -> Result<String, AppError>;
+	async fn generate_mfa_token(&self, user_id: i32, email: &str) -> Result<(String, ConfirmationClaims), AppError>;
+	async fn generate_account_unlock_token(&self, user_id: i32, email: &str) -> Result<String, AppError>;
+	async fn generate_magic_login_token(
+		&self,
+		user_id: i32,
+		email: &str,
+	) -> Result<(String, ConfirmationClaims), AppError>;
 	async fn validate_token(&self, token: &str) -> Result<ConfirmationClaims, AppError>;
 	async fn validate_stored_token(
 		&self,
@@ -150,6 +166,84 @@ impl ConfirmationTokenServiceTrait for ConfirmationTokenService {
 		Ok(token)
 	}
 
+	async fn generate_mfa_token(&self, user_id: i32, email: &str) -> Result<(String, ConfirmationClaims), AppError> {
+		let now = Utc::now();
+		let expires_at = now + Duration::seconds(self.mfa_token_expires_in);
+
+		let claims = ConfirmationClaims {
+			sub: user_id,
+			email: email.to_string(),
+			token_type: TokenType::MfaPending,
+			new_email: None,
+			exp: expires_at.timestamp(),
+			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+		};
+
+		let token = encode(
+			&Header::default(),
+			&claims,
+			&EncodingKey::from_secret(self.confirmation_token_secret.as_bytes()),
+		)
+		.map_err(|_| AppError::InternalError)?;
+
+		// The caller has to store the jti, so the token can be used only once
+		Ok((token, claims))
+	}
+
+	async fn generate_account_unlock_token(&self, user_id: i32, email: &str) -> Result<String, AppError> {
+		let now = Utc::now();
+		let expires_at = now + Duration::seconds(self.confirmation_token_expires_in);
+
+		let claims = ConfirmationClaims {
+			sub: user_id,
+			email: email.to_string(),
+			token_type: TokenType::AccountUnlock,
+			new_email: None,
+			exp: expires_at.timestamp(),
+			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+		};
+
+		let token = encode(
+			&Header::default(),
+			&claims,
+			&EncodingKey::from_secret(self.confirmation_token_secret.as_bytes()),
+		)
+		.map_err(|_| AppError::InternalError)?;
+
+		Ok(token)
+	}
+
+	async fn generate_magic_login_token(
+		&self,
+		user_id: i32,
+		email: &str,
+	) -> Result<(String, ConfirmationClaims), AppError> {
+		let now = Utc::now();
+		let expires_at = now + Duration::seconds(self.magic_login_token_expires_in);
+
+		let claims = ConfirmationClaims {
+			sub: user_id,
+			email: email.to_string(),
+			token_type: TokenType::MagicLogin,
+			new_email: None,
+			exp: expires_at.timestamp(),
+			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+		};
+
+		let token = encode(
+			&Header::default(),
+			&claims,
+			&EncodingKey::from_secret(self.confirmation_token_secret.as_bytes()),
+		)
+		.map_err(|_| AppError::InternalError)?;
+
+		// The caller has to store the jti, so the link can be used only once
+		Ok((token, claims))
+	}
+
 	async fn validate_token(&self, token: &str) -> Result<ConfirmationClaims, AppError> {
 		let token_data = decode::<ConfirmationClaims>(
 			token,
@@ -171,23 +265,19 @@ impl ConfirmationTokenServiceTrait for ConfirmationTokenService {
 		let claims = self.validate_token(token).await?;
 
 		if !matches!(claims.token_type, ref t if std::mem::discriminant(t) == std::mem::discriminant(&expected_type)) {
-			return Err(AppError::AuthorizationError(
-				translate("auth.errors.invalid_token_type").into(),
-			));
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.invalid_token_type",
+			)));
 		}
 
 		if stored_token != Some(token) {
-			return Err(AppError::AuthorizationError(
-				translate("auth.errors.invalid_token").into(),
-			));
+			return Err(AppError::AuthorizationError(translate("auth.errors.invalid_token")));
 		}
 
-		if let Some(expires_at) = expiry {
-			if Utc::now() > expires_at {
-				return Err(AppError::AuthorizationError(
-					translate("auth.errors.expired_token").into(),
-				));
-			}
+		if let Some(expires_at) = expiry
+			&& Utc::now() > expires_at
+		{
+			return Err(AppError::AuthorizationError(translate("auth.errors.expired_token")));
 		}
 
 		Ok(claims)
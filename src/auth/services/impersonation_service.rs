@@ -0,0 +1,196 @@
+use crate::auth::dto::impersonate_dto::ImpersonateDto;
+use crate::auth::entities::impersonation_logs::Model as ImpersonationLogModel;
+use crate::auth::repositories::impersonation_log_repository::ImpersonationLogRepositoryTrait;
+use crate::auth::services::auth_service::{Actor, AuthServiceTrait, Claims};
+use crate::common::enums::role_enum::RoleEnum;
+use crate::common::error::app_error::AppError;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::roles::services::permissions_service::PermissionsServiceTrait;
+use crate::roles::services::user_roles_service::UserRolesServiceTrait;
+use crate::users::entities::users::Model as UserModel;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+	pub user: UserModel,
+	pub access_token: String,
+	pub expires_in: i64,
+}
+
+/// Lets an admin act as another user with a short-lived access token. Every token handed out is
+/// recorded together with the admin who asked for it and the reason.
+pub struct ImpersonationService {
+	impersonation_log_repository: Arc<dyn ImpersonationLogRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	permissions_service: Arc<dyn PermissionsServiceTrait>,
+	auth_service: Arc<dyn AuthServiceTrait>,
+	impersonation_token_expires_in: i64,
+}
+
+impl ImpersonationService {
+	pub fn new(
+		impersonation_log_repository: Arc<dyn ImpersonationLogRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		permissions_service: Arc<dyn PermissionsServiceTrait>,
+		auth_service: Arc<dyn AuthServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let impersonation_token_expires_in = app_config.get_config().security.tokens.impersonation_token.expires_in;
+		Self {
+			impersonation_log_repository,
+			users_repository,
+			user_roles_service,
+			permissions_service,
+			auth_service,
+			impersonation_token_expires_in,
+		}
+	}
+
+	async fn effective_permissions(&self, user_id: i32) -> Result<(Vec<String>, Vec<String>), AppError> {
+		let role_names: Vec<String> = self
+			.user_roles_service
+			.get_effective_roles(user_id)
+			.await?
+			.into_iter()
+			.map(|role| role.name)
+			.collect();
+		let permissions = self.permissions_service.find_names_by_role_names(&role_names).await?;
+
+		Ok((role_names, permissions))
+	}
+
+	/// Whether the target holds a permission the impersonator does not, so impersonating it would
+	/// widen the impersonator's own access.
+	fn grants_more_than(target_permissions: &[String], impersonator_permissions: &[String]) -> bool {
+		target_permissions
+			.iter()
+			.any(|permission| !impersonator_permissions.contains(permission))
+	}
+}
+
+#[async_trait]
+pub trait ImpersonationServiceTrait: Send + Sync {
+	async fn impersonate(
+		&self,
+		impersonator: &Claims,
+		user_id: i32,
+		dto: ImpersonateDto,
+		ip_address: Option<String>,
+	) -> Result<ImpersonationResponse, AppError>;
+	async fn find_all(&self) -> Result<Vec<ImpersonationLogModel>, AppError>;
+}
+
+#[async_trait]
+impl ImpersonationServiceTrait for ImpersonationService {
+	async fn impersonate(
+		&self,
+		impersonator: &Claims,
+		user_id: i32,
+		dto: ImpersonateDto,
+		ip_address: Option<String>,
+	) -> Result<ImpersonationResponse, AppError> {
+		// Impersonation tokens cannot be used to hop on to further accounts
+		if impersonator.is_impersonated() || impersonator.sub == user_id {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.impersonation_not_allowed",
+			)));
+		}
+
+		let user = self.users_repository.find_by_id(user_id).await?;
+
+		// Other admins are off limits, as are accounts with permissions the impersonator lacks and accounts
+		// the token would not work for anyway
+		let (target_roles, target_permissions) = self.effective_permissions(user.id).await?;
+		let (_, impersonator_permissions) = self.effective_permissions(impersonator.sub).await?;
+		let is_admin = target_roles.iter().any(|role| role == RoleEnum::Admin.as_str());
+
+		if is_admin
+			|| Self::grants_more_than(&target_permissions, &impersonator_permissions)
+			|| !user.is_active
+			|| !user.is_email_confirmed
+		{
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.impersonation_not_allowed",
+			)));
+		}
+
+		let actor = Actor {
+			sub: impersonator.sub,
+			username: impersonator.username.clone(),
+		};
+		let (access_token, claims) = self
+			.auth_service
+			.generate_impersonation_token(&user, actor, self.impersonation_token_expires_in)
+			.await?;
+
+		let expires_at = DateTime::<Utc>::from_timestamp(claims.exp, 0).ok_or(AppError::InternalError)?;
+		self.impersonation_log_repository
+			.create(
+				impersonator.sub,
+				user.id,
+				dto.reason,
+				&claims.jti,
+				ip_address,
+				expires_at,
+			)
+			.await?;
+
+		tracing::info!(
+			"User {} started impersonating user {} (token {})",
+			impersonator.sub,
+			user.id,
+			claims.jti
+		);
+
+		Ok(ImpersonationResponse {
+			user,
+			access_token,
+			expires_in: self.impersonation_token_expires_in,
+		})
+	}
+
+	async fn find_all(&self) -> Result<Vec<ImpersonationLogModel>, AppError> {
+		self.impersonation_log_repository.find_all().await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn permissions(names: &[&str]) -> Vec<String> {
+		names.iter().map(|name| name.to_string()).collect()
+	}
+
+	#[test]
+	fn grants_more_than_allows_targets_with_fewer_permissions() {
+		let impersonator = permissions(&["users:impersonate", "files:write"]);
+
+		assert!(!ImpersonationService::grants_more_than(&[], &impersonator));
+		assert!(!ImpersonationService::grants_more_than(
+			&permissions(&["files:write"]),
+			&impersonator
+		));
+	}
+
+	#[test]
+	fn grants_more_than_refuses_targets_with_other_permissions() {
+		let impersonator = permissions(&["users:impersonate"]);
+
+		assert!(ImpersonationService::grants_more_than(
+			&permissions(&["roles:manage"]),
+			&impersonator
+		));
+		assert!(ImpersonationService::grants_more_than(
+			&permissions(&["users:impersonate", "files:delete"]),
+			&impersonator
+		));
+	}
+}
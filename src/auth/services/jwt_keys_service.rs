@@ -0,0 +1,235 @@
+use crate::auth::services::auth_service::Claims;
+use crate::common::error::app_error::AppError;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::jwk::{
+	AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, EllipticCurveKeyType, Jwk,
+	JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode};
+use rsa::RsaPublicKey;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::traits::PublicKeyParts;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const PUBLIC_KEY_SUFFIX: &str = ".public.pem";
+const PRIVATE_KEY_SUFFIX: &str = ".private.pem";
+
+/// Signs and verifies access tokens.
+///
+/// With an HMAC algorithm the shared `secret` is used. With an asymmetric algorithm the keys are read
+/// from `keys_dir`: every `<kid>.public.pem` is accepted for verification and `<kid>.private.pem` of
+/// the active kid is used for signing. A key is retired by removing its public key file.
+pub struct JwtKeysService {
+	algorithm: Algorithm,
+	active_kid: String,
+	encoding_key: EncodingKey,
+	decoding_keys: HashMap<String, DecodingKey>,
+	jwks: JwkSet,
+}
+
+impl JwtKeysService {
+	pub fn new(app_config: &dyn AppConfigTrait) -> Result<Self, AppError> {
+		let config = &app_config.get_config().security.tokens.jwt_access_token;
+
+		let algorithm = Algorithm::from_str(&config.algorithm)
+			.map_err(|_| AppError::ConfigError(format!("Unsupported JWT algorithm: {}", config.algorithm)))?;
+
+		if Self::is_hmac(algorithm) {
+			let decoding_keys = HashMap::from([(
+				config.active_kid.clone(),
+				DecodingKey::from_secret(config.secret.as_bytes()),
+			)]);
+
+			return Ok(Self {
+				algorithm,
+				active_kid: config.active_kid.clone(),
+				encoding_key: EncodingKey::from_secret(config.secret.as_bytes()),
+				decoding_keys,
+				// Shared secrets must never be published
+				jwks: JwkSet { keys: Vec::new() },
+			});
+		}
+
+		let key_algorithm = KeyAlgorithm::from_str(&config.algorithm).map_err(|_| AppError::InternalError)?;
+		let keys_dir = Path::new(&config.keys_dir);
+		let mut decoding_keys = HashMap::new();
+		let mut jwks = JwkSet { keys: Vec::new() };
+
+		let entries = fs::read_dir(keys_dir).map_err(|err| {
+			AppError::ConfigError(format!(
+				"Could not read JWT keys directory {}: {}. Generate a {} key pair as described in the readme, or set \
+				 security.tokens.jwt_access_token.algorithm to HS256 to sign with the shared secret",
+				config.keys_dir, err, config.algorithm
+			))
+		})?;
+
+		for entry in entries {
+			let path = entry.map_err(|err| AppError::ConfigError(err.to_string()))?.path();
+			let Some(kid) = path
+				.file_name()
+				.and_then(|name| name.to_str())
+				.and_then(|name| name.strip_suffix(PUBLIC_KEY_SUFFIX))
+			else {
+				continue;
+			};
+
+			let pem = Self::read_key_file(&path)?;
+			let decoding_key = Self::decoding_key_from_pem(algorithm, &pem)
+				.map_err(|err| AppError::ConfigError(format!("Invalid public key {}: {}", kid, err)))?;
+
+			jwks.keys
+				.push(Self::build_jwk(algorithm, key_algorithm, kid, &decoding_key)?);
+			decoding_keys.insert(kid.to_string(), decoding_key);
+		}
+
+		if !decoding_keys.contains_key(&config.active_kid) {
+			return Err(AppError::ConfigError(format!(
+				"Missing public key {}/{}{} for the active JWT key",
+				config.keys_dir, config.active_kid, PUBLIC_KEY_SUFFIX
+			)));
+		}
+
+		let private_key_path = keys_dir.join(format!("{}{}", config.active_kid, PRIVATE_KEY_SUFFIX));
+		let pem = Self::read_key_file(&private_key_path)?;
+		let encoding_key = Self::encoding_key_from_pem(algorithm, &pem)
+			.map_err(|err| AppError::ConfigError(format!("Invalid private key {}: {}", config.active_kid, err)))?;
+
+		Ok(Self {
+			algorithm,
+			active_kid: config.active_kid.clone(),
+			encoding_key,
+			decoding_keys,
+			jwks,
+		})
+	}
+
+	fn is_hmac(algorithm: Algorithm) -> bool {
+		matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+	}
+
+	fn read_key_file(path: &Path) -> Result<Vec<u8>, AppError> {
+		fs::read(path)
+			.map_err(|err| AppError::ConfigError(format!("Could not read JWT key {}: {}", path.display(), err)))
+	}
+
+	fn encoding_key_from_pem(algorithm: Algorithm, pem: &[u8]) -> jsonwebtoken::errors::Result<EncodingKey> {
+		match algorithm {
+			Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(pem),
+			Algorithm::EdDSA => EncodingKey::from_ed_pem(pem),
+			_ => EncodingKey::from_rsa_pem(pem),
+		}
+	}
+
+	fn decoding_key_from_pem(algorithm: Algorithm, pem: &[u8]) -> jsonwebtoken::errors::Result<DecodingKey> {
+		match algorithm {
+			Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(pem),
+			Algorithm::EdDSA => DecodingKey::from_ed_pem(pem),
+			_ => DecodingKey::from_rsa_pem(pem),
+		}
+	}
+
+	fn build_jwk(
+		algorithm: Algorithm,
+		key_algorithm: KeyAlgorithm,
+		kid: &str,
+		decoding_key: &DecodingKey,
+	) -> Result<Jwk, AppError> {
+		let key = decoding_key.as_bytes();
+
+		let parameters = match algorithm {
+			Algorithm::EdDSA => AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+				key_type: OctetKeyPairType::OctetKeyPair,
+				curve: EllipticCurve::Ed25519,
+				x: URL_SAFE_NO_PAD.encode(key),
+			}),
+			Algorithm::ES256 | Algorithm::ES384 => {
+				// Uncompressed SEC1 point: 0x04 || x || y
+				let coordinates = key
+					.strip_prefix(&[0x04])
+					.ok_or_else(|| AppError::ConfigError(format!("Unsupported EC public key {}", kid)))?;
+				let (x, y) = coordinates.split_at(coordinates.len() / 2);
+
+				AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+					key_type: EllipticCurveKeyType::EC,
+					curve: if algorithm == Algorithm::ES256 {
+						EllipticCurve::P256
+					} else {
+						EllipticCurve::P384
+					},
+					x: URL_SAFE_NO_PAD.encode(x),
+					y: URL_SAFE_NO_PAD.encode(y),
+				})
+			}
+			_ => {
+				let public_key = RsaPublicKey::from_pkcs1_der(key)
+					.map_err(|err| AppError::ConfigError(format!("Invalid RSA public key {}: {}", kid, err)))?;
+
+				AlgorithmParameters::RSA(RSAKeyParameters {
+					key_type: RSAKeyType::RSA,
+					n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+					e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+				})
+			}
+		};
+
+		Ok(Jwk {
+			common: CommonParameters {
+				public_key_use: Some(PublicKeyUse::Signature),
+				key_algorithm: Some(key_algorithm),
+				key_id: Some(kid.to_string()),
+				..Default::default()
+			},
+			algorithm: parameters,
+		})
+	}
+
+	fn invalid_token_error() -> AppError {
+		AppError::AuthenticationError(translate("auth.errors.invalid_token"))
+	}
+}
+
+pub trait JwtKeysServiceTrait: Send + Sync {
+	fn encode(&self, claims: &Claims) -> Result<String, AppError>;
+	fn decode(&self, token: &str) -> Result<Claims, AppError>;
+	fn jwks(&self) -> &JwkSet;
+}
+
+impl JwtKeysServiceTrait for JwtKeysService {
+	fn encode(&self, claims: &Claims) -> Result<String, AppError> {
+		let mut header = Header::new(self.algorithm);
+		header.kid = Some(self.active_kid.clone());
+
+		encode(&header, claims, &self.encoding_key).map_err(|_| AppError::InternalError)
+	}
+
+	fn decode(&self, token: &str) -> Result<Claims, AppError> {
+		let header = decode_header(token).map_err(|_| Self::invalid_token_error())?;
+
+		if header.alg != self.algorithm {
+			return Err(Self::invalid_token_error());
+		}
+
+		// Tokens issued before key ids were introduced are only accepted with the shared secret
+		let kid = match header.kid {
+			Some(kid) => kid,
+			None if Self::is_hmac(self.algorithm) => self.active_kid.clone(),
+			None => return Err(Self::invalid_token_error()),
+		};
+
+		let decoding_key = self.decoding_keys.get(&kid).ok_or_else(Self::invalid_token_error)?;
+
+		decode::<Claims>(token, decoding_key, &Validation::new(self.algorithm))
+			.map(|token_data| token_data.claims)
+			.map_err(|_| Self::invalid_token_error())
+	}
+
+	fn jwks(&self) -> &JwkSet {
+		&self.jwks
+	}
+}
@@ -0,0 +1,235 @@
+use crate::auth::entities::login_lockouts::Model as LoginLockoutModel;
+use crate::auth::repositories::login_lockout_repository::LoginLockoutRepositoryTrait;
+use crate::auth::services::confirmation_token_service::{ConfirmationTokenServiceTrait, TokenType};
+use crate::common::error::app_error::AppError;
+use crate::config::app_config::LoginProtectionConfig;
+use crate::di::AppConfigTrait;
+use crate::emails::services::emails_service::EmailsServiceTrait;
+use crate::i18n::setup::translate;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+const USERNAME_SCOPE: &str = "username";
+const IP_ADDRESS_SCOPE: &str = "ip_address";
+
+/// Counts failed logins per username and per IP address.
+///
+/// Every failure within `failure_window` doubles the time before the next attempt for the same username is
+/// accepted, earlier attempts are rejected with `Retry-After`. Reaching
+/// `max_failed_attempts` locks the username for `lockout_duration` and emails the owner an unlock
+/// link, reaching `max_failed_attempts_per_ip` locks the IP address without notifying anyone.
+pub struct LoginProtectionService {
+	login_lockout_repository: Arc<dyn LoginLockoutRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+	email_service: Arc<dyn EmailsServiceTrait>,
+	config: LoginProtectionConfig,
+}
+
+impl LoginProtectionService {
+	pub fn new(
+		login_lockout_repository: Arc<dyn LoginLockoutRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+		email_service: Arc<dyn EmailsServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let config = app_config.get_config().security.login_protection.clone();
+		Self {
+			login_lockout_repository,
+			users_repository,
+			confirmation_token_service,
+			email_service,
+			config,
+		}
+	}
+
+	fn is_locked(login_lockout: &LoginLockoutModel) -> bool {
+		login_lockout
+			.locked_until
+			.is_some_and(|locked_until| locked_until > Utc::now())
+	}
+
+	fn progressive_delay(&self, failed_attempts: i32) -> Duration {
+		if failed_attempts <= 0 {
+			return Duration::zero();
+		}
+
+		// Doubles with every failure: base, 2 * base, 4 * base, ... up to the configured maximum
+		let exponent = (failed_attempts - 1).min(16) as u32;
+		let delay_ms = self
+			.config
+			.progressive_delay_base_ms
+			.saturating_mul(1 << exponent)
+			.min(self.config.progressive_delay_max_ms);
+
+		Duration::milliseconds(delay_ms as i64)
+	}
+
+	async fn send_unlock_email(&self, username: &str) -> Result<(), AppError> {
+		// Unknown usernames are counted as well, but there is nobody to notify
+		let Ok(user) = self.users_repository.find_by_username(username).await else {
+			return Ok(());
+		};
+
+		let token = self
+			.confirmation_token_service
+			.generate_account_unlock_token(user.id, &user.email)
+			.await?;
+
+		self.email_service
+			.send_account_unlock(&user.email, &user.username, &token, self.config.lockout_duration / 60)
+			.await
+	}
+}
+
+#[async_trait]
+pub trait LoginProtectionServiceTrait: Send + Sync {
+	async fn check_login_allowed(&self, username: &str, ip_address: Option<&str>) -> Result<(), AppError>;
+	async fn record_failed_login(&self, username: &str, ip_address: Option<&str>) -> Result<(), AppError>;
+	async fn record_successful_login(&self, username: &str) -> Result<(), AppError>;
+	async fn unlock_account(&self, token: &str) -> Result<(), AppError>;
+	async fn find_all(&self) -> Result<Vec<LoginLockoutModel>, AppError>;
+	async fn clear(&self, id: i32) -> Result<(), AppError>;
+	async fn clean_expired_lockouts(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl LoginProtectionServiceTrait for LoginProtectionService {
+	async fn check_login_allowed(&self, username: &str, ip_address: Option<&str>) -> Result<(), AppError> {
+		if let Some(ip_address) = ip_address {
+			let ip_lockout = self
+				.login_lockout_repository
+				.find_by_scope_and_identifier(IP_ADDRESS_SCOPE, ip_address)
+				.await?;
+
+			if ip_lockout.as_ref().is_some_and(Self::is_locked) {
+				return Err(AppError::AuthenticationError(translate(
+					"auth.errors.too_many_login_attempts",
+				)));
+			}
+		}
+
+		let Some(login_lockout) = self
+			.login_lockout_repository
+			.find_by_scope_and_identifier(USERNAME_SCOPE, username)
+			.await?
+		else {
+			return Ok(());
+		};
+
+		if Self::is_locked(&login_lockout) {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_locked")));
+		}
+
+		let now = Utc::now();
+		let window_started_after = now - Duration::seconds(self.config.failure_window);
+		if login_lockout.window_started_at >= window_started_after
+			&& let Some(last_failed_at) = login_lockout.last_failed_at
+		{
+			// Rejecting instead of sleeping keeps attackers from tying up connections
+			let retry_at = last_failed_at.to_utc() + self.progressive_delay(login_lockout.failed_attempts);
+			if retry_at > now {
+				let retry_after = ((retry_at - now).num_milliseconds() as u64).div_ceil(1000);
+				return Err(AppError::TooManyRequests(retry_after));
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn record_failed_login(&self, username: &str, ip_address: Option<&str>) -> Result<(), AppError> {
+		let now = Utc::now();
+		let window_started_after = now - Duration::seconds(self.config.failure_window);
+		let locked_until = now + Duration::seconds(self.config.lockout_duration);
+
+		if let Some(ip_address) = ip_address {
+			let login_lockout = self
+				.login_lockout_repository
+				.record_failure(IP_ADDRESS_SCOPE, ip_address, window_started_after)
+				.await?;
+
+			if login_lockout.failed_attempts >= self.config.max_failed_attempts_per_ip
+				&& !Self::is_locked(&login_lockout)
+			{
+				tracing::warn!(
+					"Locking login attempts from {} after {} failures",
+					ip_address,
+					login_lockout.failed_attempts
+				);
+				self.login_lockout_repository
+					.lock(login_lockout.id, locked_until)
+					.await?;
+			}
+		}
+
+		let login_lockout = self
+			.login_lockout_repository
+			.record_failure(USERNAME_SCOPE, username, window_started_after)
+			.await?;
+
+		if login_lockout.failed_attempts >= self.config.max_failed_attempts && !Self::is_locked(&login_lockout) {
+			tracing::warn!(
+				"Locking account {} after {} failed login attempts",
+				username,
+				login_lockout.failed_attempts
+			);
+			self.login_lockout_repository
+				.lock(login_lockout.id, locked_until)
+				.await?;
+
+			if let Err(e) = self.send_unlock_email(username).await {
+				tracing::error!("Failed to send account unlock email: {:?}", e);
+			}
+		}
+
+		Ok(())
+	}
+
+	async fn record_successful_login(&self, username: &str) -> Result<(), AppError> {
+		self.login_lockout_repository
+			.delete_by_scope_and_identifier(USERNAME_SCOPE, username)
+			.await
+	}
+
+	async fn unlock_account(&self, token: &str) -> Result<(), AppError> {
+		let claims = self.confirmation_token_service.validate_token(token).await?;
+
+		if !matches!(claims.token_type, TokenType::AccountUnlock) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.invalid_token_type",
+			)));
+		}
+
+		let user = self.users_repository.find_by_id(claims.sub).await?;
+		let login_lockout = self
+			.login_lockout_repository
+			.find_by_scope_and_identifier(USERNAME_SCOPE, &user.username)
+			.await?
+			.ok_or_else(|| AppError::AuthorizationError(translate("auth.errors.invalid_token")))?;
+
+		// A link from an earlier lockout must not clear a newer one
+		if claims.iat < login_lockout.window_started_at.timestamp() {
+			return Err(AppError::AuthorizationError(translate("auth.errors.invalid_token")));
+		}
+
+		self.login_lockout_repository.delete_by_id(login_lockout.id).await
+	}
+
+	async fn find_all(&self) -> Result<Vec<LoginLockoutModel>, AppError> {
+		self.login_lockout_repository.find_all().await
+	}
+
+	async fn clear(&self, id: i32) -> Result<(), AppError> {
+		self.login_lockout_repository.delete_by_id(id).await
+	}
+
+	async fn clean_expired_lockouts(&self) -> Result<(), AppError> {
+		let window_started_before = Utc::now() - Duration::seconds(self.config.failure_window);
+		self.login_lockout_repository
+			.delete_expired(window_started_before)
+			.await
+	}
+}
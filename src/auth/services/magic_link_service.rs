@@ -0,0 +1,117 @@
+use crate::auth::dto::magic_link_dto::MagicLinkDto;
+use crate::auth::services::confirmation_token_service::{ConfirmationTokenServiceTrait, TokenType};
+use crate::common::error::app_error::AppError;
+use crate::di::AppConfigTrait;
+use crate::emails::services::emails_service::EmailsServiceTrait;
+use crate::i18n::setup::translate;
+use crate::users::entities::users::{self, Entity as User, Model as UserModel};
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use std::sync::Arc;
+
+/// Passwordless sign-in with a link sent by email.
+///
+/// Only the jti of the most recent link is stored on the user, so requesting a new link invalidates
+/// the previous one and consuming a link clears it.
+pub struct MagicLinkService {
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+	email_service: Arc<dyn EmailsServiceTrait>,
+	magic_login_token_expires_in: i64,
+}
+
+impl MagicLinkService {
+	pub fn new(
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+		email_service: Arc<dyn EmailsServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let magic_login_token_expires_in = app_config.get_config().security.tokens.magic_login_token.expires_in;
+		Self {
+			users_repository,
+			confirmation_token_service,
+			email_service,
+			magic_login_token_expires_in,
+		}
+	}
+}
+
+#[async_trait]
+pub trait MagicLinkServiceTrait: Send + Sync {
+	async fn request_magic_link(&self, dto: MagicLinkDto) -> Result<(), AppError>;
+	async fn consume_magic_link(&self, token: &str) -> Result<UserModel, AppError>;
+}
+
+#[async_trait]
+impl MagicLinkServiceTrait for MagicLinkService {
+	async fn request_magic_link(&self, dto: MagicLinkDto) -> Result<(), AppError> {
+		// Respond the same way whether or not the address belongs to an account that can sign in
+		let user = match self.users_repository.find_by_email(&dto.email).await {
+			Ok(user) if user.is_active && user.is_email_confirmed => user,
+			Ok(_) | Err(AppError::NotFound) => return Ok(()),
+			Err(e) => return Err(e),
+		};
+
+		let (token, claims) = self
+			.confirmation_token_service
+			.generate_magic_login_token(user.id, &user.email)
+			.await?;
+
+		let expiry = DateTime::<Utc>::from_timestamp(claims.exp, 0).ok_or(AppError::InternalError)?;
+		let user_active_model = users::ActiveModel {
+			id: Set(user.id),
+			magic_login_token_jti: Set(Some(claims.jti)),
+			magic_login_token_expiry: Set(Some(expiry.into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		self.email_service
+			.send_magic_login(
+				&user.email,
+				&user.username,
+				&token,
+				self.magic_login_token_expires_in / 60,
+			)
+			.await
+	}
+
+	async fn consume_magic_link(&self, token: &str) -> Result<UserModel, AppError> {
+		let claims = self.confirmation_token_service.validate_token(token).await?;
+
+		if !matches!(claims.token_type, TokenType::MagicLogin) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.invalid_token_type",
+			)));
+		}
+
+		// Clearing the jti only if it is still stored makes concurrent uses of the same link fail
+		let result = User::update_many()
+			.col_expr(users::Column::MagicLoginTokenJti, Expr::value(Option::<String>::None))
+			.col_expr(
+				users::Column::MagicLoginTokenExpiry,
+				Expr::value(Option::<DateTime<Utc>>::None),
+			)
+			.filter(users::Column::Id.eq(claims.sub))
+			.filter(users::Column::MagicLoginTokenJti.eq(claims.jti.as_str()))
+			.filter(users::Column::MagicLoginTokenExpiry.gt(Utc::now()))
+			.exec(self.users_repository.get_db())
+			.await?;
+
+		if result.rows_affected == 0 {
+			return Err(AppError::AuthorizationError(translate("auth.errors.invalid_token")));
+		}
+
+		let user = self.users_repository.find_by_id(claims.sub).await?;
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		Ok(user)
+	}
+}
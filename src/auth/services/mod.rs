@@ -1,3 +1,12 @@
 pub mod auth_service;
 pub mod confirmation_token_service;
+pub mod impersonation_service;
+pub mod jwt_keys_service;
+pub mod login_protection_service;
+pub mod magic_link_service;
+pub mod oidc_service;
+pub mod personal_access_token_service;
 pub mod refresh_token_service;
+pub mod token_revocation_service;
+pub mod two_factor_service;
+pub mod webauthn_service;
@@ -0,0 +1,429 @@
+use crate::auth::repositories::user_identity_repository::UserIdentityRepositoryTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::utils::token_utils::generate_opaque_token;
+use crate::config::app_config::OidcProviderConfig;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::roles::services::user_roles_service::UserRolesServiceTrait;
+use crate::users::dto::create_user_dto::CreateUserDto;
+use crate::users::entities::users::{self, Model as UserModel};
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use crate::users::services::users_service::UsersServiceTrait;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use sea_orm::{ActiveModelTrait, Set, TransactionTrait};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+const MAX_USERNAME_ATTEMPTS: usize = 10;
+
+/// Endpoints read from the provider's discovery document.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+	issuer: String,
+	authorization_endpoint: String,
+	token_endpoint: String,
+	jwks_uri: String,
+}
+
+#[derive(Debug, Clone)]
+struct Provider {
+	metadata: ProviderMetadata,
+	jwks: JwkSet,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+	id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+	sub: String,
+	nonce: Option<String>,
+	email: Option<String>,
+	#[serde(default)]
+	email_verified: bool,
+	preferred_username: Option<String>,
+}
+
+/// Everything the browser has to carry between the authorization request and the callback.
+pub struct OidcAuthorizationRequest {
+	pub authorization_url: String,
+	pub state: String,
+	pub nonce: String,
+	pub code_verifier: String,
+}
+
+/// Sign-in with external OpenID Connect providers using the authorization code flow with PKCE.
+///
+/// Provider metadata and signing keys are fetched from the discovery document on first use and kept
+/// in memory; the keys are fetched again when an ID token is signed with an unknown key.
+pub struct OidcService {
+	user_identity_repository: Arc<dyn UserIdentityRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	users_service: Arc<dyn UsersServiceTrait>,
+	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	app_config: Arc<dyn AppConfigTrait>,
+	http_client: reqwest::Client,
+	providers: RwLock<HashMap<String, Provider>>,
+}
+
+impl OidcService {
+	pub fn new(
+		user_identity_repository: Arc<dyn UserIdentityRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		users_service: Arc<dyn UsersServiceTrait>,
+		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		Self {
+			user_identity_repository,
+			users_repository,
+			users_service,
+			user_roles_service,
+			app_config,
+			http_client: reqwest::Client::new(),
+			providers: RwLock::new(HashMap::new()),
+		}
+	}
+
+	fn provider_config(&self, provider: &str) -> Result<&OidcProviderConfig, AppError> {
+		self.app_config
+			.get_config()
+			.security
+			.oidc
+			.providers
+			.get(provider)
+			.ok_or(AppError::NotFound)
+	}
+
+	fn redirect_uri(&self, provider: &str) -> String {
+		format!(
+			"{}/api/auth/oidc/{}/callback",
+			self.app_config.get_config().server.app_url,
+			provider
+		)
+	}
+
+	fn login_failed_error() -> AppError {
+		AppError::AuthenticationError(translate("auth.errors.oidc_login_failed"))
+	}
+
+	async fn fetch_json<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, AppError> {
+		let response = self
+			.http_client
+			.get(url)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| {
+				tracing::error!("OIDC request to {} failed: {}", url, e);
+				Self::login_failed_error()
+			})?;
+
+		response.json::<T>().await.map_err(|e| {
+			tracing::error!("Invalid OIDC response from {}: {}", url, e);
+			Self::login_failed_error()
+		})
+	}
+
+	async fn provider(&self, provider: &str, refresh_keys: bool) -> Result<Provider, AppError> {
+		if !refresh_keys
+			&& let Some(cached) = self
+				.providers
+				.read()
+				.map_err(|_| AppError::InternalError)?
+				.get(provider)
+		{
+			return Ok(cached.clone());
+		}
+
+		let config = self.provider_config(provider)?;
+		let discovery_url = format!(
+			"{}/.well-known/openid-configuration",
+			config.issuer_url.trim_end_matches('/')
+		);
+		let metadata: ProviderMetadata = self.fetch_json(&discovery_url).await?;
+
+		// The discovery document must describe the configured issuer, not some other one
+		if metadata.issuer.trim_end_matches('/') != config.issuer_url.trim_end_matches('/') {
+			tracing::error!(
+				"OIDC issuer mismatch for {}: expected {}, got {}",
+				provider,
+				config.issuer_url,
+				metadata.issuer
+			);
+			return Err(Self::login_failed_error());
+		}
+
+		let jwks: JwkSet = self.fetch_json(&metadata.jwks_uri).await?;
+		let fetched = Provider { metadata, jwks };
+
+		self.providers
+			.write()
+			.map_err(|_| AppError::InternalError)?
+			.insert(provider.to_string(), fetched.clone());
+
+		Ok(fetched)
+	}
+
+	async fn validate_id_token(
+		&self,
+		provider_name: &str,
+		provider: Provider,
+		id_token: &str,
+		expected_nonce: &str,
+	) -> Result<IdTokenClaims, AppError> {
+		let config = self.provider_config(provider_name)?;
+		let header = decode_header(id_token).map_err(|_| Self::login_failed_error())?;
+
+		// ID tokens signed with the client secret are not supported
+		if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+			return Err(Self::login_failed_error());
+		}
+
+		let find_jwk = |jwks: &JwkSet| match &header.kid {
+			Some(kid) => jwks.find(kid).cloned(),
+			None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+			None => None,
+		};
+
+		// An unknown key id usually means the provider has rotated its keys
+		let jwk = match find_jwk(&provider.jwks) {
+			Some(jwk) => jwk,
+			None => find_jwk(&self.provider(provider_name, true).await?.jwks).ok_or_else(Self::login_failed_error)?,
+		};
+
+		let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|_| Self::login_failed_error())?;
+
+		let mut validation = Validation::new(header.alg);
+		validation.set_audience(&[&config.client_id]);
+		validation.set_issuer(&[&provider.metadata.issuer]);
+
+		let claims = decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+			.map(|token_data| token_data.claims)
+			.map_err(|_| Self::login_failed_error())?;
+
+		if claims.nonce.as_deref() != Some(expected_nonce) {
+			return Err(Self::login_failed_error());
+		}
+
+		Ok(claims)
+	}
+
+	/// Builds a username that passes validation and is not taken yet.
+	async fn available_username(&self, claims: &IdTokenClaims, email: &str) -> Result<String, AppError> {
+		let base: String = claims
+			.preferred_username
+			.as_deref()
+			.unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+			.chars()
+			.filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+			.take(40)
+			.collect();
+		let base = if base.chars().count() < 3 {
+			"user".to_string()
+		} else {
+			base
+		};
+
+		let mut candidate = base.clone();
+		for _ in 0..MAX_USERNAME_ATTEMPTS {
+			if matches!(
+				self.users_repository.find_by_username(&candidate).await,
+				Err(AppError::NotFound)
+			) {
+				return Ok(candidate);
+			}
+
+			candidate = format!("{}_{}", base, &generate_opaque_token()[..6]);
+		}
+
+		Err(AppError::InternalError)
+	}
+
+	async fn provision_user(&self, provider: &str, claims: &IdTokenClaims, email: &str) -> Result<UserModel, AppError> {
+		let username = self.available_username(claims, email).await?;
+		let transaction = self.users_repository.get_db().begin().await?;
+
+		// The account can only be used through the provider until the user sets a password
+		let create_user_dto = CreateUserDto {
+			username,
+			email: email.to_string(),
+			password: generate_opaque_token(),
+		};
+
+		let user = self
+			.users_service
+			.create_in_transaction(&transaction, create_user_dto)
+			.await?;
+
+		self.user_roles_service
+			.assign_user_role_in_transaction(&transaction, user.id)
+			.await?;
+
+		// The provider has already verified the email address
+		let user = users::ActiveModel {
+			id: Set(user.id),
+			is_email_confirmed: Set(true),
+			..Default::default()
+		}
+		.update(&transaction)
+		.await?;
+
+		self.user_identity_repository
+			.create_in_transaction(&transaction, user.id, provider, &claims.sub, Some(email.to_string()))
+			.await?;
+
+		transaction.commit().await?;
+
+		Ok(user)
+	}
+}
+
+#[async_trait]
+pub trait OidcServiceTrait: Send + Sync {
+	async fn authorization_request(&self, provider: &str) -> Result<OidcAuthorizationRequest, AppError>;
+	async fn authenticate(
+		&self,
+		provider: &str,
+		code: &str,
+		code_verifier: &str,
+		nonce: &str,
+	) -> Result<UserModel, AppError>;
+}
+
+#[async_trait]
+impl OidcServiceTrait for OidcService {
+	async fn authorization_request(&self, provider: &str) -> Result<OidcAuthorizationRequest, AppError> {
+		let config = self.provider_config(provider)?;
+		let metadata = self.provider(provider, false).await?.metadata;
+
+		let state = generate_opaque_token();
+		let nonce = generate_opaque_token();
+		let code_verifier = generate_opaque_token();
+		let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+		let authorization_url = format!(
+			"{}{}response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&nonce={}&code_challenge={}&code_challenge_method=S256",
+			metadata.authorization_endpoint,
+			if metadata.authorization_endpoint.contains('?') {
+				'&'
+			} else {
+				'?'
+			},
+			urlencoding::encode(&config.client_id),
+			urlencoding::encode(&self.redirect_uri(provider)),
+			urlencoding::encode(&config.scopes),
+			state,
+			nonce,
+			code_challenge,
+		);
+
+		Ok(OidcAuthorizationRequest {
+			authorization_url,
+			state,
+			nonce,
+			code_verifier,
+		})
+	}
+
+	async fn authenticate(
+		&self,
+		provider: &str,
+		code: &str,
+		code_verifier: &str,
+		nonce: &str,
+	) -> Result<UserModel, AppError> {
+		let config = self.provider_config(provider)?;
+		let provider_metadata = self.provider(provider, false).await?;
+		let redirect_uri = self.redirect_uri(provider);
+
+		let params = [
+			("grant_type", "authorization_code"),
+			("code", code),
+			("redirect_uri", redirect_uri.as_str()),
+			("client_id", config.client_id.as_str()),
+			("client_secret", config.client_secret.as_str()),
+			("code_verifier", code_verifier),
+		];
+
+		let token_response: TokenResponse = self
+			.http_client
+			.post(&provider_metadata.metadata.token_endpoint)
+			.form(&params)
+			.send()
+			.await
+			.and_then(|response| response.error_for_status())
+			.map_err(|e| {
+				tracing::warn!("OIDC code exchange with {} failed: {}", provider, e);
+				Self::login_failed_error()
+			})?
+			.json()
+			.await
+			.map_err(|_| Self::login_failed_error())?;
+
+		let claims = self
+			.validate_id_token(provider, provider_metadata, &token_response.id_token, nonce)
+			.await?;
+
+		if let Some(user_identity) = self
+			.user_identity_repository
+			.find_by_provider_and_subject(provider, &claims.sub)
+			.await?
+		{
+			let user = self.users_repository.find_by_id(user_identity.user_id).await?;
+
+			if !user.is_active {
+				return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+			}
+
+			self.user_identity_repository
+				.update_last_login_at(user_identity.id)
+				.await?;
+
+			return Ok(user);
+		}
+
+		// Linking by email is only safe when the provider vouches for the address
+		let email = match (&claims.email, claims.email_verified) {
+			(Some(email), true) => email.clone(),
+			_ => {
+				return Err(AppError::AuthenticationError(translate(
+					"auth.errors.oidc_email_not_verified",
+				)));
+			}
+		};
+
+		match self.users_repository.find_by_email(&email).await {
+			Ok(user) => {
+				if !user.is_active {
+					return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+				}
+
+				// Otherwise whoever registered the address first, without owning it, would share the account
+				if !user.is_email_confirmed {
+					return Err(AppError::AuthenticationError(translate(
+						"auth.errors.oidc_email_not_confirmed_locally",
+					)));
+				}
+
+				let transaction = self.users_repository.get_db().begin().await?;
+				self.user_identity_repository
+					.create_in_transaction(&transaction, user.id, provider, &claims.sub, Some(email))
+					.await?;
+				transaction.commit().await?;
+
+				Ok(user)
+			}
+			Err(AppError::NotFound) => self.provision_user(provider, &claims, &email).await,
+			Err(e) => Err(e),
+		}
+	}
+}
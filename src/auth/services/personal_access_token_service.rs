@@ -0,0 +1,235 @@
+use crate::auth::dto::create_personal_access_token_dto::CreatePersonalAccessTokenDto;
+use crate::auth::entities::personal_access_tokens::Model as PersonalAccessTokenModel;
+use crate::auth::repositories::personal_access_token_repository::PersonalAccessTokenRepositoryTrait;
+use crate::auth::services::auth_service::Claims;
+use crate::common::error::app_error::AppError;
+use crate::common::utils::token_utils::{generate_opaque_token, hash_token};
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::roles::services::user_roles_service::UserRolesServiceTrait;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Prefix telling personal access tokens apart from JWTs in the `Authorization` header.
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "pat_";
+
+/// How often `last_used_at` is written, so busy clients do not update the row on every request.
+const LAST_USED_AT_RESOLUTION_SECONDS: i64 = 60;
+
+#[derive(Debug, Serialize)]
+pub struct PersonalAccessTokenResponse {
+	pub id: i32,
+	pub name: String,
+	pub scopes: Vec<String>,
+	pub expires_at: Option<DateTimeWithTimeZone>,
+	pub last_used_at: Option<DateTimeWithTimeZone>,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+impl From<PersonalAccessTokenModel> for PersonalAccessTokenResponse {
+	fn from(model: PersonalAccessTokenModel) -> Self {
+		Self {
+			id: model.id,
+			name: model.name,
+			scopes: serde_json::from_value(model.scopes).unwrap_or_default(),
+			expires_at: model.expires_at,
+			last_used_at: model.last_used_at,
+			created_at: model.created_at,
+		}
+	}
+}
+
+/// Returned once on creation, the plain token cannot be retrieved afterwards.
+#[derive(Debug, Serialize)]
+pub struct CreatedPersonalAccessTokenResponse {
+	#[serde(flatten)]
+	pub details: PersonalAccessTokenResponse,
+	pub token: String,
+}
+
+#[derive(Clone)]
+pub struct PersonalAccessTokenService {
+	personal_access_token_repository: Arc<dyn PersonalAccessTokenRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	personal_access_token_secret: String,
+	jwt_access_token_expires_in: i64,
+}
+
+impl PersonalAccessTokenService {
+	pub fn new(
+		personal_access_token_repository: Arc<dyn PersonalAccessTokenRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let tokens_config = &app_config.get_config().security.tokens;
+		Self {
+			personal_access_token_repository,
+			users_repository,
+			user_roles_service,
+			personal_access_token_secret: tokens_config.personal_access_token.secret.clone(),
+			jwt_access_token_expires_in: tokens_config.jwt_access_token.expires_in,
+		}
+	}
+
+	fn hash_personal_access_token(&self, token: &str) -> String {
+		hash_token(&self.personal_access_token_secret, token)
+	}
+
+	fn invalid_token_error() -> AppError {
+		AppError::AuthenticationError(translate("auth.errors.invalid_token"))
+	}
+}
+
+#[async_trait]
+pub trait PersonalAccessTokenServiceTrait: Send + Sync {
+	async fn create(
+		&self,
+		claims: &Claims,
+		dto: CreatePersonalAccessTokenDto,
+	) -> Result<CreatedPersonalAccessTokenResponse, AppError>;
+	async fn find_all(&self, user_id: i32) -> Result<Vec<PersonalAccessTokenResponse>, AppError>;
+	async fn revoke(&self, user_id: i32, id: i32) -> Result<(), AppError>;
+	async fn authenticate(&self, token: &str) -> Result<Claims, AppError>;
+}
+
+#[async_trait]
+impl PersonalAccessTokenServiceTrait for PersonalAccessTokenService {
+	async fn create(
+		&self,
+		claims: &Claims,
+		dto: CreatePersonalAccessTokenDto,
+	) -> Result<CreatedPersonalAccessTokenResponse, AppError> {
+		// Otherwise a short-lived token could mint one that never expires
+		if claims.is_personal_access_token() {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.personal_access_token_forbidden",
+			)));
+		}
+
+		// A token can never grant more than the caller currently has, inherited roles included
+		let effective_roles = self
+			.user_roles_service
+			.resolve_effective_role_names(&claims.roles)
+			.await?;
+		if dto.scopes.iter().any(|scope| !effective_roles.contains(scope)) {
+			return Err(AppError::AuthorizationError(translate(
+				"auth.errors.insufficient_permissions",
+			)));
+		}
+
+		let mut scopes = dto.scopes;
+		scopes.sort();
+		scopes.dedup();
+
+		let token = format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, generate_opaque_token());
+		let expires_at = dto.expires_in_days.map(|days| Utc::now() + Duration::days(days));
+
+		let personal_access_token = self
+			.personal_access_token_repository
+			.create(
+				claims.sub,
+				&dto.name,
+				&self.hash_personal_access_token(&token),
+				serde_json::json!(scopes),
+				expires_at,
+			)
+			.await?;
+
+		Ok(CreatedPersonalAccessTokenResponse {
+			details: personal_access_token.into(),
+			token,
+		})
+	}
+
+	async fn find_all(&self, user_id: i32) -> Result<Vec<PersonalAccessTokenResponse>, AppError> {
+		let personal_access_tokens = self.personal_access_token_repository.find_by_user_id(user_id).await?;
+
+		Ok(personal_access_tokens.into_iter().map(Into::into).collect())
+	}
+
+	async fn revoke(&self, user_id: i32, id: i32) -> Result<(), AppError> {
+		self.personal_access_token_repository
+			.delete_by_id_and_user_id(id, user_id)
+			.await
+	}
+
+	async fn authenticate(&self, token: &str) -> Result<Claims, AppError> {
+		let personal_access_token = self
+			.personal_access_token_repository
+			.find_by_token_hash(&self.hash_personal_access_token(token))
+			.await?
+			.ok_or_else(Self::invalid_token_error)?;
+
+		let now = Utc::now();
+
+		if personal_access_token
+			.expires_at
+			.is_some_and(|expires_at| expires_at < now)
+		{
+			return Err(AppError::AuthenticationError(translate("auth.errors.expired_token")));
+		}
+
+		let user = self
+			.users_repository
+			.find_by_id(personal_access_token.user_id)
+			.await
+			.map_err(|_| AppError::AuthenticationError(translate("auth.errors.user_not_found")))?;
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		if !user.is_email_confirmed {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.email_not_confirmed",
+			)));
+		}
+
+		// Tokens created before e.g. a password change or "log out everywhere" are no longer accepted
+		if user
+			.tokens_valid_after
+			.is_some_and(|valid_after| personal_access_token.created_at <= valid_after)
+		{
+			return Err(AppError::AuthenticationError(translate("auth.errors.token_revoked")));
+		}
+
+		// Roles removed from the user since the token was created are no longer granted
+		let scopes: Vec<String> = serde_json::from_value(personal_access_token.scopes.clone()).unwrap_or_default();
+		let roles = self
+			.user_roles_service
+			.get_effective_roles(user.id)
+			.await?
+			.into_iter()
+			.map(|role| role.name)
+			.filter(|role| scopes.contains(role))
+			.collect();
+
+		let last_used_recently = personal_access_token.last_used_at.is_some_and(|last_used_at| {
+			now - last_used_at.to_utc() < Duration::seconds(LAST_USED_AT_RESOLUTION_SECONDS)
+		});
+		if !last_used_recently {
+			self.personal_access_token_repository
+				.update_last_used_at(personal_access_token.id)
+				.await?;
+		}
+
+		Ok(Claims {
+			sub: user.id,
+			username: user.username,
+			email: user.email,
+			roles,
+			exp: (now + Duration::seconds(self.jwt_access_token_expires_in)).timestamp(),
+			iat: now.timestamp(),
+			jti: format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, personal_access_token.id),
+			client_id: None,
+			act: None,
+			auth_time: None,
+		})
+	}
+}
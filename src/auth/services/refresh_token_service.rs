@@ -1,34 +1,45 @@
 use crate::auth::dto::access_token_dto::AccessTokenDto;
+use crate::auth::entities::refresh_tokens::Model as RefreshTokenModel;
 use crate::auth::repositories::refresh_token_repository::RefreshTokenRepositoryTrait;
-use crate::common::enums::role_enum::RoleEnum;
+use crate::auth::services::auth_service::Claims;
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
 use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use crate::common::utils::token_utils;
 use crate::di::AppConfigTrait;
+use crate::emails::services::emails_service::EmailsServiceTrait;
 use crate::i18n::setup::translate;
 use crate::roles::services::user_roles_service::UserRolesServiceTrait;
+use crate::users::entities::users::Model as UserModel;
 use async_trait::async_trait;
 use chrono::Utc;
-use jsonwebtoken::{EncodingKey, Header, encode};
-use sea_orm::DatabaseTransaction;
-use serde::{Deserialize, Serialize};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{DatabaseTransaction, TransactionTrait};
+use serde::Serialize;
 use std::sync::Arc;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Claims {
-	pub sub: i32,
-	pub username: String,
-	pub email: String,
-	pub roles: Vec<RoleEnum>,
-	pub exp: i64,
-	pub iat: i64,
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+	pub id: String,
+	pub label: Option<String>,
+	pub user_agent: Option<String>,
+	pub ip_address: Option<String>,
+	pub created_at: DateTimeWithTimeZone,
+	pub last_used_at: Option<DateTimeWithTimeZone>,
+	pub expires_at: DateTimeWithTimeZone,
+	pub current: bool,
 }
 
 #[derive(Clone)]
 pub struct RefreshTokenService {
 	refresh_token_repository: Arc<dyn RefreshTokenRepositoryTrait>,
 	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	email_service: Arc<dyn EmailsServiceTrait>,
+	jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
 	app_config: Arc<dyn AppConfigTrait>,
-	jwt_access_token_secret: String,
 	jwt_access_token_expires_in: i64,
+	jwt_refresh_token_secret: String,
 	jwt_refresh_token_expires_in: i64,
 }
 
@@ -36,30 +47,59 @@ impl RefreshTokenService {
 	pub fn new(
 		refresh_token_repository: Arc<dyn RefreshTokenRepositoryTrait>,
 		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		email_service: Arc<dyn EmailsServiceTrait>,
+		jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
 		app_config: Arc<dyn AppConfigTrait>,
 	) -> Self {
-		let jwt_access_token_secret = app_config.get_config().security.tokens.jwt_access_token.secret.clone();
 		let jwt_access_token_expires_in = app_config.get_config().security.tokens.jwt_access_token.expires_in;
+		let jwt_refresh_token_secret = app_config.get_config().security.tokens.jwt_refresh_token.secret.clone();
 		let jwt_refresh_token_expires_in = app_config.get_config().security.tokens.jwt_refresh_token.expires_in;
 
 		Self {
 			refresh_token_repository,
 			user_roles_service,
+			email_service,
+			jwt_keys_service,
 			app_config,
-			jwt_access_token_secret,
 			jwt_access_token_expires_in,
+			jwt_refresh_token_secret,
 			jwt_refresh_token_expires_in,
 		}
 	}
-	async fn generate_access_token(&self, user_id: i32) -> Result<String, AppError> {
-		let user = self.refresh_token_repository.find_user_by_id(user_id).await?;
 
+	fn hash_refresh_token(&self, refresh_token: &str) -> String {
+		token_utils::hash_token(&self.jwt_refresh_token_secret, refresh_token)
+	}
+
+	/// The access token may already be expired, so the account state is checked here instead of by `JwtAuth`.
+	fn ensure_user_can_refresh(user: &UserModel, token: &RefreshTokenModel) -> Result<(), AppError> {
+		// Tokens issued before e.g. a password change or "log out everywhere" are no longer accepted
+		if Self::is_issued_before(token.created_at, user.tokens_valid_after) {
+			return Err(AppError::AuthenticationError(translate("auth.errors.token_revoked")));
+		}
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		if !user.is_email_confirmed {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.email_not_confirmed",
+			)));
+		}
+
+		Ok(())
+	}
+
+	fn is_issued_before(created_at: DateTimeWithTimeZone, tokens_valid_after: Option<DateTimeWithTimeZone>) -> bool {
+		tokens_valid_after.is_some_and(|valid_after| created_at <= valid_after)
+	}
+
+	async fn generate_access_token(&self, user: &UserModel) -> Result<(String, Vec<String>), AppError> {
+		let user_id = user.id;
 		let user_roles = self.user_roles_service.get_user_roles(user_id).await?;
 
-		let role_enums: Vec<RoleEnum> = user_roles
-			.into_iter()
-			.filter_map(|role| RoleEnum::from_str(&role.name))
-			.collect();
+		let role_names: Vec<String> = user_roles.into_iter().map(|role| role.name).collect();
 
 		let now = Utc::now();
 		let expires_at = now + chrono::Duration::seconds(self.jwt_access_token_expires_in);
@@ -68,40 +108,81 @@ impl RefreshTokenService {
 			sub: user_id,
 			username: user.username.clone(),
 			email: user.email.clone(),
-			roles: role_enums,
+			roles: role_names.clone(),
 			exp: expires_at.timestamp(),
 			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+			client_id: None,
+			act: None,
+			auth_time: None,
 		};
 
-		encode(
-			&Header::default(),
-			&claims,
-			&EncodingKey::from_secret(self.jwt_access_token_secret.as_bytes()),
-		)
-		.map_err(|_| AppError::InternalError)
+		let access_token = self.jwt_keys_service.encode(&claims)?;
+
+		Ok((access_token, role_names))
+	}
+
+	async fn handle_token_reuse(&self, token: &RefreshTokenModel) -> Result<(), AppError> {
+		tracing::warn!(
+			"Rotated refresh token reused for user {}, revoking token family {}",
+			token.user_id,
+			token.family_id
+		);
+
+		self.refresh_token_repository.revoke_family(&token.family_id).await?;
+
+		let user = self.refresh_token_repository.find_user_by_id(token.user_id).await?;
+		if let Err(e) = self
+			.email_service
+			.send_refresh_token_reuse_alert(&user.email, &user.username)
+			.await
+		{
+			tracing::error!("Failed to send refresh token reuse alert: {:?}", e);
+		}
+
+		Ok(())
 	}
 }
 
 #[async_trait]
 pub trait RefreshTokenServiceTrait: Send + Sync {
-	async fn generate_refresh_token(&self, user_id: i32) -> Result<String, AppError>;
+	async fn generate_refresh_token(&self, user_id: i32, client_info: &ClientInfo) -> Result<String, AppError>;
 	async fn generate_refresh_token_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
 		user_id: i32,
+		client_info: &ClientInfo,
 	) -> Result<String, AppError>;
-	async fn refresh_token(&self, user_id: i32, refresh_token: String) -> Result<AccessTokenDto, AppError>;
+	async fn refresh_token(
+		&self,
+		refresh_token: String,
+		client_info: &ClientInfo,
+	) -> Result<(AccessTokenDto, String), AppError>;
 	async fn invalidate_refresh_token(&self, user_id: i32, refresh_token: String) -> Result<(), AppError>;
 	async fn invalidate_all_user_tokens(&self, user_id: i32) -> Result<(), AppError>;
 	async fn clean_expired_tokens(&self) -> Result<(), AppError>;
+	async fn list_sessions(
+		&self,
+		user_id: i32,
+		current_refresh_token: Option<String>,
+	) -> Result<Vec<SessionResponse>, AppError>;
+	async fn revoke_session(&self, user_id: i32, session_id: &str) -> Result<(), AppError>;
+	async fn rename_session(&self, user_id: i32, session_id: &str, label: &str) -> Result<(), AppError>;
 }
 
 #[async_trait]
 impl RefreshTokenServiceTrait for RefreshTokenService {
-	async fn generate_refresh_token(&self, user_id: i32) -> Result<String, AppError> {
-		let (_, token) = self
-			.refresh_token_repository
-			.create(user_id, self.jwt_refresh_token_expires_in)
+	async fn generate_refresh_token(&self, user_id: i32, client_info: &ClientInfo) -> Result<String, AppError> {
+		let family_id = Uuid::new_v4().to_string();
+		let token = token_utils::generate_opaque_token();
+		self.refresh_token_repository
+			.create(
+				user_id,
+				&self.hash_refresh_token(&token),
+				&family_id,
+				self.jwt_refresh_token_expires_in,
+				client_info,
+			)
 			.await?;
 		Ok(token)
 	}
@@ -110,35 +191,105 @@ impl RefreshTokenServiceTrait for RefreshTokenService {
 		&self,
 		transaction: &DatabaseTransaction,
 		user_id: i32,
+		client_info: &ClientInfo,
 	) -> Result<String, AppError> {
-		let (_, token) = self
-			.refresh_token_repository
-			.create_in_transaction(transaction, user_id, self.jwt_refresh_token_expires_in)
+		let family_id = Uuid::new_v4().to_string();
+		let token = token_utils::generate_opaque_token();
+		self.refresh_token_repository
+			.create_in_transaction(
+				transaction,
+				user_id,
+				&self.hash_refresh_token(&token),
+				&family_id,
+				self.jwt_refresh_token_expires_in,
+				client_info,
+			)
 			.await?;
 		Ok(token)
 	}
 
-	async fn refresh_token(&self, user_id: i32, refresh_token: String) -> Result<AccessTokenDto, AppError> {
+	async fn refresh_token(
+		&self,
+		refresh_token: String,
+		client_info: &ClientInfo,
+	) -> Result<(AccessTokenDto, String), AppError> {
 		let token = self
 			.refresh_token_repository
-			.find_by_token_and_user_id(&refresh_token, user_id)
+			.find_by_token_hash(&self.hash_refresh_token(&refresh_token))
 			.await
 			.map_err(|_| AppError::AuthenticationError(translate("auth.errors.invalid_refresh_token")))?;
 
+		if token.revoked_at.is_some() {
+			// A token that was already rotated is being presented again, so it may have been stolen
+			if token.replaced_by_token_id.is_some() {
+				self.handle_token_reuse(&token).await?;
+				return Err(AppError::AuthenticationError(translate(
+					"auth.errors.refresh_token_reused",
+				)));
+			}
+
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.invalid_refresh_token",
+			)));
+		}
+
 		if !self.refresh_token_repository.is_token_valid(&token).await {
 			return Err(AppError::AuthenticationError(translate(
 				"auth.errors.expired_refresh_token",
 			)));
 		}
 
-		let access_token = self.generate_access_token(user_id).await?;
+		let transaction = self.refresh_token_repository.get_db().begin().await?;
+
+		// Checked before rotating, so a refused refresh leaves the token untouched
+		let user = self
+			.refresh_token_repository
+			.find_user_by_id_in_transaction(&transaction, token.user_id)
+			.await?;
+		Self::ensure_user_can_refresh(&user, &token)?;
+
+		let new_refresh_token = token_utils::generate_opaque_token();
+		let new_token_model = self
+			.refresh_token_repository
+			.create_rotated_in_transaction(
+				&transaction,
+				&token,
+				&self.hash_refresh_token(&new_refresh_token),
+				self.jwt_refresh_token_expires_in,
+				client_info,
+			)
+			.await?;
+
+		let rotated = self
+			.refresh_token_repository
+			.mark_rotated_in_transaction(&transaction, token.id, new_token_model.id)
+			.await?;
+
+		if !rotated {
+			transaction.rollback().await?;
+			self.handle_token_reuse(&token).await?;
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.refresh_token_reused",
+			)));
+		}
+
+		transaction.commit().await?;
+
+		let (access_token, roles) = self.generate_access_token(&user).await?;
 
-		Ok(AccessTokenDto { access_token })
+		Ok((
+			AccessTokenDto {
+				access_token,
+				roles,
+				refresh_token: None,
+			},
+			new_refresh_token,
+		))
 	}
 
 	async fn invalidate_refresh_token(&self, user_id: i32, refresh_token: String) -> Result<(), AppError> {
 		self.refresh_token_repository
-			.delete_by_token_and_user_id(&refresh_token, user_id)
+			.delete_by_token_hash_and_user_id(&self.hash_refresh_token(&refresh_token), user_id)
 			.await
 	}
 
@@ -149,4 +300,77 @@ impl RefreshTokenServiceTrait for RefreshTokenService {
 	async fn clean_expired_tokens(&self) -> Result<(), AppError> {
 		self.refresh_token_repository.delete_expired().await
 	}
+
+	async fn list_sessions(
+		&self,
+		user_id: i32,
+		current_refresh_token: Option<String>,
+	) -> Result<Vec<SessionResponse>, AppError> {
+		let current_token_hash = current_refresh_token.map(|token| self.hash_refresh_token(&token));
+		let tokens = self.refresh_token_repository.find_active_by_user_id(user_id).await?;
+
+		let sessions = tokens
+			.into_iter()
+			.map(|token| SessionResponse {
+				current: current_token_hash.as_deref() == Some(token.token_hash.as_str()),
+				id: token.family_id,
+				label: token.label,
+				user_agent: token.user_agent,
+				ip_address: token.ip_address,
+				created_at: token.created_at,
+				last_used_at: token.last_used_at,
+				expires_at: token.expires_at,
+			})
+			.collect();
+
+		Ok(sessions)
+	}
+
+	async fn revoke_session(&self, user_id: i32, session_id: &str) -> Result<(), AppError> {
+		self.refresh_token_repository
+			.find_active_by_family_id_and_user_id(session_id, user_id)
+			.await?
+			.ok_or(AppError::NotFound)?;
+
+		self.refresh_token_repository.revoke_family(session_id).await
+	}
+
+	async fn rename_session(&self, user_id: i32, session_id: &str, label: &str) -> Result<(), AppError> {
+		self.refresh_token_repository
+			.find_active_by_family_id_and_user_id(session_id, user_id)
+			.await?
+			.ok_or(AppError::NotFound)?;
+
+		self.refresh_token_repository
+			.update_family_label(session_id, user_id, label)
+			.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::Duration;
+
+	#[test]
+	fn tokens_issued_before_a_revocation_are_rejected() {
+		let revoked_at: DateTimeWithTimeZone = Utc::now().into();
+
+		assert!(RefreshTokenService::is_issued_before(
+			revoked_at - Duration::minutes(5),
+			Some(revoked_at)
+		));
+		assert!(RefreshTokenService::is_issued_before(revoked_at, Some(revoked_at)));
+	}
+
+	#[test]
+	fn tokens_issued_after_a_revocation_are_accepted() {
+		let revoked_at: DateTimeWithTimeZone = Utc::now().into();
+
+		assert!(!RefreshTokenService::is_issued_before(
+			revoked_at + Duration::seconds(1),
+			Some(revoked_at)
+		));
+		assert!(!RefreshTokenService::is_issued_before(revoked_at, None));
+	}
 }
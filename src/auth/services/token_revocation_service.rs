@@ -0,0 +1,134 @@
+use crate::auth::repositories::revoked_access_token_repository::RevokedAccessTokenRepositoryTrait;
+use crate::common::error::app_error::AppError;
+use crate::users::entities::users;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveModelTrait, Set};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Keeps track of access tokens that must be rejected before they expire.
+///
+/// Revoked token ids are stored in the database and mirrored in memory. The cache is filled from the
+/// database on startup and updated on every revocation; ids missing from it are looked up in the
+/// database, so revocations made by other instances are honoured as well.
+pub struct TokenRevocationService {
+	revoked_access_token_repository: Arc<dyn RevokedAccessTokenRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	revoked_jtis: RwLock<HashMap<String, i64>>,
+}
+
+impl TokenRevocationService {
+	pub fn new(
+		revoked_access_token_repository: Arc<dyn RevokedAccessTokenRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+	) -> Self {
+		Self {
+			revoked_access_token_repository,
+			users_repository,
+			revoked_jtis: RwLock::new(HashMap::new()),
+		}
+	}
+}
+
+#[async_trait]
+pub trait TokenRevocationServiceTrait: Send + Sync {
+	async fn load_revoked_tokens(&self) -> Result<(), AppError>;
+	async fn revoke_access_token(&self, jti: &str, user_id: i32, expires_at: i64) -> Result<(), AppError>;
+	async fn revoke_all_user_tokens(&self, user_id: i32) -> Result<(), AppError>;
+	/// Revokes only the user's JWT access tokens. Personal access tokens resolve the roles on every
+	/// request, so they are kept, e.g. when a role is removed.
+	async fn revoke_user_access_tokens(&self, user_id: i32) -> Result<(), AppError>;
+	async fn is_revoked(&self, jti: &str) -> Result<bool, AppError>;
+	async fn clean_expired_tokens(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl TokenRevocationServiceTrait for TokenRevocationService {
+	async fn load_revoked_tokens(&self) -> Result<(), AppError> {
+		let revoked_tokens = self.revoked_access_token_repository.find_active().await?;
+
+		let mut revoked_jtis = self.revoked_jtis.write().map_err(|_| AppError::InternalError)?;
+		revoked_jtis.clear();
+		revoked_jtis.extend(
+			revoked_tokens
+				.into_iter()
+				.map(|token| (token.jti, token.expires_at.timestamp())),
+		);
+
+		Ok(())
+	}
+
+	async fn revoke_access_token(&self, jti: &str, user_id: i32, expires_at: i64) -> Result<(), AppError> {
+		let expires_at_utc = DateTime::<Utc>::from_timestamp(expires_at, 0).ok_or(AppError::InternalError)?;
+
+		self.revoked_access_token_repository
+			.create(jti, user_id, expires_at_utc)
+			.await?;
+
+		self.revoked_jtis
+			.write()
+			.map_err(|_| AppError::InternalError)?
+			.insert(jti.to_string(), expires_at);
+
+		Ok(())
+	}
+
+	async fn revoke_all_user_tokens(&self, user_id: i32) -> Result<(), AppError> {
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			tokens_valid_after: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(())
+	}
+
+	async fn revoke_user_access_tokens(&self, user_id: i32) -> Result<(), AppError> {
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			access_tokens_valid_after: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(())
+	}
+
+	async fn is_revoked(&self, jti: &str) -> Result<bool, AppError> {
+		// Fail closed if the lock was poisoned
+		let cached = self
+			.revoked_jtis
+			.read()
+			.map(|revoked_jtis| revoked_jtis.contains_key(jti))
+			.unwrap_or(true);
+		if cached {
+			return Ok(true);
+		}
+
+		let Some(revoked_token) = self.revoked_access_token_repository.find_by_jti(jti).await? else {
+			return Ok(false);
+		};
+
+		self.revoked_jtis
+			.write()
+			.map_err(|_| AppError::InternalError)?
+			.insert(revoked_token.jti, revoked_token.expires_at.timestamp());
+
+		Ok(true)
+	}
+
+	async fn clean_expired_tokens(&self) -> Result<(), AppError> {
+		self.revoked_access_token_repository.delete_expired().await?;
+
+		let now = Utc::now().timestamp();
+		self.revoked_jtis
+			.write()
+			.map_err(|_| AppError::InternalError)?
+			.retain(|_, expires_at| *expires_at >= now);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,483 @@
+use crate::common::error::app_error::AppError;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::users::entities::users::{self, Entity as User, Model as UserModel};
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, Set};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+const TOTP_DIGITS: u32 = 6;
+const TOTP_PERIOD: i64 = 30;
+const TOTP_ALLOWED_SKEW: i64 = 1;
+const TOTP_SECRET_LENGTH: usize = 20;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollmentResponse {
+	pub secret: String,
+	pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+	pub recovery_codes: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct TwoFactorService {
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	app_config: Arc<dyn AppConfigTrait>,
+	issuer: String,
+	recovery_codes_count: usize,
+	max_challenge_attempts: i32,
+}
+
+impl TwoFactorService {
+	pub fn new(users_repository: Arc<dyn UsersRepositoryTrait>, app_config: Arc<dyn AppConfigTrait>) -> Self {
+		let issuer = app_config.get_config().security.two_factor.issuer.clone();
+		let recovery_codes_count = app_config.get_config().security.two_factor.recovery_codes_count;
+		let max_challenge_attempts = app_config.get_config().security.tokens.mfa_token.max_attempts;
+		Self {
+			users_repository,
+			app_config,
+			issuer,
+			recovery_codes_count,
+			max_challenge_attempts,
+		}
+	}
+
+	fn generate_secret() -> String {
+		let mut secret = [0u8; TOTP_SECRET_LENGTH];
+		OsRng.fill_bytes(&mut secret);
+		base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &secret)
+	}
+
+	fn decode_secret(secret: &str) -> Result<Vec<u8>, AppError> {
+		base32::decode(base32::Alphabet::Rfc4648 { padding: false }, secret).ok_or_else(|| {
+			tracing::error!("Stored two-factor secret is not valid base32");
+			AppError::InternalError
+		})
+	}
+
+	fn generate_totp(secret: &[u8], step: i64) -> Result<u32, AppError> {
+		let mut mac = Hmac::<Sha1>::new_from_slice(secret).map_err(|_| AppError::InternalError)?;
+		mac.update(&step.to_be_bytes());
+		let hash = mac.finalize().into_bytes();
+
+		// Dynamic truncation as described in RFC 4226, section 5.3
+		let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+		let binary = ((hash[offset] as u32 & 0x7f) << 24)
+			| ((hash[offset + 1] as u32) << 16)
+			| ((hash[offset + 2] as u32) << 8)
+			| (hash[offset + 3] as u32);
+
+		Ok(binary % 10u32.pow(TOTP_DIGITS))
+	}
+
+	/// Returns the time step matched by `code`, skipping steps that were already used.
+	fn find_totp_step(secret: &str, code: &str, last_used_step: Option<i64>) -> Result<Option<i64>, AppError> {
+		Self::find_totp_step_at(
+			&Self::decode_secret(secret)?,
+			code,
+			last_used_step,
+			Utc::now().timestamp(),
+		)
+	}
+
+	fn find_totp_step_at(
+		secret: &[u8],
+		code: &str,
+		last_used_step: Option<i64>,
+		timestamp: i64,
+	) -> Result<Option<i64>, AppError> {
+		if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+			return Ok(None);
+		}
+
+		let code: u32 = code.parse().map_err(|_| AppError::InternalError)?;
+		let current_step = timestamp / TOTP_PERIOD;
+
+		for step in (current_step - TOTP_ALLOWED_SKEW)..=(current_step + TOTP_ALLOWED_SKEW) {
+			if last_used_step.is_some_and(|last_used| step <= last_used) {
+				continue;
+			}
+
+			if Self::generate_totp(secret, step)? == code {
+				return Ok(Some(step));
+			}
+		}
+
+		Ok(None)
+	}
+
+	fn generate_recovery_codes(&self) -> (Vec<String>, Vec<String>) {
+		let mut codes = Vec::with_capacity(self.recovery_codes_count);
+		let mut hashes = Vec::with_capacity(self.recovery_codes_count);
+
+		for _ in 0..self.recovery_codes_count {
+			let mut bytes = [0u8; 8];
+			OsRng.fill_bytes(&mut bytes);
+			let encoded = base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &bytes).to_lowercase();
+			let code = &encoded[..RECOVERY_CODE_LENGTH];
+
+			hashes.push(Self::hash_recovery_code(code));
+			codes.push(format!(
+				"{}-{}",
+				&code[..RECOVERY_CODE_LENGTH / 2],
+				&code[RECOVERY_CODE_LENGTH / 2..]
+			));
+		}
+
+		(codes, hashes)
+	}
+
+	fn hash_recovery_code(code: &str) -> String {
+		let normalized: String = code
+			.chars()
+			.filter(|c| c.is_ascii_alphanumeric())
+			.collect::<String>()
+			.to_lowercase();
+
+		hex::encode(Sha256::digest(normalized.as_bytes()))
+	}
+
+	fn normalize_code(code: &str) -> String {
+		code.chars().filter(|c| !c.is_whitespace()).collect()
+	}
+
+	fn invalid_code_error() -> AppError {
+		let mut errors = validator::ValidationErrors::new();
+		errors.add(
+			"code",
+			validator::ValidationError::new("invalid")
+				.with_message(translate("auth.errors.invalid_two_factor_code").into()),
+		);
+		AppError::ValidationError(errors)
+	}
+}
+
+#[async_trait]
+pub trait TwoFactorServiceTrait: Send + Sync {
+	async fn enroll(&self, user_id: i32) -> Result<TwoFactorEnrollmentResponse, AppError>;
+	async fn confirm_enrollment(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError>;
+	async fn disable(&self, user_id: i32, code: &str) -> Result<(), AppError>;
+	async fn regenerate_recovery_codes(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError>;
+	async fn verify_code(&self, user: &UserModel, code: &str) -> Result<bool, AppError>;
+	async fn start_challenge(&self, user_id: i32, jti: &str) -> Result<(), AppError>;
+	async fn record_challenge_failure(&self, user_id: i32, jti: &str) -> Result<(), AppError>;
+	async fn complete_challenge(&self, user_id: i32, jti: &str) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl TwoFactorServiceTrait for TwoFactorService {
+	async fn enroll(&self, user_id: i32) -> Result<TwoFactorEnrollmentResponse, AppError> {
+		let user = self.users_repository.find_by_id(user_id).await?;
+
+		if user.is_two_factor_enabled {
+			return Err(AppError::BadRequest(translate(
+				"auth.errors.two_factor_already_enabled",
+			)));
+		}
+
+		let secret = Self::generate_secret();
+
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			two_factor_secret: Set(Some(secret.clone())),
+			two_factor_last_used_step: Set(None),
+			updated_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		let label = format!("{}:{}", self.issuer, user.email);
+		let otpauth_uri = format!(
+			"otpauth://totp/{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+			urlencoding::encode(&label),
+			secret,
+			urlencoding::encode(&self.issuer),
+			TOTP_DIGITS,
+			TOTP_PERIOD
+		);
+
+		Ok(TwoFactorEnrollmentResponse { secret, otpauth_uri })
+	}
+
+	async fn confirm_enrollment(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError> {
+		let user = self.users_repository.find_by_id(user_id).await?;
+
+		if user.is_two_factor_enabled {
+			return Err(AppError::BadRequest(translate(
+				"auth.errors.two_factor_already_enabled",
+			)));
+		}
+
+		let secret = user
+			.two_factor_secret
+			.as_deref()
+			.ok_or_else(|| AppError::BadRequest(translate("auth.errors.two_factor_not_enrolled")))?;
+
+		let step =
+			Self::find_totp_step(secret, &Self::normalize_code(code), None)?.ok_or_else(Self::invalid_code_error)?;
+
+		let (recovery_codes, recovery_code_hashes) = self.generate_recovery_codes();
+
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			is_two_factor_enabled: Set(true),
+			two_factor_recovery_codes: Set(Some(serde_json::json!(recovery_code_hashes))),
+			two_factor_last_used_step: Set(Some(step)),
+			updated_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(RecoveryCodesResponse { recovery_codes })
+	}
+
+	async fn disable(&self, user_id: i32, code: &str) -> Result<(), AppError> {
+		let user = self.users_repository.find_by_id(user_id).await?;
+
+		if !user.is_two_factor_enabled {
+			return Err(AppError::BadRequest(translate("auth.errors.two_factor_not_enabled")));
+		}
+
+		if !self.verify_code(&user, code).await? {
+			return Err(Self::invalid_code_error());
+		}
+
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			is_two_factor_enabled: Set(false),
+			two_factor_secret: Set(None),
+			two_factor_recovery_codes: Set(None),
+			two_factor_last_used_step: Set(None),
+			updated_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(())
+	}
+
+	async fn regenerate_recovery_codes(&self, user_id: i32, code: &str) -> Result<RecoveryCodesResponse, AppError> {
+		let user = self.users_repository.find_by_id(user_id).await?;
+
+		if !user.is_two_factor_enabled {
+			return Err(AppError::BadRequest(translate("auth.errors.two_factor_not_enabled")));
+		}
+
+		if !self.verify_code(&user, code).await? {
+			return Err(Self::invalid_code_error());
+		}
+
+		let (recovery_codes, recovery_code_hashes) = self.generate_recovery_codes();
+
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			two_factor_recovery_codes: Set(Some(serde_json::json!(recovery_code_hashes))),
+			updated_at: Set(Some(Utc::now().into())),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(RecoveryCodesResponse { recovery_codes })
+	}
+
+	async fn verify_code(&self, user: &UserModel, code: &str) -> Result<bool, AppError> {
+		if !user.is_two_factor_enabled {
+			return Ok(false);
+		}
+
+		let Some(secret) = user.two_factor_secret.as_deref() else {
+			return Ok(false);
+		};
+
+		let code = Self::normalize_code(code);
+
+		if let Some(step) = Self::find_totp_step(secret, &code, user.two_factor_last_used_step)? {
+			// Moving the step forward only if no concurrent request got there first makes every code single use
+			let result = User::update_many()
+				.col_expr(users::Column::TwoFactorLastUsedStep, Expr::value(step))
+				.filter(users::Column::Id.eq(user.id))
+				.filter(
+					Condition::any()
+						.add(users::Column::TwoFactorLastUsedStep.is_null())
+						.add(users::Column::TwoFactorLastUsedStep.lt(step)),
+				)
+				.exec(self.users_repository.get_db())
+				.await?;
+
+			return Ok(result.rows_affected > 0);
+		}
+
+		let Some(stored_codes) = user.two_factor_recovery_codes.clone() else {
+			return Ok(false);
+		};
+		let mut recovery_code_hashes: Vec<String> = serde_json::from_value(stored_codes.clone()).unwrap_or_default();
+
+		let code_hash = Self::hash_recovery_code(&code);
+		let Some(position) = recovery_code_hashes.iter().position(|hash| *hash == code_hash) else {
+			return Ok(false);
+		};
+
+		// Recovery codes are single use
+		recovery_code_hashes.remove(position);
+
+		// The list is replaced only if it was not changed since it was read, so a code cannot be used twice
+		let result = User::update_many()
+			.col_expr(
+				users::Column::TwoFactorRecoveryCodes,
+				Expr::value(serde_json::json!(recovery_code_hashes)),
+			)
+			.col_expr(users::Column::UpdatedAt, Expr::value(Utc::now()))
+			.filter(users::Column::Id.eq(user.id))
+			.filter(Expr::cust_with_values(
+				"two_factor_recovery_codes::jsonb = $1::jsonb",
+				[stored_codes.to_string()],
+			))
+			.exec(self.users_repository.get_db())
+			.await?;
+
+		Ok(result.rows_affected > 0)
+	}
+
+	async fn start_challenge(&self, user_id: i32, jti: &str) -> Result<(), AppError> {
+		// Only the most recent mfa_token is accepted, issuing a new one resets the attempts
+		let user_active_model = users::ActiveModel {
+			id: Set(user_id),
+			mfa_token_jti: Set(Some(jti.to_string())),
+			mfa_failed_attempts: Set(0),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(())
+	}
+
+	async fn record_challenge_failure(&self, user_id: i32, jti: &str) -> Result<(), AppError> {
+		User::update_many()
+			.col_expr(
+				users::Column::MfaFailedAttempts,
+				Expr::col(users::Column::MfaFailedAttempts).add(1),
+			)
+			.filter(users::Column::Id.eq(user_id))
+			.filter(users::Column::MfaTokenJti.eq(jti))
+			.exec(self.users_repository.get_db())
+			.await?;
+
+		// Too many wrong codes invalidate the mfa_token, the user has to enter the password again
+		User::update_many()
+			.col_expr(users::Column::MfaTokenJti, Expr::value(Option::<String>::None))
+			.filter(users::Column::Id.eq(user_id))
+			.filter(users::Column::MfaTokenJti.eq(jti))
+			.filter(users::Column::MfaFailedAttempts.gte(self.max_challenge_attempts))
+			.exec(self.users_repository.get_db())
+			.await?;
+
+		Ok(())
+	}
+
+	async fn complete_challenge(&self, user_id: i32, jti: &str) -> Result<(), AppError> {
+		// Clearing the jti only if it is still stored makes concurrent uses of the same token fail
+		let result = User::update_many()
+			.col_expr(users::Column::MfaTokenJti, Expr::value(Option::<String>::None))
+			.col_expr(users::Column::MfaFailedAttempts, Expr::value(0))
+			.filter(users::Column::Id.eq(user_id))
+			.filter(users::Column::MfaTokenJti.eq(jti))
+			.exec(self.users_repository.get_db())
+			.await?;
+
+		if result.rows_affected == 0 {
+			return Err(AppError::AuthenticationError(translate("auth.errors.invalid_token")));
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// RFC 6238, appendix B, SHA-1 with the last 6 of the 8 digits
+	const RFC_SECRET: &[u8] = b"12345678901234567890";
+	const RFC_VECTORS: [(i64, u32); 6] = [
+		(59, 287082),
+		(1111111109, 81804),
+		(1111111111, 50471),
+		(1234567890, 5924),
+		(2000000000, 279037),
+		(20000000000, 353130),
+	];
+
+	#[test]
+	fn generate_totp_matches_rfc_6238_vectors() {
+		for (timestamp, expected) in RFC_VECTORS {
+			let code = TwoFactorService::generate_totp(RFC_SECRET, timestamp / TOTP_PERIOD).unwrap();
+			assert_eq!(code, expected, "timestamp {timestamp}");
+		}
+	}
+
+	#[test]
+	fn find_totp_step_accepts_codes_within_the_allowed_skew() {
+		let timestamp = 1111111111;
+		let step = timestamp / TOTP_PERIOD;
+
+		for offset in -TOTP_ALLOWED_SKEW..=TOTP_ALLOWED_SKEW {
+			let code = format!(
+				"{:06}",
+				TwoFactorService::generate_totp(RFC_SECRET, step + offset).unwrap()
+			);
+			let found = TwoFactorService::find_totp_step_at(RFC_SECRET, &code, None, timestamp).unwrap();
+			assert_eq!(found, Some(step + offset));
+		}
+
+		let code = format!("{:06}", TwoFactorService::generate_totp(RFC_SECRET, step + 2).unwrap());
+		assert_eq!(
+			TwoFactorService::find_totp_step_at(RFC_SECRET, &code, None, timestamp).unwrap(),
+			None
+		);
+	}
+
+	#[test]
+	fn find_totp_step_rejects_already_used_steps() {
+		let timestamp = 1234567890;
+		let step = timestamp / TOTP_PERIOD;
+
+		assert_eq!(
+			TwoFactorService::find_totp_step_at(RFC_SECRET, "005924", None, timestamp).unwrap(),
+			Some(step)
+		);
+		assert_eq!(
+			TwoFactorService::find_totp_step_at(RFC_SECRET, "005924", Some(step), timestamp).unwrap(),
+			None
+		);
+	}
+
+	#[test]
+	fn find_totp_step_rejects_malformed_codes() {
+		for code in ["", "5924", "0059240", "00592a"] {
+			assert_eq!(
+				TwoFactorService::find_totp_step_at(RFC_SECRET, code, None, 1234567890).unwrap(),
+				None
+			);
+		}
+	}
+
+	#[test]
+	fn hash_recovery_code_ignores_case_and_separators() {
+		assert_eq!(
+			TwoFactorService::hash_recovery_code("abcde-fghij"),
+			TwoFactorService::hash_recovery_code("ABCDE FGHIJ")
+		);
+	}
+}
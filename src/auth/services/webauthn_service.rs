@@ -0,0 +1,750 @@
+use crate::auth::dto::webauthn_login_dto::WebauthnLoginDto;
+use crate::auth::dto::webauthn_login_start_dto::WebauthnLoginStartDto;
+use crate::auth::dto::webauthn_registration_dto::WebauthnRegistrationDto;
+use crate::auth::entities::webauthn_credentials::Model as WebauthnCredentialModel;
+use crate::auth::repositories::webauthn_credential_repository::WebauthnCredentialRepositoryTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::utils::token_utils::generate_opaque_token;
+use crate::config::app_config::WebauthnConfig;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::users::entities::users::Model as UserModel;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::Utc;
+use ciborium::Value;
+use p256::ecdsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+const COSE_ALGORITHM_ES256: i64 = -7;
+const COSE_ALGORITHM_RS256: i64 = -257;
+const COSE_KEY_TYPE_EC2: i64 = 2;
+const COSE_KEY_TYPE_RSA: i64 = 3;
+const COSE_CURVE_P256: i64 = 1;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Debug, Clone, Copy)]
+enum Ceremony {
+	Registration { user_id: i32 },
+	Authentication { user_id: Option<i32> },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingChallenge {
+	ceremony: Ceremony,
+	expires_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClientData {
+	#[serde(rename = "type")]
+	ceremony_type: String,
+	challenge: String,
+	origin: String,
+}
+
+struct AuthenticatorData {
+	rp_id_hash: Vec<u8>,
+	flags: u8,
+	sign_count: u32,
+	attested_credential: Option<(Vec<u8>, Value)>,
+}
+
+enum CosePublicKey {
+	Es256(p256::ecdsa::VerifyingKey),
+	Rs256(RsaPublicKey),
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelyingParty {
+	pub id: String,
+	pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialUser {
+	pub id: String,
+	pub name: String,
+	pub display_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicKeyCredentialParameters {
+	#[serde(rename = "type")]
+	pub credential_type: &'static str,
+	pub alg: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublicKeyCredentialDescriptor {
+	#[serde(rename = "type")]
+	pub credential_type: &'static str,
+	pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+	pub resident_key: &'static str,
+	pub user_verification: &'static str,
+}
+
+/// Options for `navigator.credentials.create()`, binary values are base64url encoded.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialCreationOptions {
+	pub challenge: String,
+	pub rp: RelyingParty,
+	pub user: PublicKeyCredentialUser,
+	pub pub_key_cred_params: Vec<PublicKeyCredentialParameters>,
+	pub timeout: i64,
+	pub exclude_credentials: Vec<PublicKeyCredentialDescriptor>,
+	pub authenticator_selection: AuthenticatorSelection,
+	pub attestation: &'static str,
+}
+
+/// Options for `navigator.credentials.get()`, binary values are base64url encoded.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyCredentialRequestOptions {
+	pub challenge: String,
+	pub timeout: i64,
+	pub rp_id: String,
+	pub allow_credentials: Vec<PublicKeyCredentialDescriptor>,
+	pub user_verification: &'static str,
+}
+
+/// Passwordless sign-in with passkeys (WebAuthn).
+///
+/// Only the `none` attestation is requested, so the attestation statement is not verified. ES256 and
+/// RS256 credentials are supported and user verification is required for both ceremonies. Challenges
+/// are kept in memory until they are used or expire, at most `max_challenges` of them, as anyone can
+/// start a sign-in.
+pub struct WebauthnService {
+	webauthn_credential_repository: Arc<dyn WebauthnCredentialRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	config: WebauthnConfig,
+	challenges: RwLock<HashMap<String, PendingChallenge>>,
+}
+
+impl WebauthnService {
+	pub fn new(
+		webauthn_credential_repository: Arc<dyn WebauthnCredentialRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let config = app_config.get_config().security.webauthn.clone();
+		Self {
+			webauthn_credential_repository,
+			users_repository,
+			config,
+			challenges: RwLock::new(HashMap::new()),
+		}
+	}
+
+	fn verification_failed() -> AppError {
+		AppError::AuthenticationError(translate("auth.errors.webauthn_verification_failed"))
+	}
+
+	fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+		URL_SAFE_NO_PAD
+			.decode(value.trim_end_matches('='))
+			.map_err(|_| Self::verification_failed())
+	}
+
+	fn user_handle(user_id: i32) -> String {
+		URL_SAFE_NO_PAD.encode(user_id.to_string())
+	}
+
+	fn descriptor(credential: &WebauthnCredentialModel) -> PublicKeyCredentialDescriptor {
+		PublicKeyCredentialDescriptor {
+			credential_type: "public-key",
+			id: credential.credential_id.clone(),
+		}
+	}
+
+	/// Makes room for a new challenge. Expired challenges go first; when that is not enough, the oldest
+	/// tenth is dropped, so the scan is not repeated for every new challenge.
+	fn evict(challenges: &mut HashMap<String, PendingChallenge>, max_challenges: usize, now: i64) {
+		challenges.retain(|_, pending| pending.expires_at > now);
+
+		if challenges.len() < max_challenges {
+			return;
+		}
+
+		let target = max_challenges - max_challenges.div_ceil(10);
+		let mut by_expiry: Vec<(i64, String)> = challenges
+			.iter()
+			.map(|(challenge, pending)| (pending.expires_at, challenge.clone()))
+			.collect();
+		by_expiry.sort_unstable();
+
+		for (_, challenge) in by_expiry.into_iter().take(challenges.len() - target) {
+			challenges.remove(&challenge);
+		}
+	}
+
+	fn create_challenge(&self, ceremony: Ceremony) -> Result<String, AppError> {
+		let challenge = generate_opaque_token();
+		let now = Utc::now().timestamp();
+		let max_challenges = self.config.max_challenges.max(1);
+
+		let mut challenges = self.challenges.write().map_err(|_| AppError::InternalError)?;
+		if challenges.len() >= max_challenges {
+			Self::evict(&mut challenges, max_challenges, now);
+		}
+		challenges.insert(
+			challenge.clone(),
+			PendingChallenge {
+				ceremony,
+				expires_at: now + self.config.challenge_expires_in,
+			},
+		);
+
+		Ok(challenge)
+	}
+
+	/// Removes the challenge, so every challenge can be answered only once.
+	fn take_challenge(&self, challenge: &str) -> Result<Ceremony, AppError> {
+		let pending = self
+			.challenges
+			.write()
+			.map_err(|_| AppError::InternalError)?
+			.remove(challenge)
+			.filter(|pending| pending.expires_at > Utc::now().timestamp())
+			.ok_or_else(|| AppError::AuthenticationError(translate("auth.errors.invalid_webauthn_challenge")))?;
+
+		Ok(pending.ceremony)
+	}
+
+	fn verify_client_data(&self, client_data_json: &[u8], ceremony_type: &str) -> Result<ClientData, AppError> {
+		let client_data: ClientData =
+			serde_json::from_slice(client_data_json).map_err(|_| Self::verification_failed())?;
+
+		if client_data.ceremony_type != ceremony_type || client_data.origin != self.config.origin {
+			return Err(Self::verification_failed());
+		}
+
+		Ok(client_data)
+	}
+
+	fn verify_authenticator_data(&self, authenticator_data: &AuthenticatorData) -> Result<(), AppError> {
+		let expected_rp_id_hash = Sha256::digest(self.config.rp_id.as_bytes());
+
+		if authenticator_data.rp_id_hash != expected_rp_id_hash.as_slice()
+			|| authenticator_data.flags & FLAG_USER_PRESENT == 0
+			|| authenticator_data.flags & FLAG_USER_VERIFIED == 0
+		{
+			return Err(Self::verification_failed());
+		}
+
+		Ok(())
+	}
+
+	fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData, AppError> {
+		if data.len() < 37 {
+			return Err(Self::verification_failed());
+		}
+
+		let flags = data[32];
+		let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+		let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+			// AAGUID (16 bytes), credential id length (2 bytes), credential id, COSE public key
+			let rest = data.get(37..).ok_or_else(Self::verification_failed)?;
+			let length_bytes = rest.get(16..18).ok_or_else(Self::verification_failed)?;
+			let length = u16::from_be_bytes([length_bytes[0], length_bytes[1]]) as usize;
+			let credential_id = rest.get(18..18 + length).ok_or_else(Self::verification_failed)?;
+			let public_key: Value =
+				ciborium::from_reader(&rest[18 + length..]).map_err(|_| Self::verification_failed())?;
+
+			Some((credential_id.to_vec(), public_key))
+		} else {
+			None
+		};
+
+		Ok(AuthenticatorData {
+			rp_id_hash: data[..32].to_vec(),
+			flags,
+			sign_count,
+			attested_credential,
+		})
+	}
+
+	fn cose_field(key: &Value, label: i64) -> Option<&Value> {
+		key.as_map()?
+			.iter()
+			.find(|(field, _)| {
+				field
+					.as_integer()
+					.is_some_and(|field| i128::from(field) == i128::from(label))
+			})
+			.map(|(_, value)| value)
+	}
+
+	fn cose_integer(key: &Value, label: i64) -> Option<i128> {
+		Self::cose_field(key, label)?.as_integer().map(i128::from)
+	}
+
+	fn cose_bytes(key: &Value, label: i64) -> Option<&Vec<u8>> {
+		Self::cose_field(key, label)?.as_bytes()
+	}
+
+	fn parse_public_key(key: &Value) -> Result<CosePublicKey, AppError> {
+		let key_type = Self::cose_integer(key, 1).ok_or_else(Self::verification_failed)?;
+		let algorithm = Self::cose_integer(key, 3).ok_or_else(Self::verification_failed)?;
+
+		match (key_type, algorithm) {
+			(kty, alg) if kty == COSE_KEY_TYPE_EC2.into() && alg == COSE_ALGORITHM_ES256.into() => {
+				if Self::cose_integer(key, -1) != Some(COSE_CURVE_P256.into()) {
+					return Err(Self::verification_failed());
+				}
+
+				let x = Self::cose_bytes(key, -2).ok_or_else(Self::verification_failed)?;
+				let y = Self::cose_bytes(key, -3).ok_or_else(Self::verification_failed)?;
+				let point = [&[0x04], x.as_slice(), y.as_slice()].concat();
+
+				p256::ecdsa::VerifyingKey::from_sec1_bytes(&point)
+					.map(CosePublicKey::Es256)
+					.map_err(|_| Self::verification_failed())
+			}
+			(kty, alg) if kty == COSE_KEY_TYPE_RSA.into() && alg == COSE_ALGORITHM_RS256.into() => {
+				let n = Self::cose_bytes(key, -1).ok_or_else(Self::verification_failed)?;
+				let e = Self::cose_bytes(key, -2).ok_or_else(Self::verification_failed)?;
+
+				RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))
+					.map(CosePublicKey::Rs256)
+					.map_err(|_| Self::verification_failed())
+			}
+			_ => Err(Self::verification_failed()),
+		}
+	}
+
+	fn verify_signature(public_key: &CosePublicKey, message: &[u8], signature: &[u8]) -> Result<(), AppError> {
+		let verified = match public_key {
+			CosePublicKey::Es256(verifying_key) => p256::ecdsa::Signature::from_der(signature)
+				.is_ok_and(|signature| verifying_key.verify(message, &signature).is_ok()),
+			CosePublicKey::Rs256(public_key) => {
+				let verifying_key = rsa::pkcs1v15::VerifyingKey::<Sha256>::new(public_key.clone());
+				rsa::pkcs1v15::Signature::try_from(signature)
+					.is_ok_and(|signature| verifying_key.verify(message, &signature).is_ok())
+			}
+		};
+
+		if !verified {
+			return Err(Self::verification_failed());
+		}
+
+		Ok(())
+	}
+
+	/// A counter that does not increase points to a cloned authenticator, zero means it is not supported.
+	fn is_sign_count_valid(stored: i64, received: i64) -> bool {
+		(stored == 0 && received == 0) || received > stored
+	}
+}
+
+#[async_trait]
+pub trait WebauthnServiceTrait: Send + Sync {
+	async fn start_registration(&self, user_id: i32) -> Result<PublicKeyCredentialCreationOptions, AppError>;
+	async fn finish_registration(
+		&self,
+		user_id: i32,
+		dto: WebauthnRegistrationDto,
+	) -> Result<WebauthnCredentialModel, AppError>;
+	async fn start_authentication(
+		&self,
+		dto: WebauthnLoginStartDto,
+	) -> Result<PublicKeyCredentialRequestOptions, AppError>;
+	async fn finish_authentication(&self, dto: WebauthnLoginDto) -> Result<UserModel, AppError>;
+	async fn find_all(&self, user_id: i32) -> Result<Vec<WebauthnCredentialModel>, AppError>;
+	async fn delete(&self, id: i32, user_id: i32) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl WebauthnServiceTrait for WebauthnService {
+	async fn start_registration(&self, user_id: i32) -> Result<PublicKeyCredentialCreationOptions, AppError> {
+		let user = self.users_repository.find_by_id(user_id).await?;
+		let credentials = self.webauthn_credential_repository.find_by_user_id(user_id).await?;
+		let challenge = self.create_challenge(Ceremony::Registration { user_id })?;
+
+		Ok(PublicKeyCredentialCreationOptions {
+			challenge,
+			rp: RelyingParty {
+				id: self.config.rp_id.clone(),
+				name: self.config.rp_name.clone(),
+			},
+			user: PublicKeyCredentialUser {
+				id: Self::user_handle(user.id),
+				name: user.username.clone(),
+				display_name: user.username,
+			},
+			pub_key_cred_params: [COSE_ALGORITHM_ES256, COSE_ALGORITHM_RS256]
+				.into_iter()
+				.map(|alg| PublicKeyCredentialParameters {
+					credential_type: "public-key",
+					alg,
+				})
+				.collect(),
+			timeout: self.config.challenge_expires_in * 1000,
+			exclude_credentials: credentials.iter().map(Self::descriptor).collect(),
+			authenticator_selection: AuthenticatorSelection {
+				resident_key: "preferred",
+				user_verification: "required",
+			},
+			attestation: "none",
+		})
+	}
+
+	async fn finish_registration(
+		&self,
+		user_id: i32,
+		dto: WebauthnRegistrationDto,
+	) -> Result<WebauthnCredentialModel, AppError> {
+		let client_data_json = Self::decode(&dto.credential.response.client_data_json)?;
+		let client_data = self.verify_client_data(&client_data_json, "webauthn.create")?;
+
+		match self.take_challenge(&client_data.challenge)? {
+			Ceremony::Registration { user_id: expected } if expected == user_id => {}
+			_ => {
+				return Err(AppError::AuthenticationError(translate(
+					"auth.errors.invalid_webauthn_challenge",
+				)));
+			}
+		}
+
+		let attestation_object: Value =
+			ciborium::from_reader(Self::decode(&dto.credential.response.attestation_object)?.as_slice())
+				.map_err(|_| Self::verification_failed())?;
+		let authenticator_data = attestation_object
+			.as_map()
+			.and_then(|fields| fields.iter().find(|(field, _)| field.as_text() == Some("authData")))
+			.and_then(|(_, value)| value.as_bytes())
+			.ok_or_else(Self::verification_failed)?;
+		let authenticator_data = Self::parse_authenticator_data(authenticator_data)?;
+		self.verify_authenticator_data(&authenticator_data)?;
+
+		let (credential_id, public_key) = authenticator_data
+			.attested_credential
+			.ok_or_else(Self::verification_failed)?;
+		Self::parse_public_key(&public_key)?;
+
+		let credential_id = URL_SAFE_NO_PAD.encode(credential_id);
+		if self
+			.webauthn_credential_repository
+			.find_by_credential_id(&credential_id)
+			.await?
+			.is_some()
+		{
+			return Err(AppError::BadRequest(translate(
+				"auth.errors.webauthn_credential_already_registered",
+			)));
+		}
+
+		let mut encoded_public_key = Vec::new();
+		ciborium::into_writer(&public_key, &mut encoded_public_key).map_err(|_| AppError::InternalError)?;
+
+		self.webauthn_credential_repository
+			.create(
+				user_id,
+				&dto.name,
+				&credential_id,
+				encoded_public_key,
+				authenticator_data.sign_count.into(),
+			)
+			.await
+	}
+
+	async fn start_authentication(
+		&self,
+		dto: WebauthnLoginStartDto,
+	) -> Result<PublicKeyCredentialRequestOptions, AppError> {
+		let (user_id, allow_credentials) = match dto.username {
+			Some(username) => match self.users_repository.find_by_username(&username).await {
+				Ok(user) => {
+					let credentials = self.webauthn_credential_repository.find_by_user_id(user.id).await?;
+					(Some(user.id), credentials.iter().map(Self::descriptor).collect())
+				}
+				// Answer like for a user without passkeys, so usernames cannot be probed
+				Err(AppError::NotFound) => (None, Vec::new()),
+				Err(e) => return Err(e),
+			},
+			None => (None, Vec::new()),
+		};
+
+		let challenge = self.create_challenge(Ceremony::Authentication { user_id })?;
+
+		Ok(PublicKeyCredentialRequestOptions {
+			challenge,
+			timeout: self.config.challenge_expires_in * 1000,
+			rp_id: self.config.rp_id.clone(),
+			allow_credentials,
+			user_verification: "required",
+		})
+	}
+
+	async fn finish_authentication(&self, dto: WebauthnLoginDto) -> Result<UserModel, AppError> {
+		let client_data_json = Self::decode(&dto.response.client_data_json)?;
+		let client_data = self.verify_client_data(&client_data_json, "webauthn.get")?;
+
+		let Ceremony::Authentication { user_id } = self.take_challenge(&client_data.challenge)? else {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.invalid_webauthn_challenge",
+			)));
+		};
+
+		let credential_id = URL_SAFE_NO_PAD.encode(Self::decode(&dto.id)?);
+		let credential = self
+			.webauthn_credential_repository
+			.find_by_credential_id(&credential_id)
+			.await?
+			.ok_or_else(Self::verification_failed)?;
+
+		if user_id.is_some_and(|user_id| user_id != credential.user_id) {
+			return Err(Self::verification_failed());
+		}
+
+		if let Some(user_handle) = &dto.response.user_handle
+			&& Self::decode(user_handle)? != credential.user_id.to_string().as_bytes()
+		{
+			return Err(Self::verification_failed());
+		}
+
+		let authenticator_data_bytes = Self::decode(&dto.response.authenticator_data)?;
+		let authenticator_data = Self::parse_authenticator_data(&authenticator_data_bytes)?;
+		self.verify_authenticator_data(&authenticator_data)?;
+
+		let public_key: Value =
+			ciborium::from_reader(credential.public_key.as_slice()).map_err(|_| AppError::InternalError)?;
+		let public_key = Self::parse_public_key(&public_key)?;
+
+		let message = [
+			authenticator_data_bytes.as_slice(),
+			Sha256::digest(&client_data_json).as_slice(),
+		]
+		.concat();
+		Self::verify_signature(&public_key, &message, &Self::decode(&dto.response.signature)?)?;
+
+		let sign_count = i64::from(authenticator_data.sign_count);
+		if !Self::is_sign_count_valid(credential.sign_count, sign_count) {
+			tracing::warn!(
+				"WebAuthn sign count did not increase for credential {} of user {}",
+				credential.id,
+				credential.user_id
+			);
+			return Err(Self::verification_failed());
+		}
+
+		let user = self.users_repository.find_by_id(credential.user_id).await?;
+
+		if !user.is_active {
+			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
+		}
+
+		if !user.is_email_confirmed {
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.email_not_confirmed",
+			)));
+		}
+
+		self.webauthn_credential_repository
+			.update_sign_count(credential.id, sign_count)
+			.await?;
+
+		Ok(user)
+	}
+
+	async fn find_all(&self, user_id: i32) -> Result<Vec<WebauthnCredentialModel>, AppError> {
+		self.webauthn_credential_repository.find_by_user_id(user_id).await
+	}
+
+	async fn delete(&self, id: i32, user_id: i32) -> Result<(), AppError> {
+		self.webauthn_credential_repository
+			.delete_by_id_and_user_id(id, user_id)
+			.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use p256::ecdsa::SigningKey;
+	use p256::ecdsa::signature::Signer;
+
+	fn signing_key() -> SigningKey {
+		SigningKey::from_bytes(&[7u8; 32].into()).unwrap()
+	}
+
+	fn es256_cose_key(signing_key: &SigningKey) -> Value {
+		let point = signing_key.verifying_key().to_encoded_point(false);
+
+		Value::Map(vec![
+			(Value::from(1), Value::from(COSE_KEY_TYPE_EC2)),
+			(Value::from(3), Value::from(COSE_ALGORITHM_ES256)),
+			(Value::from(-1), Value::from(COSE_CURVE_P256)),
+			(Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+			(Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+		])
+	}
+
+	fn authenticator_data(flags: u8, sign_count: u32, attested_credential: Option<(&[u8], &Value)>) -> Vec<u8> {
+		let mut data = Sha256::digest(b"localhost").to_vec();
+		data.push(flags);
+		data.extend_from_slice(&sign_count.to_be_bytes());
+
+		if let Some((credential_id, public_key)) = attested_credential {
+			data.extend_from_slice(&[0u8; 16]);
+			data.extend_from_slice(&(credential_id.len() as u16).to_be_bytes());
+			data.extend_from_slice(credential_id);
+			ciborium::into_writer(public_key, &mut data).unwrap();
+		}
+
+		data
+	}
+
+	#[test]
+	fn parse_authenticator_data_reads_flags_and_sign_count() {
+		let data = authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED, 42, None);
+
+		let parsed = WebauthnService::parse_authenticator_data(&data).unwrap();
+
+		assert_eq!(parsed.rp_id_hash, Sha256::digest(b"localhost").to_vec());
+		assert_eq!(parsed.flags, FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+		assert_eq!(parsed.sign_count, 42);
+		assert!(parsed.attested_credential.is_none());
+	}
+
+	#[test]
+	fn parse_authenticator_data_reads_the_attested_credential() {
+		let public_key = es256_cose_key(&signing_key());
+		let data = authenticator_data(
+			FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA,
+			0,
+			Some((b"credential-id", &public_key)),
+		);
+
+		let parsed = WebauthnService::parse_authenticator_data(&data).unwrap();
+
+		let (credential_id, parsed_key) = parsed.attested_credential.unwrap();
+		assert_eq!(credential_id, b"credential-id");
+		assert_eq!(parsed_key, public_key);
+	}
+
+	#[test]
+	fn parse_authenticator_data_rejects_truncated_data() {
+		let public_key = es256_cose_key(&signing_key());
+		let data = authenticator_data(FLAG_ATTESTED_CREDENTIAL_DATA, 0, Some((b"credential-id", &public_key)));
+
+		assert!(WebauthnService::parse_authenticator_data(&data[..36]).is_err());
+		// The credential id length points past the end of the data
+		assert!(WebauthnService::parse_authenticator_data(&data[..60]).is_err());
+		assert!(WebauthnService::parse_authenticator_data(&data[..data.len() - 1]).is_err());
+	}
+
+	#[test]
+	fn parse_public_key_accepts_es256_and_rs256() {
+		let es256 = WebauthnService::parse_public_key(&es256_cose_key(&signing_key())).unwrap();
+		assert!(matches!(es256, CosePublicKey::Es256(_)));
+
+		let mut modulus = vec![0xFF; 256];
+		modulus[255] = 0xFD;
+		let rs256 = Value::Map(vec![
+			(Value::from(1), Value::from(COSE_KEY_TYPE_RSA)),
+			(Value::from(3), Value::from(COSE_ALGORITHM_RS256)),
+			(Value::from(-1), Value::Bytes(modulus)),
+			(Value::from(-2), Value::Bytes(vec![0x01, 0x00, 0x01])),
+		]);
+		assert!(matches!(
+			WebauthnService::parse_public_key(&rs256).unwrap(),
+			CosePublicKey::Rs256(_)
+		));
+	}
+
+	#[test]
+	fn parse_public_key_rejects_unsupported_keys() {
+		let Value::Map(fields) = es256_cose_key(&signing_key()) else {
+			unreachable!()
+		};
+		let with_field = |label: i64, value: Value| {
+			let mut fields = fields.clone();
+			for (field, field_value) in fields.iter_mut() {
+				if *field == Value::from(label) {
+					*field_value = value.clone();
+				}
+			}
+			Value::Map(fields)
+		};
+
+		// EdDSA
+		assert!(WebauthnService::parse_public_key(&with_field(3, Value::from(-8))).is_err());
+		// P-384
+		assert!(WebauthnService::parse_public_key(&with_field(-1, Value::from(2))).is_err());
+		// Not a point on the curve
+		assert!(WebauthnService::parse_public_key(&with_field(-3, Value::Bytes(vec![0; 32]))).is_err());
+		assert!(WebauthnService::parse_public_key(&Value::Map(Vec::new())).is_err());
+	}
+
+	#[test]
+	fn verify_signature_checks_es256_signatures() {
+		let signing_key = signing_key();
+		let public_key = WebauthnService::parse_public_key(&es256_cose_key(&signing_key)).unwrap();
+		let signature: p256::ecdsa::Signature = signing_key.sign(b"message");
+		let signature = signature.to_der();
+
+		assert!(WebauthnService::verify_signature(&public_key, b"message", signature.as_bytes()).is_ok());
+		assert!(WebauthnService::verify_signature(&public_key, b"other message", signature.as_bytes()).is_err());
+		assert!(WebauthnService::verify_signature(&public_key, b"message", b"not a signature").is_err());
+	}
+
+	#[test]
+	fn sign_count_has_to_increase_unless_unsupported() {
+		assert!(WebauthnService::is_sign_count_valid(0, 0));
+		assert!(WebauthnService::is_sign_count_valid(0, 1));
+		assert!(WebauthnService::is_sign_count_valid(5, 6));
+		assert!(!WebauthnService::is_sign_count_valid(5, 5));
+		assert!(!WebauthnService::is_sign_count_valid(5, 4));
+		assert!(!WebauthnService::is_sign_count_valid(5, 0));
+	}
+
+	fn pending(expires_at: i64) -> PendingChallenge {
+		PendingChallenge {
+			ceremony: Ceremony::Authentication { user_id: None },
+			expires_at,
+		}
+	}
+
+	#[test]
+	fn evict_drops_expired_challenges_first() {
+		let mut challenges = HashMap::from([
+			("expired".to_string(), pending(100)),
+			("pending".to_string(), pending(200)),
+		]);
+
+		WebauthnService::evict(&mut challenges, 2, 150);
+
+		assert_eq!(challenges.len(), 1);
+		assert!(challenges.contains_key("pending"));
+	}
+
+	#[test]
+	fn evict_drops_the_oldest_challenges_when_full() {
+		let mut challenges: HashMap<String, PendingChallenge> =
+			(0..10).map(|i| (format!("challenge-{i}"), pending(200 + i))).collect();
+
+		WebauthnService::evict(&mut challenges, 10, 150);
+
+		assert_eq!(challenges.len(), 9);
+		assert!(!challenges.contains_key("challenge-0"));
+		assert!(challenges.contains_key("challenge-9"));
+	}
+}
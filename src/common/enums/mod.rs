@@ -1,3 +1,4 @@
 pub mod environment_enum;
 pub mod file_storage_type_enum;
+pub mod permission_enum;
 pub mod role_enum;
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+/// Permissions checked by the API. Each one is seeded into the `permissions` table and granted to
+/// the admin role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PermissionEnum {
+	#[serde(rename = "users:write")]
+	UsersWrite,
+	#[serde(rename = "users:delete")]
+	UsersDelete,
+	#[serde(rename = "users:impersonate")]
+	UsersImpersonate,
+	#[serde(rename = "roles:manage")]
+	RolesManage,
+	#[serde(rename = "files:write")]
+	FilesWrite,
+	#[serde(rename = "files:delete")]
+	FilesDelete,
+	#[serde(rename = "service_accounts:manage")]
+	ServiceAccountsManage,
+	#[serde(rename = "lockouts:manage")]
+	LockoutsManage,
+}
+
+impl PermissionEnum {
+	pub const ALL: [PermissionEnum; 8] = [
+		PermissionEnum::UsersWrite,
+		PermissionEnum::UsersDelete,
+		PermissionEnum::UsersImpersonate,
+		PermissionEnum::RolesManage,
+		PermissionEnum::FilesWrite,
+		PermissionEnum::FilesDelete,
+		PermissionEnum::ServiceAccountsManage,
+		PermissionEnum::LockoutsManage,
+	];
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			PermissionEnum::UsersWrite => "users:write",
+			PermissionEnum::UsersDelete => "users:delete",
+			PermissionEnum::UsersImpersonate => "users:impersonate",
+			PermissionEnum::RolesManage => "roles:manage",
+			PermissionEnum::FilesWrite => "files:write",
+			PermissionEnum::FilesDelete => "files:delete",
+			PermissionEnum::ServiceAccountsManage => "service_accounts:manage",
+			PermissionEnum::LockoutsManage => "lockouts:manage",
+		}
+	}
+
+	pub fn description(&self) -> &'static str {
+		match self {
+			PermissionEnum::UsersWrite => "Create and update users",
+			PermissionEnum::UsersDelete => "Delete users",
+			PermissionEnum::UsersImpersonate => "Act as another user",
+			PermissionEnum::RolesManage => "Manage roles, their permissions and user assignments",
+			PermissionEnum::FilesWrite => "Update any file",
+			PermissionEnum::FilesDelete => "Delete any file",
+			PermissionEnum::ServiceAccountsManage => "Manage service accounts",
+			PermissionEnum::LockoutsManage => "View and clear login lockouts",
+		}
+	}
+}
+
+impl Display for PermissionEnum {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
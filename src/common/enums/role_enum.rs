@@ -1,8 +1,8 @@
-use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+/// Roles the application relies on by name. Claims carry plain role names, so roles created
+/// through the roles API work alongside these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RoleEnum {
 	Admin,
 	Manager,
@@ -10,6 +10,8 @@ pub enum RoleEnum {
 }
 
 impl RoleEnum {
+	pub const ALL: [RoleEnum; 3] = [RoleEnum::Admin, RoleEnum::Manager, RoleEnum::User];
+
 	pub fn as_str(&self) -> &'static str {
 		match self {
 			RoleEnum::Admin => "admin",
@@ -18,13 +20,8 @@ impl RoleEnum {
 		}
 	}
 
-	pub fn from_str(s: &str) -> Option<Self> {
-		match s.to_lowercase().as_str() {
-			"admin" => Some(RoleEnum::Admin),
-			"manager" => Some(RoleEnum::Manager),
-			"user" => Some(RoleEnum::User),
-			_ => None,
-		}
+	pub fn is_built_in(name: &str) -> bool {
+		Self::ALL.iter().any(|role| role.as_str() == name)
 	}
 }
 
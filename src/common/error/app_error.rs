@@ -1,7 +1,7 @@
 use crate::i18n::setup::translate;
 use axum::{
 	Json,
-	http::StatusCode,
+	http::{HeaderValue, StatusCode, header::RETRY_AFTER},
 	response::{IntoResponse, Response},
 };
 use sea_orm::DbErr;
@@ -17,10 +17,17 @@ pub enum AppError {
 	AuthenticationError(String),
 	AuthorizationError(String),
 	BadRequest(String),
+	/// Carries the number of seconds after which the client may retry
+	TooManyRequests(u64),
 }
 
 impl IntoResponse for AppError {
 	fn into_response(self) -> Response {
+		let retry_after = match self {
+			AppError::TooManyRequests(retry_after) => Some(retry_after),
+			_ => None,
+		};
+
 		let (status, error_body) = match self {
 			AppError::ValidationError(errors) => (
 				StatusCode::BAD_REQUEST,
@@ -57,9 +64,20 @@ impl IntoResponse for AppError {
 				let translated = rust_i18n::t!("errors.bad_request", message = message);
 				(StatusCode::BAD_REQUEST, json!({"error": translated}))
 			}
+			AppError::TooManyRequests(_) => (
+				StatusCode::TOO_MANY_REQUESTS,
+				json!({"error": translate("errors.too_many_requests")}),
+			),
 		};
 
-		(status, Json(error_body)).into_response()
+		let mut response = (status, Json(error_body)).into_response();
+		if let Some(retry_after) = retry_after {
+			response
+				.headers_mut()
+				.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+		}
+
+		response
 	}
 }
 
@@ -0,0 +1,174 @@
+use crate::common::error::app_error::AppError;
+use crate::config::app_config::AppConfig;
+use axum::extract::ConnectInfo;
+use axum::{extract::FromRequestParts, http::header::USER_AGENT, http::request::Parts};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Details about the client that sent the request, recorded with sessions.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+	pub user_agent: Option<String>,
+	pub ip_address: Option<String>,
+}
+
+/// A reverse proxy allowed to report the client address, a single address or a CIDR range.
+struct TrustedProxy {
+	network: IpAddr,
+	prefix_len: u32,
+}
+
+impl TrustedProxy {
+	fn parse(value: &str) -> Option<Self> {
+		let (address, prefix_len) = match value.trim().split_once('/') {
+			Some((address, prefix_len)) => (address, Some(prefix_len.parse::<u32>().ok()?)),
+			None => (value.trim(), None),
+		};
+		let network: IpAddr = address.parse().ok()?;
+		let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+		let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+
+		(prefix_len <= max_prefix_len).then_some(Self { network, prefix_len })
+	}
+
+	fn contains(&self, ip: IpAddr) -> bool {
+		match (self.network, ip.to_canonical()) {
+			(IpAddr::V4(network), IpAddr::V4(ip)) => {
+				let mask = u32::MAX.checked_shl(32 - self.prefix_len).unwrap_or(0);
+				u32::from(network) & mask == u32::from(ip) & mask
+			}
+			(IpAddr::V6(network), IpAddr::V6(ip)) => {
+				let mask = u128::MAX.checked_shl(128 - self.prefix_len).unwrap_or(0);
+				u128::from(network) & mask == u128::from(ip) & mask
+			}
+			_ => false,
+		}
+	}
+}
+
+/// Starts from the connected peer and walks `X-Forwarded-For` from the right for as long as the hop
+/// is a trusted proxy. The first untrusted hop is the client, entries left of it are client-supplied.
+fn resolve_client_ip(peer: IpAddr, forwarded_for: &[&str], trusted_proxies: &[TrustedProxy]) -> IpAddr {
+	let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+	let mut client_ip = peer;
+	let hops = forwarded_for
+		.iter()
+		.flat_map(|value| value.split(','))
+		.map(str::trim)
+		.filter(|hop| !hop.is_empty())
+		.rev();
+
+	for hop in hops {
+		if !is_trusted(client_ip) {
+			break;
+		}
+
+		match hop.parse::<IpAddr>() {
+			Ok(ip) => client_ip = ip,
+			// A malformed entry cannot be attributed, the proxy that forwarded it is the last known hop
+			Err(_) => break,
+		}
+	}
+
+	client_ip
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+	S: Send + Sync,
+{
+	type Rejection = AppError;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		let user_agent = parts
+			.headers
+			.get(USER_AGENT)
+			.and_then(|value| value.to_str().ok())
+			.map(|value| value.chars().take(MAX_USER_AGENT_LENGTH).collect());
+
+		// X-Forwarded-For is only honoured when the request comes from a configured proxy
+		let trusted_proxies: Vec<TrustedProxy> = parts
+			.extensions
+			.get::<Arc<AppConfig>>()
+			.map(|config| {
+				config
+					.server
+					.trusted_proxies
+					.iter()
+					.filter_map(|value| TrustedProxy::parse(value))
+					.collect()
+			})
+			.unwrap_or_default();
+
+		let forwarded_for: Vec<&str> = parts
+			.headers
+			.get_all("X-Forwarded-For")
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.collect();
+
+		let ip_address = parts
+			.extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(addr)| resolve_client_ip(addr.ip(), &forwarded_for, &trusted_proxies).to_string());
+
+		Ok(ClientInfo { user_agent, ip_address })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn proxies(values: &[&str]) -> Vec<TrustedProxy> {
+		values.iter().filter_map(|value| TrustedProxy::parse(value)).collect()
+	}
+
+	fn ip(value: &str) -> IpAddr {
+		value.parse().unwrap()
+	}
+
+	#[test]
+	fn ignores_forwarded_for_from_untrusted_peer() {
+		let trusted = proxies(&["10.0.0.1"]);
+		let client_ip = resolve_client_ip(ip("203.0.113.9"), &["198.51.100.1"], &trusted);
+		assert_eq!(client_ip, ip("203.0.113.9"));
+	}
+
+	#[test]
+	fn takes_right_most_untrusted_hop() {
+		let trusted = proxies(&["10.0.0.0/8"]);
+		let client_ip = resolve_client_ip(ip("10.0.0.1"), &["1.1.1.1, 198.51.100.7, 10.0.0.2"], &trusted);
+		assert_eq!(client_ip, ip("198.51.100.7"));
+	}
+
+	#[test]
+	fn joins_repeated_headers() {
+		let trusted = proxies(&["10.0.0.1"]);
+		let client_ip = resolve_client_ip(ip("10.0.0.1"), &["1.1.1.1", "198.51.100.7"], &trusted);
+		assert_eq!(client_ip, ip("198.51.100.7"));
+	}
+
+	#[test]
+	fn keeps_last_trusted_hop_when_chain_is_exhausted_or_malformed() {
+		let trusted = proxies(&["10.0.0.0/8"]);
+		assert_eq!(resolve_client_ip(ip("10.0.0.1"), &[], &trusted), ip("10.0.0.1"));
+		assert_eq!(
+			resolve_client_ip(ip("10.0.0.1"), &["garbage, 10.0.0.2"], &trusted),
+			ip("10.0.0.2")
+		);
+	}
+
+	#[test]
+	fn matches_cidr_ranges_and_mapped_addresses() {
+		let proxy = TrustedProxy::parse("192.168.0.0/16").unwrap();
+		assert!(proxy.contains(ip("192.168.10.1")));
+		assert!(proxy.contains(ip("::ffff:192.168.10.1")));
+		assert!(!proxy.contains(ip("192.169.0.1")));
+		assert!(TrustedProxy::parse("10.0.0.0/33").is_none());
+		assert!(TrustedProxy::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+	}
+}
@@ -1 +1,2 @@
 pub mod i18n_middleware;
+pub mod rate_limit_middleware;
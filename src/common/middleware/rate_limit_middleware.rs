@@ -0,0 +1,43 @@
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::extractor::client_info_extractor::ClientInfo;
+use crate::common::services::rate_limit_service::RateLimitServiceTrait;
+use axum::{
+	extract::Request,
+	http::header::AUTHORIZATION,
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+pub async fn rate_limit_middleware(client_info: ClientInfo, request: Request, next: Next) -> Response {
+	let Some(rate_limit_service) = request.extensions().get::<Arc<dyn RateLimitServiceTrait>>().cloned() else {
+		return AppError::InternalError.into_response();
+	};
+
+	// The token is only used to tell users apart, it is still fully verified by the JwtAuth extractor
+	let subject = request
+		.headers()
+		.get(AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Bearer "))
+		.zip(request.extensions().get::<Arc<dyn JwtKeysServiceTrait>>())
+		.and_then(|(token, jwt_keys_service)| jwt_keys_service.decode(token).ok())
+		.map(|claims| match claims.client_id {
+			Some(client_id) => format!("client:{}", client_id),
+			None => format!("user:{}", claims.sub),
+		});
+
+	if let Err(err) = rate_limit_service
+		.check(
+			request.uri().path(),
+			client_info.ip_address.as_deref(),
+			subject.as_deref(),
+		)
+		.await
+	{
+		return err.into_response();
+	}
+
+	next.run(request).await
+}
@@ -1,4 +1,7 @@
 pub mod enums;
 pub mod error;
+pub mod extractor;
 pub mod middleware;
+pub mod services;
 pub mod r#struct;
+pub mod utils;
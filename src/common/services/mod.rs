@@ -0,0 +1,2 @@
+pub mod rate_limit_service;
+pub mod rate_limit_store;
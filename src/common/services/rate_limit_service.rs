@@ -0,0 +1,102 @@
+use crate::common::error::app_error::AppError;
+use crate::common::services::rate_limit_store::{RateLimitDecision, RateLimitStoreTrait};
+use crate::config::app_config::{RateLimitConfig, RateLimitRuleConfig};
+use crate::di::AppConfigTrait;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Routes sharing the stricter `auth` limits, as they are the usual targets of credential stuffing.
+const AUTH_ROUTES: &[&str] = &[
+	"/api/auth/login",
+	"/api/auth/login/2fa",
+	"/api/auth/reauthenticate",
+	"/api/auth/magic-link",
+	"/api/auth/register",
+	"/api/auth/confirm-email/resend",
+	"/api/auth/password/reset",
+	"/api/auth/token",
+	"/api/auth/webauthn/login/start",
+	"/api/auth/webauthn/login/finish",
+];
+
+/// Applies the configured limits to a request.
+///
+/// Every request is counted against its client IP and, when it carries a valid access token, against
+/// the user or service account as well. Each route group has its own buckets, so exhausting the `auth` limit does not
+/// block the rest of the API.
+pub struct RateLimitService {
+	rate_limit_store: Arc<dyn RateLimitStoreTrait>,
+	config: RateLimitConfig,
+}
+
+impl RateLimitService {
+	pub fn new(rate_limit_store: Arc<dyn RateLimitStoreTrait>, app_config: Arc<dyn AppConfigTrait>) -> Self {
+		let config = app_config.get_config().security.rate_limit.clone();
+		Self {
+			rate_limit_store,
+			config,
+		}
+	}
+
+	fn route_group(&self, path: &str) -> (&'static str, &RateLimitRuleConfig) {
+		if AUTH_ROUTES.contains(&path.trim_end_matches('/')) {
+			("auth", &self.config.auth)
+		} else {
+			("default", &self.config.default)
+		}
+	}
+
+	async fn consume(&self, key: &str, rule: &RateLimitRuleConfig) -> Result<(), AppError> {
+		match self
+			.rate_limit_store
+			.consume(key, rule.requests, Duration::from_secs(rule.period))
+			.await?
+		{
+			RateLimitDecision::Allowed => Ok(()),
+			RateLimitDecision::Limited { retry_after } => Err(AppError::TooManyRequests(retry_after)),
+		}
+	}
+}
+
+#[async_trait]
+pub trait RateLimitServiceTrait: Send + Sync {
+	async fn check(&self, path: &str, ip_address: Option<&str>, subject: Option<&str>) -> Result<(), AppError>;
+	async fn check_email(&self, email: &str) -> Result<(), AppError>;
+	async fn clean_expired(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl RateLimitServiceTrait for RateLimitService {
+	async fn check(&self, path: &str, ip_address: Option<&str>, subject: Option<&str>) -> Result<(), AppError> {
+		if !self.config.enabled {
+			return Ok(());
+		}
+
+		let (group, rule) = self.route_group(path);
+
+		if let Some(ip_address) = ip_address {
+			self.consume(&format!("{}:ip:{}", group, ip_address), rule).await?;
+		}
+
+		if let Some(subject) = subject {
+			self.consume(&format!("{}:{}", group, subject), rule).await?;
+		}
+
+		Ok(())
+	}
+
+	async fn check_email(&self, email: &str) -> Result<(), AppError> {
+		if !self.config.enabled {
+			return Ok(());
+		}
+
+		// Counted per address whether or not it belongs to an account, so the limit reveals nothing
+		self.consume(&format!("email:{}", email.trim().to_lowercase()), &self.config.email)
+			.await
+	}
+
+	async fn clean_expired(&self) -> Result<(), AppError> {
+		self.rate_limit_store.clean_expired().await
+	}
+}
@@ -0,0 +1,219 @@
+use crate::common::error::app_error::AppError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Outcome of taking a token from a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+	Allowed,
+	Limited { retry_after: u64 },
+}
+
+/// Backend keeping the token buckets.
+///
+/// A bucket holds up to `capacity` tokens and gets `capacity` new tokens every `period`. Every request
+/// takes one token; a request finding the bucket empty is limited.
+#[async_trait]
+pub trait RateLimitStoreTrait: Send + Sync {
+	async fn consume(&self, key: &str, capacity: u32, period: Duration) -> Result<RateLimitDecision, AppError>;
+	async fn clean_expired(&self) -> Result<(), AppError>;
+}
+
+struct TokenBucket {
+	tokens: f64,
+	capacity: f64,
+	refill_per_second: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+		self.last_refill = now;
+	}
+}
+
+/// Keeps the buckets in memory, so limits are per application instance.
+///
+/// At most `max_buckets` are kept, so requests from many different addresses cannot exhaust the memory.
+pub struct InMemoryRateLimitStore {
+	buckets: Mutex<HashMap<String, TokenBucket>>,
+	max_buckets: usize,
+}
+
+impl InMemoryRateLimitStore {
+	pub fn new(max_buckets: usize) -> Self {
+		Self {
+			buckets: Mutex::new(HashMap::new()),
+			max_buckets: max_buckets.max(1),
+		}
+	}
+
+	/// Makes room for a new bucket. Full buckets go first, as dropping them changes nothing; when that is
+	/// not enough, the least recently used tenth of the buckets is dropped, so the scan is not repeated
+	/// for every new key.
+	fn evict(buckets: &mut HashMap<String, TokenBucket>, max_buckets: usize, now: Instant) {
+		buckets.retain(|_, bucket| {
+			bucket.refill(now);
+			bucket.tokens < bucket.capacity
+		});
+
+		if buckets.len() < max_buckets {
+			return;
+		}
+
+		let target = max_buckets - max_buckets.div_ceil(10);
+		let mut by_last_use: Vec<(Instant, String)> = buckets
+			.iter()
+			.map(|(key, bucket)| (bucket.last_refill, key.clone()))
+			.collect();
+		by_last_use.sort_unstable();
+
+		for (_, key) in by_last_use.into_iter().take(buckets.len() - target) {
+			buckets.remove(&key);
+		}
+	}
+
+	fn consume_at(
+		&self,
+		key: &str,
+		capacity: u32,
+		period: Duration,
+		now: Instant,
+	) -> Result<RateLimitDecision, AppError> {
+		let capacity = f64::from(capacity.max(1));
+		let refill_per_second = capacity / period.as_secs_f64().max(1.0);
+
+		let mut buckets = self.buckets.lock().map_err(|_| AppError::InternalError)?;
+		if !buckets.contains_key(key) && buckets.len() >= self.max_buckets {
+			Self::evict(&mut buckets, self.max_buckets, now);
+		}
+
+		let bucket = buckets.entry(key.to_string()).or_insert_with(|| TokenBucket {
+			tokens: capacity,
+			capacity,
+			refill_per_second,
+			last_refill: now,
+		});
+
+		bucket.refill(now);
+
+		if bucket.tokens >= 1.0 {
+			bucket.tokens -= 1.0;
+			return Ok(RateLimitDecision::Allowed);
+		}
+
+		let retry_after = ((1.0 - bucket.tokens) / bucket.refill_per_second).ceil() as u64;
+
+		Ok(RateLimitDecision::Limited {
+			retry_after: retry_after.max(1),
+		})
+	}
+}
+
+#[async_trait]
+impl RateLimitStoreTrait for InMemoryRateLimitStore {
+	async fn consume(&self, key: &str, capacity: u32, period: Duration) -> Result<RateLimitDecision, AppError> {
+		self.consume_at(key, capacity, period, Instant::now())
+	}
+
+	async fn clean_expired(&self) -> Result<(), AppError> {
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock().map_err(|_| AppError::InternalError)?;
+
+		// A bucket that has refilled completely behaves exactly like a new one
+		buckets.retain(|_, bucket| {
+			bucket.refill(now);
+			bucket.tokens < bucket.capacity
+		});
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PERIOD: Duration = Duration::from_secs(60);
+
+	#[test]
+	fn refill_adds_tokens_proportionally_up_to_capacity() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket {
+			tokens: 0.0,
+			capacity: 10.0,
+			refill_per_second: 0.5,
+			last_refill: start,
+		};
+
+		bucket.refill(start + Duration::from_secs(4));
+		assert_eq!(bucket.tokens, 2.0);
+
+		bucket.refill(start + Duration::from_secs(3600));
+		assert_eq!(bucket.tokens, 10.0);
+	}
+
+	#[test]
+	fn consume_limits_once_the_bucket_is_empty() {
+		let store = InMemoryRateLimitStore::new(10);
+		let now = Instant::now();
+
+		for _ in 0..3 {
+			assert_eq!(
+				store.consume_at("key", 3, PERIOD, now).unwrap(),
+				RateLimitDecision::Allowed
+			);
+		}
+
+		// 3 tokens per 60 seconds, the next one arrives after 20 seconds
+		assert_eq!(
+			store.consume_at("key", 3, PERIOD, now).unwrap(),
+			RateLimitDecision::Limited { retry_after: 20 }
+		);
+		assert_eq!(
+			store
+				.consume_at("key", 3, PERIOD, now + Duration::from_secs(20))
+				.unwrap(),
+			RateLimitDecision::Allowed
+		);
+	}
+
+	#[test]
+	fn consume_keeps_keys_apart() {
+		let store = InMemoryRateLimitStore::new(10);
+		let now = Instant::now();
+
+		assert_eq!(
+			store.consume_at("first", 1, PERIOD, now).unwrap(),
+			RateLimitDecision::Allowed
+		);
+		assert_eq!(
+			store.consume_at("second", 1, PERIOD, now).unwrap(),
+			RateLimitDecision::Allowed
+		);
+		assert!(matches!(
+			store.consume_at("first", 1, PERIOD, now).unwrap(),
+			RateLimitDecision::Limited { .. }
+		));
+	}
+
+	#[test]
+	fn consume_never_keeps_more_than_max_buckets() {
+		let store = InMemoryRateLimitStore::new(10);
+		let start = Instant::now();
+
+		for i in 0..100 {
+			let now = start + Duration::from_millis(i);
+			store.consume_at(&format!("key-{i}"), 5, PERIOD, now).unwrap();
+			assert!(store.buckets.lock().unwrap().len() <= 10);
+		}
+
+		// The most recently used buckets are the ones kept
+		assert!(store.buckets.lock().unwrap().contains_key("key-99"));
+		assert!(!store.buckets.lock().unwrap().contains_key("key-0"));
+	}
+}
@@ -0,0 +1,48 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const OPAQUE_TOKEN_BYTES: usize = 32;
+
+/// Generates a random, URL-safe token with 256 bits of entropy.
+pub fn generate_opaque_token() -> String {
+	let mut bytes = [0u8; OPAQUE_TOKEN_BYTES];
+	OsRng.fill_bytes(&mut bytes);
+	URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Hashes a token with HMAC-SHA256 so only the digest has to be stored.
+pub fn hash_token(secret: &str, token: &str) -> String {
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+	mac.update(token.as_bytes());
+	hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks a token against a digest from `hash_token` in constant time.
+pub fn verify_token_hash(secret: &str, token: &str, token_hash: &str) -> bool {
+	let Ok(expected) = hex::decode(token_hash) else {
+		return false;
+	};
+
+	let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+	mac.update(token.as_bytes());
+	mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn verify_token_hash_accepts_only_the_hashed_token() {
+		let token_hash = hash_token("secret", "token");
+
+		assert!(verify_token_hash("secret", "token", &token_hash));
+		assert!(!verify_token_hash("secret", "other token", &token_hash));
+		assert!(!verify_token_hash("other secret", "token", &token_hash));
+		assert!(!verify_token_hash("secret", "token", "not hex"));
+		assert!(!verify_token_hash("secret", "token", &token_hash[..32]));
+	}
+}
@@ -1,5 +1,6 @@
 use crate::config::database_config::DatabaseConfig;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
@@ -7,12 +8,18 @@ pub struct ServerConfig {
 	pub app_host: String,
 	pub app_environment: String,
 	pub app_url: String,
+	/// Reverse proxies (addresses or CIDR ranges) whose `X-Forwarded-For` header is trusted
+	#[serde(default)]
+	pub trusted_proxies: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct JwtAccessTokenConfig {
 	pub secret: String,
 	pub expires_in: i64,
+	pub algorithm: String,
+	pub keys_dir: String,
+	pub active_kid: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -27,16 +34,145 @@ pub struct ConfirmationTokenConfig {
 	pub expires_in: i64,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct PersonalAccessTokenConfig {
+	pub secret: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClientCredentialsConfig {
+	pub secret: String,
+	pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MfaTokenConfig {
+	pub expires_in: i64,
+	pub max_attempts: i32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MagicLoginTokenConfig {
+	pub expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ImpersonationTokenConfig {
+	pub expires_in: i64,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct TokensConfig {
 	pub jwt_access_token: JwtAccessTokenConfig,
 	pub jwt_refresh_token: JwtRefreshTokenConfig,
 	pub confirmation_token: ConfirmationTokenConfig,
+	pub mfa_token: MfaTokenConfig,
+	pub magic_login_token: MagicLoginTokenConfig,
+	pub impersonation_token: ImpersonationTokenConfig,
+	pub personal_access_token: PersonalAccessTokenConfig,
+	pub client_credentials: ClientCredentialsConfig,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TwoFactorConfig {
+	pub issuer: String,
+	pub recovery_codes_count: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PasswordHashingConfig {
+	/// Argon2id memory cost in KiB
+	pub memory_cost: u32,
+	pub time_cost: u32,
+	pub parallelism: u32,
+	/// Secret mixed into every hash, kept out of the database
+	pub pepper: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PasswordPolicyConfig {
+	pub min_length: usize,
+	pub max_length: usize,
+	pub require_lowercase: bool,
+	pub require_uppercase: bool,
+	pub require_digit: bool,
+	pub require_symbol: bool,
+	/// Rejects passwords containing the username or the local part of the email address
+	pub reject_user_info: bool,
+	/// Number of previous passwords that cannot be reused, 0 disables the check
+	pub history_size: u64,
+	/// Directory with breached password hash ranges, one `<SHA-1 prefix>.txt` file per range
+	pub breached_passwords_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LoginProtectionConfig {
+	pub max_failed_attempts: i32,
+	pub max_failed_attempts_per_ip: i32,
+	pub failure_window: i64,
+	pub lockout_duration: i64,
+	pub progressive_delay_base_ms: u64,
+	pub progressive_delay_max_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitRuleConfig {
+	pub requests: u32,
+	pub period: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+	pub enabled: bool,
+	/// Buckets kept in memory at most, the least recently used ones are dropped beyond that
+	pub max_buckets: usize,
+	pub default: RateLimitRuleConfig,
+	pub auth: RateLimitRuleConfig,
+	pub email: RateLimitRuleConfig,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcProviderConfig {
+	pub issuer_url: String,
+	pub client_id: String,
+	pub client_secret: String,
+	#[serde(default = "default_oidc_scopes")]
+	pub scopes: String,
+}
+
+fn default_oidc_scopes() -> String {
+	"openid email profile".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct OidcConfig {
+	#[serde(default)]
+	pub providers: HashMap<String, OidcProviderConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebauthnConfig {
+	pub rp_id: String,
+	pub rp_name: String,
+	pub origin: String,
+	pub challenge_expires_in: i64,
+	/// Pending challenges kept in memory at most, the oldest ones are dropped beyond that
+	pub max_challenges: usize,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct SecurityConfig {
 	pub tokens: TokensConfig,
+	pub two_factor: TwoFactorConfig,
+	pub password_hashing: PasswordHashingConfig,
+	pub password_policy: PasswordPolicyConfig,
+	pub login_protection: LoginProtectionConfig,
+	pub rate_limit: RateLimitConfig,
+	#[serde(default)]
+	pub oidc: OidcConfig,
+	pub webauthn: WebauthnConfig,
+	/// Makes password reset and registration respond the same way for known and unknown emails
+	pub enumeration_safe: bool,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -85,10 +221,53 @@ impl AppConfig {
 			// Security
 			.set_default("security.tokens.jwt_access_token.secret", "secret")?
 			.set_default("security.tokens.jwt_access_token.expires_in", 3600)?
+			.set_default("security.tokens.jwt_access_token.algorithm", "EdDSA")?
+			.set_default("security.tokens.jwt_access_token.keys_dir", "keys/jwt")?
+			.set_default("security.tokens.jwt_access_token.active_kid", "default")?
 			.set_default("security.tokens.jwt_refresh_token.secret", "secret")?
 			.set_default("security.tokens.jwt_refresh_token.expires_in", 2592000)?
 			.set_default("security.tokens.confirmation_token.secret", "secret")?
 			.set_default("security.tokens.confirmation_token.expires_in", 86400)?
+			.set_default("security.tokens.mfa_token.expires_in", 300)?
+			.set_default("security.tokens.mfa_token.max_attempts", 3)?
+			.set_default("security.tokens.magic_login_token.expires_in", 900)?
+			.set_default("security.tokens.impersonation_token.expires_in", 900)?
+			.set_default("security.tokens.personal_access_token.secret", "secret")?
+			.set_default("security.tokens.client_credentials.secret", "secret")?
+			.set_default("security.tokens.client_credentials.expires_in", 3600)?
+			.set_default("security.enumeration_safe", false)?
+			.set_default("security.two_factor.issuer", "Rust Axum App")?
+			.set_default("security.two_factor.recovery_codes_count", 10)?
+			.set_default("security.password_hashing.memory_cost", 19456)?
+			.set_default("security.password_hashing.time_cost", 2)?
+			.set_default("security.password_hashing.parallelism", 1)?
+			.set_default("security.password_policy.min_length", 8)?
+			.set_default("security.password_policy.max_length", 128)?
+			.set_default("security.password_policy.require_lowercase", false)?
+			.set_default("security.password_policy.require_uppercase", false)?
+			.set_default("security.password_policy.require_digit", true)?
+			.set_default("security.password_policy.require_symbol", false)?
+			.set_default("security.password_policy.reject_user_info", true)?
+			.set_default("security.password_policy.history_size", 5)?
+			.set_default("security.login_protection.max_failed_attempts", 5)?
+			.set_default("security.login_protection.max_failed_attempts_per_ip", 20)?
+			.set_default("security.login_protection.failure_window", 900)?
+			.set_default("security.login_protection.lockout_duration", 900)?
+			.set_default("security.login_protection.progressive_delay_base_ms", 250)?
+			.set_default("security.login_protection.progressive_delay_max_ms", 5000)?
+			.set_default("security.rate_limit.enabled", true)?
+			.set_default("security.rate_limit.max_buckets", 100_000)?
+			.set_default("security.rate_limit.default.requests", 120)?
+			.set_default("security.rate_limit.default.period", 60)?
+			.set_default("security.rate_limit.auth.requests", 10)?
+			.set_default("security.rate_limit.auth.period", 60)?
+			.set_default("security.rate_limit.email.requests", 3)?
+			.set_default("security.rate_limit.email.period", 3600)?
+			.set_default("security.webauthn.rp_id", "localhost")?
+			.set_default("security.webauthn.rp_name", "Rust Axum App")?
+			.set_default("security.webauthn.origin", "http://localhost:3000")?
+			.set_default("security.webauthn.challenge_expires_in", 300)?
+			.set_default("security.webauthn.max_challenges", 10_000)?
 			// Files
 			.set_default("files.upload_dir", "uploads")?
 			.set_default("files.base_url", "/uploads")?
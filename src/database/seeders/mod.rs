@@ -7,6 +7,7 @@ pub async fn run_seeders(db: &DatabaseConnection) -> Result<(), AppError> {
 	println!("Running seeders...");
 
 	roles_seeder::seed_roles(db).await?;
+	roles_seeder::seed_permissions(db).await?;
 
 	println!("Seeders completed successfully.");
 	Ok(())
@@ -1,9 +1,12 @@
 use chrono::{DateTime, Utc};
 use sea_orm::TransactionTrait;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 
+use crate::common::enums::permission_enum::PermissionEnum;
 use crate::common::enums::role_enum::RoleEnum;
 use crate::common::error::app_error::AppError;
+use crate::roles::entities::permissions::{self, Entity as Permission};
+use crate::roles::entities::role_permissions::{self, Entity as RolePermission};
 use crate::roles::entities::roles;
 use crate::roles::entities::roles::Entity as Role;
 
@@ -19,24 +22,28 @@ pub async fn seed_roles(db: &DatabaseConnection) -> Result<(), AppError> {
 
 	let txn = db.begin().await.map_err(|_| AppError::InternalError)?;
 
+	// Each role inherits from the one before it: admin > manager > user
 	let roles = [
-		(RoleEnum::Admin, "Administrator with full access"),
 		(RoleEnum::User, "Regular user with limited access"),
 		(RoleEnum::Manager, "User with management privileges"),
+		(RoleEnum::Admin, "Administrator with full access"),
 	];
 
 	let now: DateTime<Utc> = Utc::now();
+	let mut parent_id = None;
 
 	for (role_type, description) in roles {
 		let role_model = roles::ActiveModel {
 			id: Default::default(),
 			name: Set(role_type.as_str().to_string()),
 			description: Set(Some(description.to_string())),
+			parent_id: Set(parent_id),
 			created_at: Set(now.into()),
 			updated_at: Set(Some(now.into())),
 		};
 
-		role_model.save(&txn).await.map_err(|_| AppError::InternalError)?;
+		let role = role_model.insert(&txn).await.map_err(|_| AppError::InternalError)?;
+		parent_id = Some(role.id);
 
 		println!("Created role: {}", role_type.as_str());
 	}
@@ -46,3 +53,73 @@ pub async fn seed_roles(db: &DatabaseConnection) -> Result<(), AppError> {
 
 	Ok(())
 }
+
+/// Adds permissions introduced since the last run and keeps all of them granted to the admin role.
+pub async fn seed_permissions(db: &DatabaseConnection) -> Result<(), AppError> {
+	println!("Seeding permissions...");
+
+	let txn = db.begin().await.map_err(|_| AppError::InternalError)?;
+
+	let admin_role = Role::find()
+		.filter(roles::Column::Name.eq(RoleEnum::Admin.as_str()))
+		.one(&txn)
+		.await
+		.map_err(|_| AppError::InternalError)?
+		.ok_or(AppError::InternalError)?;
+
+	let now: DateTime<Utc> = Utc::now();
+
+	for permission in PermissionEnum::ALL {
+		let existing_permission = Permission::find()
+			.filter(permissions::Column::Name.eq(permission.as_str()))
+			.one(&txn)
+			.await
+			.map_err(|_| AppError::InternalError)?;
+
+		let permission_id = match existing_permission {
+			Some(existing_permission) => existing_permission.id,
+			None => {
+				let permission_model = permissions::ActiveModel {
+					id: Default::default(),
+					name: Set(permission.as_str().to_string()),
+					description: Set(Some(permission.description().to_string())),
+					created_at: Set(now.into()),
+				};
+
+				let created = permission_model
+					.insert(&txn)
+					.await
+					.map_err(|_| AppError::InternalError)?;
+				println!("Created permission: {}", permission.as_str());
+				created.id
+			}
+		};
+
+		let is_granted = RolePermission::find()
+			.filter(role_permissions::Column::RoleId.eq(admin_role.id))
+			.filter(role_permissions::Column::PermissionId.eq(permission_id))
+			.one(&txn)
+			.await
+			.map_err(|_| AppError::InternalError)?
+			.is_some();
+
+		if !is_granted {
+			let role_permission_model = role_permissions::ActiveModel {
+				id: Default::default(),
+				role_id: Set(admin_role.id),
+				permission_id: Set(permission_id),
+				created_at: Set(now.into()),
+			};
+
+			role_permission_model
+				.insert(&txn)
+				.await
+				.map_err(|_| AppError::InternalError)?;
+		}
+	}
+
+	txn.commit().await.map_err(|_| AppError::InternalError)?;
+	println!("Permissions seeded successfully.");
+
+	Ok(())
+}
@@ -1,16 +1,43 @@
+use crate::auth::repositories::impersonation_log_repository::ImpersonationLogRepository;
+use crate::auth::repositories::login_lockout_repository::LoginLockoutRepository;
+use crate::auth::repositories::personal_access_token_repository::PersonalAccessTokenRepository;
 use crate::auth::repositories::refresh_token_repository::{RefreshTokenRepository, RefreshTokenRepositoryTrait};
+use crate::auth::repositories::revoked_access_token_repository::RevokedAccessTokenRepository;
+use crate::auth::repositories::user_identity_repository::UserIdentityRepository;
+use crate::auth::repositories::webauthn_credential_repository::WebauthnCredentialRepository;
 use crate::auth::services::auth_service::{AuthService, AuthServiceTrait};
 use crate::auth::services::confirmation_token_service::{ConfirmationTokenService, ConfirmationTokenServiceTrait};
+use crate::auth::services::impersonation_service::{ImpersonationService, ImpersonationServiceTrait};
+use crate::auth::services::jwt_keys_service::{JwtKeysService, JwtKeysServiceTrait};
+use crate::auth::services::login_protection_service::{LoginProtectionService, LoginProtectionServiceTrait};
+use crate::auth::services::magic_link_service::{MagicLinkService, MagicLinkServiceTrait};
+use crate::auth::services::oidc_service::{OidcService, OidcServiceTrait};
+use crate::auth::services::personal_access_token_service::{
+	PersonalAccessTokenService, PersonalAccessTokenServiceTrait,
+};
 use crate::auth::services::refresh_token_service::{RefreshTokenService, RefreshTokenServiceTrait};
+use crate::auth::services::token_revocation_service::{TokenRevocationService, TokenRevocationServiceTrait};
+use crate::auth::services::two_factor_service::{TwoFactorService, TwoFactorServiceTrait};
+use crate::auth::services::webauthn_service::{WebauthnService, WebauthnServiceTrait};
+use crate::common::error::app_error::AppError;
+use crate::common::services::rate_limit_service::{RateLimitService, RateLimitServiceTrait};
+use crate::common::services::rate_limit_store::{InMemoryRateLimitStore, RateLimitStoreTrait};
 use crate::config::app_config::AppConfig;
 use crate::emails::services::emails_service::{EmailsService, EmailsServiceTrait};
 use crate::files::repositories::files_repository::FilesRepository;
 use crate::files::services::files_service::{FilesService, FilesServiceTrait};
+use crate::roles::repositories::permissions_repository::PermissionsRepository;
 use crate::roles::repositories::roles_repository::{RolesRepository, RolesRepositoryTrait};
 use crate::roles::repositories::user_roles_repository::{UserRolesRepository, UserRolesRepositoryTrait};
+use crate::roles::services::permissions_service::{PermissionsService, PermissionsServiceTrait};
 use crate::roles::services::roles_service::{RolesService, RolesServiceTrait};
 use crate::roles::services::user_roles_service::{UserRolesService, UserRolesServiceTrait};
+use crate::service_accounts::repositories::service_accounts_repository::ServiceAccountsRepository;
+use crate::service_accounts::services::service_accounts_service::{
+	ServiceAccountsService, ServiceAccountsServiceTrait,
+};
 use crate::users::repositories::users_repository::{UsersRepository, UsersRepositoryTrait};
+use crate::users::services::password_policy_service::{PasswordPolicyService, PasswordPolicyServiceTrait};
 use crate::users::services::users_service::{UsersService, UsersServiceTrait};
 use sea_orm::DatabaseConnection;
 use std::sync::Arc;
@@ -63,6 +90,7 @@ pub struct AppModule {
 
 	// Add dependencies
 	pub users_service: Arc<dyn UsersServiceTrait>,
+	pub password_policy_service: Arc<dyn PasswordPolicyServiceTrait>,
 	pub auth_service: Arc<dyn AuthServiceTrait>,
 	pub refresh_token_service: Arc<dyn RefreshTokenServiceTrait>,
 	pub email_service: Arc<dyn EmailsServiceTrait>,
@@ -70,6 +98,18 @@ pub struct AppModule {
 	pub confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
 	pub files_service: Arc<dyn FilesServiceTrait>,
 	pub roles_service: Arc<dyn RolesServiceTrait>,
+	pub permissions_service: Arc<dyn PermissionsServiceTrait>,
+	pub two_factor_service: Arc<dyn TwoFactorServiceTrait>,
+	pub jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
+	pub token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
+	pub login_protection_service: Arc<dyn LoginProtectionServiceTrait>,
+	pub rate_limit_service: Arc<dyn RateLimitServiceTrait>,
+	pub personal_access_token_service: Arc<dyn PersonalAccessTokenServiceTrait>,
+	pub service_accounts_service: Arc<dyn ServiceAccountsServiceTrait>,
+	pub oidc_service: Arc<dyn OidcServiceTrait>,
+	pub webauthn_service: Arc<dyn WebauthnServiceTrait>,
+	pub magic_link_service: Arc<dyn MagicLinkServiceTrait>,
+	pub impersonation_service: Arc<dyn ImpersonationServiceTrait>,
 	pub refresh_token_repository: Arc<dyn RefreshTokenRepositoryTrait>,
 	pub users_repository: Arc<dyn UsersRepositoryTrait>,
 	pub roles_repository: Arc<dyn RolesRepositoryTrait>,
@@ -77,7 +117,7 @@ pub struct AppModule {
 }
 
 // Dependency injection
-pub fn initialize_di(db: DatabaseConnection, config: Arc<AppConfig>) -> AppModule {
+pub fn initialize_di(db: DatabaseConnection, config: Arc<AppConfig>) -> Result<AppModule, AppError> {
 	// Add db connection and config
 	let db_arc = Arc::new(db);
 	let db_connection = Arc::new(DatabaseConnectionImpl::new(db_arc)) as Arc<dyn DatabaseConnectionTrait>;
@@ -88,41 +128,137 @@ pub fn initialize_di(db: DatabaseConnection, config: Arc<AppConfig>) -> AppModul
 	let users_repository = Arc::new(UsersRepository::new(db_connection.clone()));
 	let roles_repository = Arc::new(RolesRepository::new(db_connection.clone()));
 	let user_roles_repository = Arc::new(UserRolesRepository::new(db_connection.clone()));
+	let permissions_repository = Arc::new(PermissionsRepository::new(db_connection.clone()));
 	let files_repository = Arc::new(FilesRepository::new(db_connection.clone()));
+	let revoked_access_token_repository = Arc::new(RevokedAccessTokenRepository::new(db_connection.clone()));
+	let login_lockout_repository = Arc::new(LoginLockoutRepository::new(db_connection.clone()));
+	let personal_access_token_repository = Arc::new(PersonalAccessTokenRepository::new(db_connection.clone()));
+	let service_accounts_repository = Arc::new(ServiceAccountsRepository::new(db_connection.clone()));
+	let user_identity_repository = Arc::new(UserIdentityRepository::new(db_connection.clone()));
+	let webauthn_credential_repository = Arc::new(WebauthnCredentialRepository::new(db_connection.clone()));
+	let impersonation_log_repository = Arc::new(ImpersonationLogRepository::new(db_connection.clone()));
 
 	// 2. Add basic dependencies
 	let email_service = Arc::new(EmailsService::new(app_config.clone()));
 	let confirmation_token_service = Arc::new(ConfirmationTokenService::new(app_config.clone()));
+	let jwt_keys_service = Arc::new(JwtKeysService::new(app_config.as_ref())?) as Arc<dyn JwtKeysServiceTrait>;
+	let rate_limit_store = Arc::new(InMemoryRateLimitStore::new(
+		app_config.get_config().security.rate_limit.max_buckets,
+	)) as Arc<dyn RateLimitStoreTrait>;
 
 	// 3. Add dependencies with sub-dependencies
-	let roles_service = Arc::new(RolesService::new(roles_repository.clone()));
-	let user_roles_service = Arc::new(UserRolesService::new(user_roles_repository.clone()));
+	let token_revocation_service = Arc::new(TokenRevocationService::new(
+		revoked_access_token_repository.clone(),
+		users_repository.clone(),
+	));
+	let login_protection_service = Arc::new(LoginProtectionService::new(
+		login_lockout_repository.clone(),
+		users_repository.clone(),
+		confirmation_token_service.clone(),
+		email_service.clone(),
+		app_config.clone(),
+	));
+	let rate_limit_service = Arc::new(RateLimitService::new(rate_limit_store.clone(), app_config.clone()));
+	let permissions_service = Arc::new(PermissionsService::new(
+		permissions_repository.clone(),
+		roles_repository.clone(),
+	));
+	let user_roles_service = Arc::new(UserRolesService::new(
+		user_roles_repository.clone(),
+		roles_repository.clone(),
+		users_repository.clone(),
+		token_revocation_service.clone(),
+	));
+	let roles_service = Arc::new(RolesService::new(
+		roles_repository.clone(),
+		user_roles_repository.clone(),
+		user_roles_service.clone(),
+		token_revocation_service.clone(),
+	));
+	let password_policy_service = Arc::new(PasswordPolicyService::new(app_config.clone()));
+	let refresh_token_service = Arc::new(RefreshTokenService::new(
+		refresh_token_repository.clone(),
+		user_roles_service.clone(),
+		email_service.clone(),
+		jwt_keys_service.clone(),
+		app_config.clone(),
+	));
 	let users_service = Arc::new(UsersService::new(
 		users_repository.clone(),
 		user_roles_service.clone(),
 		email_service.clone(),
 		confirmation_token_service.clone(),
+		token_revocation_service.clone(),
+		refresh_token_service.clone(),
+		login_protection_service.clone(),
+		password_policy_service.clone(),
 		app_config.clone(),
 	));
 
+	let two_factor_service = Arc::new(TwoFactorService::new(users_repository.clone(), app_config.clone()));
+
 	let auth_service = Arc::new(AuthService::new(
 		users_service.clone(),
 		user_roles_service.clone(),
+		two_factor_service.clone(),
+		confirmation_token_service.clone(),
+		jwt_keys_service.clone(),
+		password_policy_service.clone(),
+		login_protection_service.clone(),
 		app_config.clone(),
 	));
 
-	let refresh_token_service = Arc::new(RefreshTokenService::new(
-		refresh_token_repository.clone(),
+	let personal_access_token_service = Arc::new(PersonalAccessTokenService::new(
+		personal_access_token_repository.clone(),
+		users_repository.clone(),
+		user_roles_service.clone(),
+		app_config.clone(),
+	));
+
+	let service_accounts_service = Arc::new(ServiceAccountsService::new(
+		service_accounts_repository.clone(),
+		roles_repository.clone(),
+		jwt_keys_service.clone(),
+		app_config.clone(),
+	));
+
+	let oidc_service = Arc::new(OidcService::new(
+		user_identity_repository.clone(),
+		users_repository.clone(),
+		users_service.clone(),
+		user_roles_service.clone(),
+		app_config.clone(),
+	));
+
+	let webauthn_service = Arc::new(WebauthnService::new(
+		webauthn_credential_repository.clone(),
+		users_repository.clone(),
+		app_config.clone(),
+	));
+
+	let magic_link_service = Arc::new(MagicLinkService::new(
+		users_repository.clone(),
+		confirmation_token_service.clone(),
+		email_service.clone(),
+		app_config.clone(),
+	));
+
+	let impersonation_service = Arc::new(ImpersonationService::new(
+		impersonation_log_repository.clone(),
+		users_repository.clone(),
 		user_roles_service.clone(),
+		permissions_service.clone(),
+		auth_service.clone(),
 		app_config.clone(),
 	));
 
 	let files_service = Arc::new(FilesService::new(files_repository.clone(), app_config.clone()));
 
-	AppModule {
+	Ok(AppModule {
 		db_connection,
 		app_config,
 		users_service,
+		password_policy_service,
 		auth_service,
 		refresh_token_service,
 		email_service,
@@ -130,9 +266,21 @@ pub fn initialize_di(db: DatabaseConnection, config: Arc<AppConfig>) -> AppModul
 		confirmation_token_service,
 		files_service,
 		roles_service,
+		permissions_service,
+		two_factor_service,
+		jwt_keys_service,
+		token_revocation_service,
+		login_protection_service,
+		rate_limit_service,
+		personal_access_token_service,
+		service_accounts_service,
+		oidc_service,
+		webauthn_service,
+		magic_link_service,
+		impersonation_service,
 		refresh_token_repository,
 		users_repository,
 		roles_repository,
 		user_roles_repository,
-	}
+	})
 }
@@ -53,6 +53,22 @@ pub trait EmailsServiceTrait: Send + Sync {
 	async fn send_email_confirmation(&self, to: &str, username: &str, token: &str) -> Result<(), AppError>;
 	async fn send_password_reset(&self, to: &str, username: &str, token: &str) -> Result<(), AppError>;
 	async fn send_email_change_confirmation(&self, to: &str, username: &str, token: &str) -> Result<(), AppError>;
+	async fn send_refresh_token_reuse_alert(&self, to: &str, username: &str) -> Result<(), AppError>;
+	async fn send_account_unlock(
+		&self,
+		to: &str,
+		username: &str,
+		token: &str,
+		lockout_minutes: i64,
+	) -> Result<(), AppError>;
+	async fn send_registration_attempt(&self, to: &str, username: &str) -> Result<(), AppError>;
+	async fn send_magic_login(
+		&self,
+		to: &str,
+		username: &str,
+		token: &str,
+		expires_in_minutes: i64,
+	) -> Result<(), AppError>;
 }
 
 #[async_trait]
@@ -96,4 +112,64 @@ impl EmailsServiceTrait for EmailsService {
 		let body = self.render_template("email_change.html", &context)?;
 		self.email_strategy.send_email(to, "Confirm Email Change", &body).await
 	}
+
+	async fn send_refresh_token_reuse_alert(&self, to: &str, username: &str) -> Result<(), AppError> {
+		let mut context = Context::new();
+		context.insert("username", username);
+
+		let body = self.render_template("refresh_token_reuse.html", &context)?;
+		self.email_strategy
+			.send_email(to, "Suspicious Activity On Your Account", &body)
+			.await
+	}
+
+	async fn send_account_unlock(
+		&self,
+		to: &str,
+		username: &str,
+		token: &str,
+		lockout_minutes: i64,
+	) -> Result<(), AppError> {
+		let mut context = Context::new();
+		context.insert("username", username);
+		context.insert("lockout_minutes", &lockout_minutes);
+		context.insert(
+			"unlock_link",
+			&format!("{}/api/auth/unlock-account?token={}", self.app_url, token),
+		);
+
+		let body = self.render_template("account_unlock.html", &context)?;
+		self.email_strategy
+			.send_email(to, "Your Account Has Been Locked", &body)
+			.await
+	}
+
+	async fn send_magic_login(
+		&self,
+		to: &str,
+		username: &str,
+		token: &str,
+		expires_in_minutes: i64,
+	) -> Result<(), AppError> {
+		let mut context = Context::new();
+		context.insert("username", username);
+		context.insert("expires_in_minutes", &expires_in_minutes);
+		context.insert(
+			"login_link",
+			&format!("{}/api/auth/magic-link/verify?token={}", self.app_url, token),
+		);
+
+		let body = self.render_template("magic_login.html", &context)?;
+		self.email_strategy.send_email(to, "Your Sign-In Link", &body).await
+	}
+
+	async fn send_registration_attempt(&self, to: &str, username: &str) -> Result<(), AppError> {
+		let mut context = Context::new();
+		context.insert("username", username);
+
+		let body = self.render_template("registration_attempt.html", &context)?;
+		self.email_strategy
+			.send_email(to, "Registration Attempt With Your Email", &body)
+			.await
+	}
 }
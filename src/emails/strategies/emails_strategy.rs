@@ -105,7 +105,7 @@ pub fn get_email_strategy(
 	match EnvironmentEnum::from_str(environment) {
 		Some(EnvironmentEnum::Development) => Arc::new(SmtpEmailStrategy::new(
 			config.smtp_host.clone(),
-			config.smtp_port.clone(),
+			config.smtp_port,
 			config.smtp_username.clone(),
 			config.smtp_password.clone(),
 			config.email_from.clone(),
@@ -1,5 +1,5 @@
-use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
-use crate::auth::extractor::role_extractor::AdminRole;
+use crate::auth::extractor::jwt_auth_extractor::{JwtAuth, UserAuth};
+use crate::auth::extractor::permission_extractor::{FilesDelete, FilesWrite, RequirePermission};
 use crate::common::error::app_error::AppError;
 use crate::files::dto::update_file_dto::UpdateFileDto;
 use crate::files::entities::files;
@@ -54,7 +54,7 @@ async fn upload_file(
 
 async fn update_file(
 	JwtAuth(_claims): JwtAuth,
-	_admin_role: AdminRole,
+	_permission: RequirePermission<FilesWrite>,
 	Extension(files_service): Extension<Arc<dyn FilesServiceTrait>>,
 	Path(id): Path<i32>,
 	Json(dto): Json<UpdateFileDto>,
@@ -67,7 +67,7 @@ async fn update_file(
 
 async fn delete_file(
 	JwtAuth(_claims): JwtAuth,
-	_admin_role: AdminRole,
+	_permission: RequirePermission<FilesDelete>,
 	Extension(files_service): Extension<Arc<dyn FilesServiceTrait>>,
 	Path(id): Path<i32>,
 ) -> Result<(), AppError> {
@@ -76,8 +76,8 @@ async fn delete_file(
 }
 
 async fn soft_delete_file(
-	JwtAuth(claims): JwtAuth,
-	_admin_role: AdminRole,
+	UserAuth(claims): UserAuth,
+	_permission: RequirePermission<FilesDelete>,
 	Extension(files_service): Extension<Arc<dyn FilesServiceTrait>>,
 	Path(id): Path<i32>,
 ) -> Result<(), AppError> {
@@ -104,10 +104,8 @@ impl StorageStrategy for LocalStorageStrategy {
 }
 
 pub fn get_storage_strategy(storage_type: &str, app_config: &dyn AppConfigTrait) -> Box<dyn StorageStrategy> {
-	match storage_type {
-		_ => Box::new(LocalStorageStrategy::new(
-			app_config.get_config().files.upload_dir.clone(),
-			app_config.get_config().files.base_url.clone(),
-		)),
-	}
+	Box::new(LocalStorageStrategy::new(
+		app_config.get_config().files.upload_dir.clone(),
+		app_config.get_config().files.base_url.clone(),
+	))
 }
@@ -1,7 +1,11 @@
 use crate::auth::repositories::refresh_token_repository::RefreshTokenRepository;
 use crate::auth::services::auth_service::{AuthService, AuthServiceTrait};
 use crate::auth::services::confirmation_token_service::{ConfirmationTokenService, ConfirmationTokenServiceTrait};
+use crate::auth::services::login_protection_service::LoginProtectionServiceTrait;
 use crate::auth::services::refresh_token_service::{RefreshTokenService, RefreshTokenServiceTrait};
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::services::rate_limit_service::RateLimitServiceTrait;
 use crate::config::app_config::AppConfig;
 use crate::di::module;
 use crate::di::{AppConfigImpl, AppConfigTrait, DatabaseConnectionImpl, DatabaseConnectionTrait};
@@ -34,6 +38,7 @@ mod emails;
 mod files;
 mod i18n;
 mod roles;
+mod service_accounts;
 mod users;
 
 #[tokio::main]
@@ -62,11 +67,36 @@ async fn main() {
 	seeders::run_seeders(&db).await.expect("Could not run seeders");
 
 	// Initialize services
-	let di_module = Arc::new(module::initialize_di(db.clone(), app_config_arc.clone()));
+	let di_module = match module::initialize_di(db.clone(), app_config_arc.clone()) {
+		Ok(di_module) => Arc::new(di_module),
+		Err(err) => {
+			let message = match err {
+				AppError::ConfigError(message) => message,
+				err => format!("{:?}", err),
+			};
+			tracing::error!("Could not initialize the application: {}", message);
+			std::process::exit(1);
+		}
+	};
+
+	// Load revoked access tokens into memory
+	di_module
+		.token_revocation_service
+		.load_revoked_tokens()
+		.await
+		.expect("Could not load revoked access tokens");
 
 	// CRON jobs
 	// Run a job to clean expired tokens every 24 hours
 	spawn_token_cleanup_job(di_module.refresh_token_service.clone());
+	// Run a job to clean expired access token revocations every hour
+	spawn_revoked_tokens_cleanup_job(di_module.token_revocation_service.clone());
+	// Run a job to clean expired login lockouts every hour
+	spawn_login_lockouts_cleanup_job(di_module.login_protection_service.clone());
+	// Run a job to drop idle rate limit buckets every 10 minutes
+	spawn_rate_limit_cleanup_job(di_module.rate_limit_service.clone());
+	// Run a job to remove expired role assignments every minute
+	spawn_role_assignments_expiry_job(di_module.user_roles_service.clone());
 
 	let app = app_module::configure(app_config_arc.clone(), di_module).await;
 
@@ -77,7 +107,9 @@ async fn main() {
 		.await
 		.expect("Could not bind to the address");
 
-	axum::serve(listener, app).await.expect("Server failed to start");
+	axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+		.await
+		.expect("Server failed to start");
 }
 
 // CRON jobs
@@ -94,3 +126,55 @@ fn spawn_token_cleanup_job(refresh_token_service: Arc<dyn RefreshTokenServiceTra
 		}
 	});
 }
+
+fn spawn_revoked_tokens_cleanup_job(token_revocation_service: Arc<dyn TokenRevocationServiceTrait>) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600)); // 1 hour
+		loop {
+			interval.tick().await;
+			if let Err(err) = token_revocation_service.clean_expired_tokens().await {
+				tracing::error!("Error cleaning revoked access tokens: {:?}", err);
+			} else {
+				tracing::info!("Successfully cleaned revoked access tokens");
+			}
+		}
+	});
+}
+
+fn spawn_login_lockouts_cleanup_job(login_protection_service: Arc<dyn LoginProtectionServiceTrait>) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600)); // 1 hour
+		loop {
+			interval.tick().await;
+			if let Err(err) = login_protection_service.clean_expired_lockouts().await {
+				tracing::error!("Error cleaning expired login lockouts: {:?}", err);
+			} else {
+				tracing::info!("Successfully cleaned expired login lockouts");
+			}
+		}
+	});
+}
+
+fn spawn_rate_limit_cleanup_job(rate_limit_service: Arc<dyn RateLimitServiceTrait>) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(600)); // 10 minutes
+		loop {
+			interval.tick().await;
+			if let Err(err) = rate_limit_service.clean_expired().await {
+				tracing::error!("Error cleaning rate limit buckets: {:?}", err);
+			}
+		}
+	});
+}
+
+fn spawn_role_assignments_expiry_job(user_roles_service: Arc<dyn UserRolesServiceTrait>) {
+	tokio::spawn(async move {
+		let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60)); // 1 minute
+		loop {
+			interval.tick().await;
+			if let Err(err) = user_roles_service.expire_role_assignments().await {
+				tracing::error!("Error expiring role assignments: {:?}", err);
+			}
+		}
+	});
+}
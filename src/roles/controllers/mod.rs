@@ -0,0 +1,2 @@
+pub mod roles_controller;
+pub mod user_roles_controller;
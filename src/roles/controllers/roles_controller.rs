@@ -0,0 +1,108 @@
+use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
+use crate::auth::extractor::permission_extractor::{RequirePermission, RolesManage};
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
+use crate::common::error::app_error::AppError;
+use crate::roles::dto::create_role_dto::CreateRoleDto;
+use crate::roles::dto::update_role_dto::UpdateRoleDto;
+use crate::roles::dto::update_role_permissions_dto::UpdateRolePermissionsDto;
+use crate::roles::entities::{permissions, roles};
+use crate::roles::services::permissions_service::PermissionsServiceTrait;
+use crate::roles::services::roles_service::RolesServiceTrait;
+use axum::{Extension, Json, Router, extract::Path, routing::get};
+use std::sync::Arc;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(get_all_roles).post(create_role))
+		.route("/permissions", get(get_all_permissions))
+		.route("/{id}", get(get_role_by_id).put(update_role).delete(delete_role))
+		.route(
+			"/{id}/permissions",
+			get(get_role_permissions).put(update_role_permissions),
+		)
+}
+
+async fn get_all_roles(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(roles_service): Extension<Arc<dyn RolesServiceTrait>>,
+) -> Result<Json<Vec<roles::Model>>, AppError> {
+	let roles = roles_service.find_all().await?;
+	Ok(Json(roles))
+}
+
+async fn get_role_by_id(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(roles_service): Extension<Arc<dyn RolesServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<Json<roles::Model>, AppError> {
+	let role = roles_service.find_by_id(id).await?;
+	Ok(Json(role))
+}
+
+async fn create_role(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(roles_service): Extension<Arc<dyn RolesServiceTrait>>,
+	Json(dto): Json<CreateRoleDto>,
+) -> Result<Json<roles::Model>, AppError> {
+	dto.validate()?;
+	let role = roles_service.create(dto.name, dto.description, dto.parent_id).await?;
+	Ok(Json(role))
+}
+
+async fn update_role(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(roles_service): Extension<Arc<dyn RolesServiceTrait>>,
+	Path(id): Path<i32>,
+	Json(dto): Json<UpdateRoleDto>,
+) -> Result<Json<roles::Model>, AppError> {
+	dto.validate()?;
+	let role = roles_service
+		.update(id, dto.name, dto.description, dto.parent_id)
+		.await?;
+	Ok(Json(role))
+}
+
+async fn delete_role(
+	RecentAuth(_claims): RecentAuth<300>,
+	_permission: RequirePermission<RolesManage>,
+	Extension(roles_service): Extension<Arc<dyn RolesServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<(), AppError> {
+	roles_service.delete(id).await
+}
+
+async fn get_all_permissions(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(permissions_service): Extension<Arc<dyn PermissionsServiceTrait>>,
+) -> Result<Json<Vec<permissions::Model>>, AppError> {
+	let permissions = permissions_service.find_all().await?;
+	Ok(Json(permissions))
+}
+
+async fn get_role_permissions(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(permissions_service): Extension<Arc<dyn PermissionsServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<Json<Vec<permissions::Model>>, AppError> {
+	let permissions = permissions_service.find_by_role(id).await?;
+	Ok(Json(permissions))
+}
+
+async fn update_role_permissions(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(permissions_service): Extension<Arc<dyn PermissionsServiceTrait>>,
+	Path(id): Path<i32>,
+	Json(dto): Json<UpdateRolePermissionsDto>,
+) -> Result<Json<Vec<permissions::Model>>, AppError> {
+	dto.validate()?;
+	let permissions = permissions_service.set_role_permissions(id, dto.permissions).await?;
+	Ok(Json(permissions))
+}
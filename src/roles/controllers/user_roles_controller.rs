@@ -0,0 +1,54 @@
+use crate::auth::extractor::jwt_auth_extractor::{JwtAuth, UserAuth};
+use crate::auth::extractor::permission_extractor::{RequirePermission, RolesManage};
+use crate::common::error::app_error::AppError;
+use crate::roles::dto::assign_role_dto::AssignRoleDto;
+use crate::roles::entities::user_roles;
+use crate::roles::services::user_roles_service::{UserRoleAssignmentResponse, UserRolesServiceTrait};
+use axum::{
+	Extension, Json, Router,
+	extract::Path,
+	routing::{delete, get},
+};
+use std::sync::Arc;
+use validator::Validate;
+
+/// Nested under `/api/users/{id}/roles`.
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(get_user_roles).post(assign_role))
+		.route("/{role_id}", delete(remove_role))
+}
+
+async fn get_user_roles(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(user_roles_service): Extension<Arc<dyn UserRolesServiceTrait>>,
+	Path(user_id): Path<i32>,
+) -> Result<Json<Vec<UserRoleAssignmentResponse>>, AppError> {
+	let assignments = user_roles_service.get_user_role_assignments(user_id).await?;
+	Ok(Json(assignments))
+}
+
+// The caller is recorded as `granted_by_user_id`, which has to be a user id
+async fn assign_role(
+	UserAuth(claims): UserAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(user_roles_service): Extension<Arc<dyn UserRolesServiceTrait>>,
+	Path(user_id): Path<i32>,
+	Json(dto): Json<AssignRoleDto>,
+) -> Result<Json<user_roles::Model>, AppError> {
+	dto.validate()?;
+	let user_role = user_roles_service
+		.assign_role(user_id, dto.role_id, claims.sub, dto.valid_from, dto.valid_until)
+		.await?;
+	Ok(Json(user_role))
+}
+
+async fn remove_role(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<RolesManage>,
+	Extension(user_roles_service): Extension<Arc<dyn UserRolesServiceTrait>>,
+	Path((user_id, role_id)): Path<(i32, i32)>,
+) -> Result<(), AppError> {
+	user_roles_service.remove_role(user_id, role_id).await
+}
@@ -1,8 +1,13 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct AssignRoleDto {
-	pub user_id: i32,
+	#[validate(range(min = 1))]
 	pub role_id: i32,
+	/// The role is granted right away when omitted
+	pub valid_from: Option<DateTime<Utc>>,
+	/// The role is granted permanently when omitted
+	pub valid_until: Option<DateTime<Utc>>,
 }
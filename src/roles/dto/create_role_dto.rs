@@ -6,4 +6,6 @@ pub struct CreateRoleDto {
 	#[validate(length(min = 1, max = 50))]
 	pub name: String,
 	pub description: Option<String>,
+	/// Role to inherit from
+	pub parent_id: Option<i32>,
 }
@@ -1,3 +1,4 @@
 pub mod assign_role_dto;
 pub mod create_role_dto;
 pub mod update_role_dto;
+pub mod update_role_permissions_dto;
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -6,4 +6,14 @@ pub struct UpdateRoleDto {
 	#[validate(length(min = 1, max = 50))]
 	pub name: Option<String>,
 	pub description: Option<String>,
+	/// Left unchanged when omitted, `null` removes the parent role
+	#[serde(default, deserialize_with = "deserialize_parent_id")]
+	pub parent_id: Option<Option<i32>>,
+}
+
+fn deserialize_parent_id<'de, D>(deserializer: D) -> Result<Option<Option<i32>>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Option::<i32>::deserialize(deserializer).map(Some)
 }
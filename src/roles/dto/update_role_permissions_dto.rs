@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateRolePermissionsDto {
+	/// Permission names, e.g. `files:delete`, replacing the ones the role has
+	pub permissions: Vec<String>,
+}
@@ -1,2 +1,4 @@
+pub mod permissions;
+pub mod role_permissions;
 pub mod roles;
 pub mod user_roles;
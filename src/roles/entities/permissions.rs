@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "permissions")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	#[sea_orm(unique)]
+	pub name: String,
+	pub description: Option<String>,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(has_many = "super::role_permissions::Entity")]
+	RolePermission,
+}
+
+impl Related<super::role_permissions::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::RolePermission.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -9,6 +9,8 @@ pub struct Model {
 	#[sea_orm(unique)]
 	pub name: String,
 	pub description: Option<String>,
+	/// Role whose privileges this role inherits, e.g. admin inherits from manager
+	pub parent_id: Option<i32>,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: Option<DateTimeWithTimeZone>,
 }
@@ -17,6 +19,8 @@ pub struct Model {
 pub enum Relation {
 	#[sea_orm(has_many = "super::user_roles::Entity")]
 	UserRole,
+	#[sea_orm(belongs_to = "Entity", from = "Column::ParentId", to = "Column::Id")]
+	Parent,
 }
 
 impl Related<super::user_roles::Entity> for Entity {
@@ -26,3 +30,18 @@ impl Related<super::user_roles::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+impl Model {
+	/// A role named after its id, for tests of the hierarchy.
+	pub fn fixture(id: i32, parent_id: Option<i32>) -> Self {
+		Self {
+			id,
+			name: format!("role-{id}"),
+			description: None,
+			parent_id,
+			created_at: chrono::Utc::now().into(),
+			updated_at: None,
+		}
+	}
+}
@@ -8,6 +8,11 @@ pub struct Model {
 	pub id: i32,
 	pub user_id: i32,
 	pub role_id: i32,
+	/// The role is granted from this moment, or right away when not set
+	pub valid_from: Option<DateTimeWithTimeZone>,
+	/// The grant expires at this moment, or never when not set
+	pub valid_until: Option<DateTimeWithTimeZone>,
+	pub granted_by_user_id: Option<i32>,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: Option<DateTimeWithTimeZone>,
 }
@@ -1,4 +1,6 @@
+pub mod controllers;
 pub mod dto;
 pub mod entities;
 pub mod repositories;
+pub mod roles_module;
 pub mod services;
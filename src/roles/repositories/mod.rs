@@ -1,2 +1,3 @@
+pub mod permissions_repository;
 pub mod roles_repository;
 pub mod user_roles_repository;
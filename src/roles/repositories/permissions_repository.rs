@@ -0,0 +1,110 @@
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use crate::roles::entities::permissions::{self, Entity as Permission, Model as PermissionModel};
+use crate::roles::entities::role_permissions::{self, Entity as RolePermission};
+use crate::roles::entities::roles;
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+	ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+	TransactionTrait,
+};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct PermissionsRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl PermissionsRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+}
+
+#[async_trait]
+pub trait PermissionsRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &DatabaseConnection;
+	async fn find_all(&self) -> Result<Vec<PermissionModel>, AppError>;
+	async fn find_by_names(&self, names: &[String]) -> Result<Vec<PermissionModel>, AppError>;
+	async fn find_by_role_id(&self, role_id: i32) -> Result<Vec<PermissionModel>, AppError>;
+	async fn find_names_by_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError>;
+	async fn set_role_permissions(&self, role_id: i32, permission_ids: &[i32]) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl PermissionsRepositoryTrait for PermissionsRepository {
+	fn get_db(&self) -> &DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn find_all(&self) -> Result<Vec<PermissionModel>, AppError> {
+		let permissions = Permission::find()
+			.order_by_asc(permissions::Column::Name)
+			.all(self.get_db())
+			.await?;
+		Ok(permissions)
+	}
+
+	async fn find_by_names(&self, names: &[String]) -> Result<Vec<PermissionModel>, AppError> {
+		let permissions = Permission::find()
+			.filter(permissions::Column::Name.is_in(names.iter().cloned()))
+			.all(self.get_db())
+			.await?;
+		Ok(permissions)
+	}
+
+	async fn find_by_role_id(&self, role_id: i32) -> Result<Vec<PermissionModel>, AppError> {
+		let permissions = Permission::find()
+			.inner_join(RolePermission)
+			.filter(role_permissions::Column::RoleId.eq(role_id))
+			.order_by_asc(permissions::Column::Name)
+			.all(self.get_db())
+			.await?;
+		Ok(permissions)
+	}
+
+	async fn find_names_by_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError> {
+		if role_names.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let names = Permission::find()
+			.select_only()
+			.column(permissions::Column::Name)
+			.distinct()
+			.inner_join(RolePermission)
+			.join(JoinType::InnerJoin, role_permissions::Relation::Role.def())
+			.filter(roles::Column::Name.is_in(role_names.iter().cloned()))
+			.into_tuple()
+			.all(self.get_db())
+			.await?;
+		Ok(names)
+	}
+
+	async fn set_role_permissions(&self, role_id: i32, permission_ids: &[i32]) -> Result<(), AppError> {
+		let transaction = self.get_db().begin().await?;
+
+		RolePermission::delete_many()
+			.filter(role_permissions::Column::RoleId.eq(role_id))
+			.exec(&transaction)
+			.await?;
+
+		if !permission_ids.is_empty() {
+			let now = Utc::now();
+			let role_permissions = permission_ids
+				.iter()
+				.map(|&permission_id| role_permissions::ActiveModel {
+					role_id: Set(role_id),
+					permission_id: Set(permission_id),
+					created_at: Set(now.into()),
+					..Default::default()
+				});
+
+			RolePermission::insert_many(role_permissions).exec(&transaction).await?;
+		}
+
+		transaction.commit().await?;
+		Ok(())
+	}
+}
@@ -2,7 +2,9 @@ use crate::common::error::app_error::AppError;
 use crate::di::DatabaseConnectionTrait;
 use crate::roles::entities::roles::{self, ActiveModel as RoleActiveModel, Entity as Role, Model as RoleModel};
 use async_trait::async_trait;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, QuerySelect, Set,
+};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -20,11 +22,25 @@ impl RolesRepository {
 pub trait RolesRepositoryTrait: Send + Sync {
 	fn get_db(&self) -> &sea_orm::DatabaseConnection;
 	async fn find_all(&self) -> Result<Vec<RoleModel>, AppError>;
+	/// Locks every role until the transaction ends, so hierarchy changes are checked one at a time.
+	async fn find_all_for_update(&self, transaction: &DatabaseTransaction) -> Result<Vec<RoleModel>, AppError>;
 	async fn find_by_id(&self, id: i32) -> Result<RoleModel, AppError>;
 	async fn find_by_name(&self, name: &str) -> Result<RoleModel, AppError>;
-	async fn create(&self, name: String, description: Option<String>) -> Result<RoleModel, AppError>;
-	async fn update(&self, id: i32, name: Option<String>, description: Option<String>) -> Result<RoleModel, AppError>;
-	async fn delete(&self, id: i32) -> Result<(), AppError>;
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		parent_id: Option<i32>,
+	) -> Result<RoleModel, AppError>;
+	async fn update_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		parent_id: Option<Option<i32>>,
+	) -> Result<RoleModel, AppError>;
+	async fn delete_in_transaction(&self, transaction: &DatabaseTransaction, id: i32) -> Result<(), AppError>;
 }
 
 #[async_trait]
@@ -38,6 +54,11 @@ impl RolesRepositoryTrait for RolesRepository {
 		Ok(roles)
 	}
 
+	async fn find_all_for_update(&self, transaction: &DatabaseTransaction) -> Result<Vec<RoleModel>, AppError> {
+		let roles = Role::find().lock_exclusive().all(transaction).await?;
+		Ok(roles)
+	}
+
 	async fn find_by_id(&self, id: i32) -> Result<RoleModel, AppError> {
 		let role = Role::find_by_id(id)
 			.one(self.get_db())
@@ -55,12 +76,18 @@ impl RolesRepositoryTrait for RolesRepository {
 		Ok(role)
 	}
 
-	async fn create(&self, name: String, description: Option<String>) -> Result<RoleModel, AppError> {
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		parent_id: Option<i32>,
+	) -> Result<RoleModel, AppError> {
 		let now = chrono::Utc::now();
 
 		let role_active_model = RoleActiveModel {
 			name: Set(name),
 			description: Set(description),
+			parent_id: Set(parent_id),
 			created_at: Set(now.into()),
 			updated_at: Set(Some(now.into())),
 			..Default::default()
@@ -70,8 +97,15 @@ impl RolesRepositoryTrait for RolesRepository {
 		Ok(role)
 	}
 
-	async fn update(&self, id: i32, name: Option<String>, description: Option<String>) -> Result<RoleModel, AppError> {
-		let role = self.find_by_id(id).await?;
+	async fn update_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		parent_id: Option<Option<i32>>,
+	) -> Result<RoleModel, AppError> {
+		let role = Role::find_by_id(id).one(transaction).await?.ok_or(AppError::NotFound)?;
 		let now = chrono::Utc::now();
 
 		let mut role_active_model: RoleActiveModel = role.into();
@@ -84,16 +118,20 @@ impl RolesRepositoryTrait for RolesRepository {
 			role_active_model.description = Set(Some(description));
 		}
 
+		if let Some(parent_id) = parent_id {
+			role_active_model.parent_id = Set(parent_id);
+		}
+
 		role_active_model.updated_at = Set(Some(now.into()));
-		let updated_role = role_active_model.update(self.get_db()).await?;
+		let updated_role = role_active_model.update(transaction).await?;
 
 		Ok(updated_role)
 	}
 
-	async fn delete(&self, id: i32) -> Result<(), AppError> {
-		let role = self.find_by_id(id).await?;
+	async fn delete_in_transaction(&self, transaction: &DatabaseTransaction, id: i32) -> Result<(), AppError> {
+		let role = Role::find_by_id(id).one(transaction).await?.ok_or(AppError::NotFound)?;
 		let role_active_model: RoleActiveModel = role.into();
-		role_active_model.delete(self.get_db()).await?;
+		role_active_model.delete(transaction).await?;
 		Ok(())
 	}
 }
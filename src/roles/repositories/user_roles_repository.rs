@@ -4,10 +4,12 @@ use crate::di::DatabaseConnectionTrait;
 use crate::roles::entities::roles;
 use crate::roles::entities::roles::{self as role_entity, Entity as Role};
 use crate::roles::entities::user_roles::{self, Entity as UserRole, Model as UserRoleModel};
+use crate::users::entities::users::{self, Entity as User};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, Set,
+	ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter,
+	QuerySelect, Set,
 };
 use std::sync::Arc;
 
@@ -20,19 +22,66 @@ impl UserRolesRepository {
 	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
 		Self { db_connection }
 	}
+
+	/// Grants that have started and not yet expired.
+	fn active_condition() -> Condition {
+		let now = Utc::now();
+
+		Condition::all()
+			.add(
+				Condition::any()
+					.add(user_roles::Column::ValidFrom.is_null())
+					.add(user_roles::Column::ValidFrom.lte(now)),
+			)
+			.add(
+				Condition::any()
+					.add(user_roles::Column::ValidUntil.is_null())
+					.add(user_roles::Column::ValidUntil.gt(now)),
+			)
+	}
 }
 
 #[async_trait]
 pub trait UserRolesRepositoryTrait: Send + Sync {
 	fn get_db(&self) -> &DatabaseConnection;
+	/// Roles of the active grants only.
 	async fn find_user_roles(&self, user_id: i32) -> Result<Vec<role_entity::Model>, AppError>;
+	/// Every grant of the user with its role, including ones not yet started.
+	async fn find_user_role_assignments(
+		&self,
+		user_id: i32,
+	) -> Result<Vec<(UserRoleModel, Option<role_entity::Model>)>, AppError>;
+	async fn find_user_role(&self, user_id: i32, role_id: i32) -> Result<Option<UserRoleModel>, AppError>;
 	async fn assign_user_role_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
 		user_id: i32,
 	) -> Result<UserRoleModel, AppError>;
-	async fn remove_role(&self, user_id: i32, role_id: i32) -> Result<(), AppError>;
-	async fn has_role(&self, user_id: i32, role: RoleEnum) -> Result<bool, AppError>;
+	async fn assign_role(
+		&self,
+		user_id: i32,
+		role_id: i32,
+		granted_by_user_id: Option<i32>,
+		valid_from: Option<DateTime<Utc>>,
+		valid_until: Option<DateTime<Utc>>,
+	) -> Result<UserRoleModel, AppError>;
+	async fn find_user_ids_by_role(&self, role_id: i32) -> Result<Vec<i32>, AppError>;
+	/// Active grants of any of the roles, skipping deactivated users. The grants and the users stay locked
+	/// until the transaction ends.
+	async fn find_active_by_roles_for_update(
+		&self,
+		transaction: &DatabaseTransaction,
+		role_ids: Vec<i32>,
+	) -> Result<Vec<UserRoleModel>, AppError>;
+	async fn remove_role_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		role_id: i32,
+	) -> Result<(), AppError>;
+	/// Deletes the grants past their `valid_until` and returns the ids of the users who held them.
+	async fn delete_expired(&self) -> Result<Vec<i32>, AppError>;
+	async fn has_role(&self, user_id: i32, role_name: &str) -> Result<bool, AppError>;
 }
 
 #[async_trait]
@@ -43,6 +92,7 @@ impl UserRolesRepositoryTrait for UserRolesRepository {
 	async fn find_user_roles(&self, user_id: i32) -> Result<Vec<role_entity::Model>, AppError> {
 		let user_roles = UserRole::find()
 			.filter(user_roles::Column::UserId.eq(user_id))
+			.filter(Self::active_condition())
 			.find_with_related(Role)
 			.all(self.get_db())
 			.await?;
@@ -52,6 +102,29 @@ impl UserRolesRepositoryTrait for UserRolesRepository {
 		Ok(roles)
 	}
 
+	async fn find_user_role_assignments(
+		&self,
+		user_id: i32,
+	) -> Result<Vec<(UserRoleModel, Option<role_entity::Model>)>, AppError> {
+		let assignments = UserRole::find()
+			.filter(user_roles::Column::UserId.eq(user_id))
+			.find_also_related(Role)
+			.all(self.get_db())
+			.await?;
+
+		Ok(assignments)
+	}
+
+	async fn find_user_role(&self, user_id: i32, role_id: i32) -> Result<Option<UserRoleModel>, AppError> {
+		let user_role = UserRole::find()
+			.filter(user_roles::Column::UserId.eq(user_id))
+			.filter(user_roles::Column::RoleId.eq(role_id))
+			.one(self.get_db())
+			.await?;
+
+		Ok(user_role)
+	}
+
 	async fn assign_user_role_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
@@ -70,6 +143,9 @@ impl UserRolesRepositoryTrait for UserRolesRepository {
 			id: Default::default(),
 			user_id: Set(user_id),
 			role_id: Set(user_role.id),
+			valid_from: Set(None),
+			valid_until: Set(None),
+			granted_by_user_id: Set(None),
 			created_at: Set(Utc::now().into()),
 			updated_at: Set(Some(Utc::now().into())),
 		};
@@ -80,14 +156,74 @@ impl UserRolesRepositoryTrait for UserRolesRepository {
 			.map_err(|_| AppError::InternalError)
 	}
 
-	async fn remove_role(&self, user_id: i32, role_id: i32) -> Result<(), AppError> {
+	async fn assign_role(
+		&self,
+		user_id: i32,
+		role_id: i32,
+		granted_by_user_id: Option<i32>,
+		valid_from: Option<DateTime<Utc>>,
+		valid_until: Option<DateTime<Utc>>,
+	) -> Result<UserRoleModel, AppError> {
+		let now = Utc::now();
+
+		let user_role_model = user_roles::ActiveModel {
+			id: Default::default(),
+			user_id: Set(user_id),
+			role_id: Set(role_id),
+			valid_from: Set(valid_from.map(Into::into)),
+			valid_until: Set(valid_until.map(Into::into)),
+			granted_by_user_id: Set(granted_by_user_id),
+			created_at: Set(now.into()),
+			updated_at: Set(Some(now.into())),
+		};
+
+		let user_role = user_role_model.insert(self.get_db()).await?;
+		Ok(user_role)
+	}
+
+	async fn find_user_ids_by_role(&self, role_id: i32) -> Result<Vec<i32>, AppError> {
+		let user_ids = UserRole::find()
+			.filter(user_roles::Column::RoleId.eq(role_id))
+			.filter(Self::active_condition())
+			.all(self.get_db())
+			.await?
+			.into_iter()
+			.map(|user_role| user_role.user_id)
+			.collect();
+
+		Ok(user_ids)
+	}
+
+	async fn find_active_by_roles_for_update(
+		&self,
+		transaction: &DatabaseTransaction,
+		role_ids: Vec<i32>,
+	) -> Result<Vec<UserRoleModel>, AppError> {
+		let user_roles = UserRole::find()
+			.inner_join(User)
+			.filter(user_roles::Column::RoleId.is_in(role_ids))
+			.filter(Self::active_condition())
+			.filter(users::Column::IsActive.eq(true))
+			.lock_exclusive()
+			.all(transaction)
+			.await?;
+
+		Ok(user_roles)
+	}
+
+	async fn remove_role_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		role_id: i32,
+	) -> Result<(), AppError> {
 		let result = UserRole::delete_many()
 			.filter(
 				Condition::all()
 					.add(user_roles::Column::UserId.eq(user_id))
 					.add(user_roles::Column::RoleId.eq(role_id)),
 			)
-			.exec(self.get_db())
+			.exec(transaction)
 			.await?;
 
 		if result.rows_affected == 0 {
@@ -97,9 +233,35 @@ impl UserRolesRepositoryTrait for UserRolesRepository {
 		Ok(())
 	}
 
-	async fn has_role(&self, user_id: i32, role: RoleEnum) -> Result<bool, AppError> {
+	async fn delete_expired(&self) -> Result<Vec<i32>, AppError> {
+		let expired_user_roles = UserRole::find()
+			.filter(user_roles::Column::ValidUntil.lte(Utc::now()))
+			.all(self.get_db())
+			.await?;
+
+		if expired_user_roles.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let ids: Vec<i32> = expired_user_roles.iter().map(|user_role| user_role.id).collect();
+		UserRole::delete_many()
+			.filter(user_roles::Column::Id.is_in(ids))
+			.exec(self.get_db())
+			.await?;
+
+		let mut user_ids: Vec<i32> = expired_user_roles
+			.into_iter()
+			.map(|user_role| user_role.user_id)
+			.collect();
+		user_ids.sort_unstable();
+		user_ids.dedup();
+
+		Ok(user_ids)
+	}
+
+	async fn has_role(&self, user_id: i32, role_name: &str) -> Result<bool, AppError> {
 		let roles = self.find_user_roles(user_id).await?;
-		let has_role = roles.iter().any(|r| r.name == role.as_str());
+		let has_role = roles.iter().any(|r| r.name == role_name);
 		Ok(has_role)
 	}
 }
@@ -0,0 +1,8 @@
+use crate::roles::controllers::{roles_controller, user_roles_controller};
+use axum::Router;
+
+pub fn configure() -> Router {
+	Router::new()
+		.nest("/api/roles", roles_controller::routes())
+		.nest("/api/users/{:id}/roles", user_roles_controller::routes())
+}
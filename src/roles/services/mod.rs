@@ -1,2 +1,3 @@
+pub mod permissions_service;
 pub mod roles_service;
 pub mod user_roles_service;
@@ -0,0 +1,90 @@
+use crate::common::enums::permission_enum::PermissionEnum;
+use crate::common::enums::role_enum::RoleEnum;
+use crate::common::error::app_error::AppError;
+use crate::i18n::setup::translate;
+use crate::roles::entities::permissions::Model as PermissionModel;
+use crate::roles::repositories::permissions_repository::PermissionsRepositoryTrait;
+use crate::roles::repositories::roles_repository::RolesRepositoryTrait;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct PermissionsService {
+	permissions_repository: Arc<dyn PermissionsRepositoryTrait>,
+	roles_repository: Arc<dyn RolesRepositoryTrait>,
+}
+
+impl PermissionsService {
+	pub fn new(
+		permissions_repository: Arc<dyn PermissionsRepositoryTrait>,
+		roles_repository: Arc<dyn RolesRepositoryTrait>,
+	) -> Self {
+		Self {
+			permissions_repository,
+			roles_repository,
+		}
+	}
+}
+
+#[async_trait]
+pub trait PermissionsServiceTrait: Send + Sync {
+	async fn find_all(&self) -> Result<Vec<PermissionModel>, AppError>;
+	async fn find_by_role(&self, role_id: i32) -> Result<Vec<PermissionModel>, AppError>;
+	async fn set_role_permissions(&self, role_id: i32, names: Vec<String>) -> Result<Vec<PermissionModel>, AppError>;
+	/// Whether any of the roles grants the permission. Resolved on every call, so changes to a
+	/// role's permissions apply to tokens that were already issued.
+	async fn has_permission(&self, role_names: &[String], permission: PermissionEnum) -> Result<bool, AppError>;
+	/// Names of every permission granted by any of the roles.
+	async fn find_names_by_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError>;
+}
+
+#[async_trait]
+impl PermissionsServiceTrait for PermissionsService {
+	async fn find_all(&self) -> Result<Vec<PermissionModel>, AppError> {
+		self.permissions_repository.find_all().await
+	}
+
+	async fn find_by_role(&self, role_id: i32) -> Result<Vec<PermissionModel>, AppError> {
+		let _existing_role = self.roles_repository.find_by_id(role_id).await?;
+		self.permissions_repository.find_by_role_id(role_id).await
+	}
+
+	async fn set_role_permissions(&self, role_id: i32, names: Vec<String>) -> Result<Vec<PermissionModel>, AppError> {
+		let role = self.roles_repository.find_by_id(role_id).await?;
+
+		// The admin role always holds every permission, so it cannot lock everyone out
+		if role.name == RoleEnum::Admin.as_str() {
+			return Err(AppError::BadRequest(translate("roles.errors.admin_permissions")));
+		}
+
+		let permissions = self.permissions_repository.find_by_names(&names).await?;
+		if names
+			.iter()
+			.any(|name| !permissions.iter().any(|permission| permission.name == *name))
+		{
+			let mut errors = validator::ValidationErrors::new();
+			errors.add(
+				"permissions",
+				validator::ValidationError::new("unknown")
+					.with_message(translate("roles.errors.unknown_permission").into()),
+			);
+			return Err(AppError::ValidationError(errors));
+		}
+
+		let permission_ids: Vec<i32> = permissions.iter().map(|permission| permission.id).collect();
+		self.permissions_repository
+			.set_role_permissions(role_id, &permission_ids)
+			.await?;
+
+		self.permissions_repository.find_by_role_id(role_id).await
+	}
+
+	async fn has_permission(&self, role_names: &[String], permission: PermissionEnum) -> Result<bool, AppError> {
+		let permissions = self.permissions_repository.find_names_by_role_names(role_names).await?;
+		Ok(permissions.iter().any(|name| name == permission.as_str()))
+	}
+
+	async fn find_names_by_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError> {
+		self.permissions_repository.find_names_by_role_names(role_names).await
+	}
+}
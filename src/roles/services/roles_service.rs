@@ -1,17 +1,93 @@
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
+use crate::common::enums::role_enum::RoleEnum;
 use crate::common::error::app_error::AppError;
+use crate::i18n::setup::translate;
 use crate::roles::entities::roles::Model as RoleModel;
 use crate::roles::repositories::roles_repository::RolesRepositoryTrait;
+use crate::roles::repositories::user_roles_repository::UserRolesRepositoryTrait;
+use crate::roles::services::user_roles_service::{RoleChange, UserRolesServiceTrait};
 use async_trait::async_trait;
+use sea_orm::TransactionTrait;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct RolesService {
 	roles_repository: Arc<dyn RolesRepositoryTrait>,
+	user_roles_repository: Arc<dyn UserRolesRepositoryTrait>,
+	user_roles_service: Arc<dyn UserRolesServiceTrait>,
+	token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
 }
 
 impl RolesService {
-	pub fn new(roles_repository: Arc<dyn RolesRepositoryTrait>) -> Self {
-		Self { roles_repository }
+	pub fn new(
+		roles_repository: Arc<dyn RolesRepositoryTrait>,
+		user_roles_repository: Arc<dyn UserRolesRepositoryTrait>,
+		user_roles_service: Arc<dyn UserRolesServiceTrait>,
+		token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
+	) -> Self {
+		Self {
+			roles_repository,
+			user_roles_repository,
+			user_roles_service,
+			token_revocation_service,
+		}
+	}
+
+	fn name_taken_error() -> AppError {
+		let mut errors = validator::ValidationErrors::new();
+		errors.add(
+			"name",
+			validator::ValidationError::new("already_exists")
+				.with_message(translate("roles.errors.role_already_exists").into()),
+		);
+		AppError::ValidationError(errors)
+	}
+
+	fn parent_error(code: &'static str, key: &str) -> AppError {
+		let mut errors = validator::ValidationErrors::new();
+		errors.add(
+			"parent_id",
+			validator::ValidationError::new(code).with_message(translate(key).into()),
+		);
+		AppError::ValidationError(errors)
+	}
+
+	/// Checks that `parent_id` exists and that inheriting from it would not make `role_id` (`None` for a
+	/// new role) its own ancestor.
+	async fn ensure_valid_parent(&self, role_id: Option<i32>, parent_id: i32) -> Result<(), AppError> {
+		let all_roles = self.roles_repository.find_all().await?;
+		Self::validate_parent(&all_roles, role_id, parent_id)
+	}
+
+	fn validate_parent(all_roles: &[RoleModel], role_id: Option<i32>, parent_id: i32) -> Result<(), AppError> {
+		let parents_by_id: HashMap<i32, Option<i32>> = all_roles.iter().map(|role| (role.id, role.parent_id)).collect();
+
+		if !parents_by_id.contains_key(&parent_id) {
+			return Err(Self::parent_error("not_found", "roles.errors.parent_not_found"));
+		}
+
+		let mut visited = HashSet::new();
+		let mut current_id = Some(parent_id);
+
+		while let Some(id) = current_id {
+			if Some(id) == role_id {
+				return Err(Self::parent_error("cycle", "roles.errors.hierarchy_cycle"));
+			}
+
+			if !visited.insert(id) {
+				break;
+			}
+
+			current_id = parents_by_id.get(&id).copied().flatten();
+		}
+
+		Ok(())
+	}
+
+	/// Built-in roles are referenced by name in the code, so they cannot be renamed or deleted.
+	fn is_built_in(role: &RoleModel) -> bool {
+		RoleEnum::is_built_in(&role.name)
 	}
 }
 
@@ -20,8 +96,19 @@ pub trait RolesServiceTrait: Send + Sync {
 	async fn find_all(&self) -> Result<Vec<RoleModel>, AppError>;
 	async fn find_by_id(&self, id: i32) -> Result<RoleModel, AppError>;
 	async fn find_by_name(&self, name: &str) -> Result<RoleModel, AppError>;
-	async fn create(&self, name: String, description: Option<String>) -> Result<RoleModel, AppError>;
-	async fn update(&self, id: i32, name: Option<String>, description: Option<String>) -> Result<RoleModel, AppError>;
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		parent_id: Option<i32>,
+	) -> Result<RoleModel, AppError>;
+	async fn update(
+		&self,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		parent_id: Option<Option<i32>>,
+	) -> Result<RoleModel, AppError>;
 	async fn delete(&self, id: i32) -> Result<(), AppError>;
 }
 
@@ -39,37 +126,153 @@ impl RolesServiceTrait for RolesService {
 		self.roles_repository.find_by_name(name).await
 	}
 
-	async fn create(&self, name: String, description: Option<String>) -> Result<RoleModel, AppError> {
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		parent_id: Option<i32>,
+	) -> Result<RoleModel, AppError> {
 		let existing_role = self.roles_repository.find_by_name(&name).await;
 		if let Ok(_role) = existing_role {
-			return Err(AppError::ValidationError({
-				let mut errors = validator::ValidationErrors::new();
-				errors.add("name", validator::ValidationError::new("already_exists"));
-				errors
-			}));
+			return Err(Self::name_taken_error());
 		}
 
-		self.roles_repository.create(name, description).await
+		if let Some(parent_id) = parent_id {
+			self.ensure_valid_parent(None, parent_id).await?;
+		}
+
+		self.roles_repository.create(name, description, parent_id).await
 	}
 
-	async fn update(&self, id: i32, name: Option<String>, description: Option<String>) -> Result<RoleModel, AppError> {
+	async fn update(
+		&self,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		parent_id: Option<Option<i32>>,
+	) -> Result<RoleModel, AppError> {
+		let role = self.roles_repository.find_by_id(id).await?;
+
+		if let Some(Some(parent_id)) = parent_id {
+			self.ensure_valid_parent(Some(id), parent_id).await?;
+		}
+
 		if let Some(ref new_name) = name {
+			if Self::is_built_in(&role) && *new_name != role.name {
+				return Err(AppError::BadRequest(translate("roles.errors.built_in_role")));
+			}
+
 			let existing_role = self.roles_repository.find_by_name(new_name).await;
-			if let Ok(role) = existing_role {
-				if role.id != id {
-					return Err(AppError::ValidationError({
-						let mut errors = validator::ValidationErrors::new();
-						errors.add("name", validator::ValidationError::new("already_exists"));
-						errors
-					}));
-				}
+			if let Ok(role) = existing_role
+				&& role.id != id
+			{
+				return Err(Self::name_taken_error());
 			}
 		}
 
-		self.roles_repository.update(id, name, description).await
+		let transaction = self.roles_repository.get_db().begin().await?;
+
+		// Moving a role in the hierarchy can take the admin role away from its holders
+		if let Some(parent_id) = parent_id {
+			self.user_roles_service
+				.ensure_admin_remains_in_transaction(&transaction, RoleChange::SetParent { role_id: id, parent_id })
+				.await?;
+		}
+
+		let role = self
+			.roles_repository
+			.update_in_transaction(&transaction, id, name, description, parent_id)
+			.await?;
+		transaction.commit().await?;
+
+		Ok(role)
 	}
 
 	async fn delete(&self, id: i32) -> Result<(), AppError> {
-		self.roles_repository.delete(id).await
+		let role = self.roles_repository.find_by_id(id).await?;
+
+		if Self::is_built_in(&role) {
+			return Err(AppError::BadRequest(translate("roles.errors.built_in_role")));
+		}
+
+		let user_ids = self.user_roles_repository.find_user_ids_by_role(id).await?;
+
+		let transaction = self.roles_repository.get_db().begin().await?;
+		self.user_roles_service
+			.ensure_admin_remains_in_transaction(&transaction, RoleChange::DeleteRole { role_id: id })
+			.await?;
+		self.roles_repository.delete_in_transaction(&transaction, id).await?;
+		transaction.commit().await?;
+
+		// Assignments are removed with the role, tokens issued before still list it
+		for user_id in user_ids {
+			self.token_revocation_service.revoke_user_access_tokens(user_id).await?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn error_code(result: Result<(), AppError>) -> Option<String> {
+		match result {
+			Err(AppError::ValidationError(errors)) => Some(errors.field_errors()["parent_id"][0].code.to_string()),
+			_ => None,
+		}
+	}
+
+	// admin (1) > manager (2) > user (3)
+	fn hierarchy() -> Vec<RoleModel> {
+		vec![
+			RoleModel::fixture(1, Some(2)),
+			RoleModel::fixture(2, Some(3)),
+			RoleModel::fixture(3, None),
+			RoleModel::fixture(4, None),
+		]
+	}
+
+	#[test]
+	fn validate_parent_accepts_parents_outside_the_role_subtree() {
+		assert!(RolesService::validate_parent(&hierarchy(), None, 1).is_ok());
+		assert!(RolesService::validate_parent(&hierarchy(), Some(4), 1).is_ok());
+		assert!(RolesService::validate_parent(&hierarchy(), Some(1), 3).is_ok());
+	}
+
+	#[test]
+	fn validate_parent_rejects_unknown_parents() {
+		assert_eq!(
+			error_code(RolesService::validate_parent(&hierarchy(), Some(1), 99)).as_deref(),
+			Some("not_found")
+		);
+	}
+
+	#[test]
+	fn validate_parent_rejects_cycles() {
+		assert_eq!(
+			error_code(RolesService::validate_parent(&hierarchy(), Some(3), 1)).as_deref(),
+			Some("cycle")
+		);
+		assert_eq!(
+			error_code(RolesService::validate_parent(&hierarchy(), Some(2), 1)).as_deref(),
+			Some("cycle")
+		);
+		assert_eq!(
+			error_code(RolesService::validate_parent(&hierarchy(), Some(4), 4)).as_deref(),
+			Some("cycle")
+		);
+	}
+
+	#[test]
+	fn validate_parent_stops_on_an_existing_cycle() {
+		let roles = vec![
+			RoleModel::fixture(1, Some(2)),
+			RoleModel::fixture(2, Some(1)),
+			RoleModel::fixture(3, None),
+		];
+
+		assert!(RolesService::validate_parent(&roles, Some(3), 1).is_ok());
 	}
 }
@@ -1,33 +1,181 @@
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
 use crate::common::enums::role_enum::RoleEnum;
 use crate::common::error::app_error::AppError;
+use crate::i18n::setup::translate;
 use crate::roles::entities::roles::Model as RoleModel;
 use crate::roles::entities::user_roles::Model as UserRoleModel;
+use crate::roles::repositories::roles_repository::RolesRepositoryTrait;
 use crate::roles::repositories::user_roles_repository::UserRolesRepositoryTrait;
+use crate::users::repositories::users_repository::UsersRepositoryTrait;
 use async_trait::async_trait;
-use sea_orm::DatabaseTransaction;
+use chrono::{DateTime, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{DatabaseTransaction, TransactionTrait};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+#[derive(Debug, Serialize)]
+pub struct UserRoleAssignmentResponse {
+	pub role: RoleModel,
+	pub valid_from: Option<DateTimeWithTimeZone>,
+	pub valid_until: Option<DateTimeWithTimeZone>,
+	pub granted_by_user_id: Option<i32>,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+/// A change that can take the admin role away from users, see `ensure_admin_remains_in_transaction`.
+#[derive(Debug, Clone, Copy)]
+pub enum RoleChange {
+	DeleteUser { user_id: i32 },
+	RemoveGrant { user_id: i32, role_id: i32 },
+	DeleteRole { role_id: i32 },
+	SetParent { role_id: i32, parent_id: Option<i32> },
+}
+
+impl RoleChange {
+	/// Applies the change to a copy of the roles and the `(user_id, role_id)` grants.
+	fn apply(self, roles: &mut Vec<RoleModel>, grants: &mut Vec<(i32, i32)>) {
+		match self {
+			Self::DeleteUser { user_id } => grants.retain(|&(grant_user_id, _)| grant_user_id != user_id),
+			Self::RemoveGrant { user_id, role_id } => grants.retain(|&grant| grant != (user_id, role_id)),
+			Self::DeleteRole { role_id } => {
+				roles.retain(|role| role.id != role_id);
+				// The children of a deleted role lose their parent
+				for role in roles.iter_mut().filter(|role| role.parent_id == Some(role_id)) {
+					role.parent_id = None;
+				}
+				grants.retain(|&(_, grant_role_id)| grant_role_id != role_id);
+			}
+			Self::SetParent { role_id, parent_id } => {
+				for role in roles.iter_mut().filter(|role| role.id == role_id) {
+					role.parent_id = parent_id;
+				}
+			}
+		}
+	}
+}
+
 #[derive(Clone)]
 pub struct UserRolesService {
 	user_roles_repository: Arc<dyn UserRolesRepositoryTrait>,
+	roles_repository: Arc<dyn RolesRepositoryTrait>,
+	users_repository: Arc<dyn UsersRepositoryTrait>,
+	token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
 }
 
 impl UserRolesService {
-	pub fn new(user_roles_repository: Arc<dyn UserRolesRepositoryTrait>) -> Self {
-		Self { user_roles_repository }
+	pub fn new(
+		user_roles_repository: Arc<dyn UserRolesRepositoryTrait>,
+		roles_repository: Arc<dyn RolesRepositoryTrait>,
+		users_repository: Arc<dyn UsersRepositoryTrait>,
+		token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
+	) -> Self {
+		Self {
+			user_roles_repository,
+			roles_repository,
+			users_repository,
+			token_revocation_service,
+		}
+	}
+
+	/// Follows `parent_id` from each of the given roles and returns them with every inherited role.
+	/// Roles are visited once, so a cycle in the table cannot loop forever.
+	fn expand_hierarchy(all_roles: &[RoleModel], role_ids: impl IntoIterator<Item = i32>) -> Vec<RoleModel> {
+		let roles_by_id: HashMap<i32, &RoleModel> = all_roles.iter().map(|role| (role.id, role)).collect();
+		let mut visited = HashSet::new();
+		let mut effective_roles = Vec::new();
+
+		for role_id in role_ids {
+			let mut current_id = Some(role_id);
+
+			while let Some(id) = current_id {
+				if !visited.insert(id) {
+					break;
+				}
+
+				let Some(role) = roles_by_id.get(&id) else {
+					break;
+				};
+
+				effective_roles.push((*role).clone());
+				current_id = role.parent_id;
+			}
+		}
+
+		effective_roles
+	}
+
+	/// Roles granting admin, either the admin role itself or a role inheriting it.
+	fn admin_role_ids(all_roles: &[RoleModel]) -> HashSet<i32> {
+		all_roles
+			.iter()
+			.filter(|role| {
+				Self::expand_hierarchy(all_roles, [role.id])
+					.iter()
+					.any(|effective_role| effective_role.name == RoleEnum::Admin.as_str())
+			})
+			.map(|role| role.id)
+			.collect()
+	}
+
+	fn admin_user_ids(all_roles: &[RoleModel], grants: &[(i32, i32)]) -> HashSet<i32> {
+		let admin_role_ids = Self::admin_role_ids(all_roles);
+
+		grants
+			.iter()
+			.filter(|(_, role_id)| admin_role_ids.contains(role_id))
+			.map(|&(user_id, _)| user_id)
+			.collect()
+	}
+
+	/// Whether the change leaves no user with the admin role, directly or inherited, when there was one
+	/// before. `grants` has to hold every grant of a role granting admin before or after the change.
+	fn removes_last_admin(all_roles: &[RoleModel], grants: &[(i32, i32)], change: RoleChange) -> bool {
+		let mut roles_after = all_roles.to_vec();
+		let mut grants_after = grants.to_vec();
+		change.apply(&mut roles_after, &mut grants_after);
+
+		!Self::admin_user_ids(all_roles, grants).is_empty()
+			&& Self::admin_user_ids(&roles_after, &grants_after).is_empty()
 	}
 }
 
 #[async_trait]
 pub trait UserRolesServiceTrait: Send + Sync {
 	async fn get_user_roles(&self, user_id: i32) -> Result<Vec<RoleModel>, AppError>;
+	/// Every grant of the user, including ones not yet started.
+	async fn get_user_role_assignments(&self, user_id: i32) -> Result<Vec<UserRoleAssignmentResponse>, AppError>;
+	/// The user's roles together with every role they inherit.
+	async fn get_effective_roles(&self, user_id: i32) -> Result<Vec<RoleModel>, AppError>;
+	/// Expands role names, e.g. from token claims, with every role they inherit. Names of roles that
+	/// no longer exist are dropped.
+	async fn resolve_effective_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError>;
 	async fn assign_user_role_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
 		user_id: i32,
 	) -> Result<UserRoleModel, AppError>;
+	async fn assign_role(
+		&self,
+		user_id: i32,
+		role_id: i32,
+		granted_by_user_id: i32,
+		valid_from: Option<DateTime<Utc>>,
+		valid_until: Option<DateTime<Utc>>,
+	) -> Result<UserRoleModel, AppError>;
 	async fn remove_role(&self, user_id: i32, role_id: i32) -> Result<(), AppError>;
-	async fn has_role(&self, user_id: i32, role_name: RoleEnum) -> Result<bool, AppError>;
+	/// Removes grants past their `valid_until` and revokes the tokens that still list them.
+	async fn expire_role_assignments(&self) -> Result<(), AppError>;
+	async fn has_role(&self, user_id: i32, role_name: &str) -> Result<bool, AppError>;
+	/// Fails when the change would leave no active user with the admin role, directly or through the
+	/// hierarchy. The roles and the admin grants stay locked until the transaction ends, so concurrent
+	/// changes cannot both pass the check.
+	async fn ensure_admin_remains_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		change: RoleChange,
+	) -> Result<(), AppError>;
 }
 
 #[async_trait]
@@ -36,6 +184,47 @@ impl UserRolesServiceTrait for UserRolesService {
 		self.user_roles_repository.find_user_roles(user_id).await
 	}
 
+	async fn get_user_role_assignments(&self, user_id: i32) -> Result<Vec<UserRoleAssignmentResponse>, AppError> {
+		let assignments = self.user_roles_repository.find_user_role_assignments(user_id).await?;
+
+		Ok(assignments
+			.into_iter()
+			.filter_map(|(user_role, role)| {
+				role.map(|role| UserRoleAssignmentResponse {
+					role,
+					valid_from: user_role.valid_from,
+					valid_until: user_role.valid_until,
+					granted_by_user_id: user_role.granted_by_user_id,
+					created_at: user_role.created_at,
+				})
+			})
+			.collect())
+	}
+
+	async fn get_effective_roles(&self, user_id: i32) -> Result<Vec<RoleModel>, AppError> {
+		let user_roles = self.user_roles_repository.find_user_roles(user_id).await?;
+		let all_roles = self.roles_repository.find_all().await?;
+
+		Ok(Self::expand_hierarchy(
+			&all_roles,
+			user_roles.iter().map(|role| role.id),
+		))
+	}
+
+	async fn resolve_effective_role_names(&self, role_names: &[String]) -> Result<Vec<String>, AppError> {
+		let all_roles = self.roles_repository.find_all().await?;
+		let role_ids: Vec<i32> = all_roles
+			.iter()
+			.filter(|role| role_names.contains(&role.name))
+			.map(|role| role.id)
+			.collect();
+
+		Ok(Self::expand_hierarchy(&all_roles, role_ids)
+			.into_iter()
+			.map(|role| role.name)
+			.collect())
+	}
+
 	async fn assign_user_role_in_transaction(
 		&self,
 		transaction: &DatabaseTransaction,
@@ -46,11 +235,249 @@ impl UserRolesServiceTrait for UserRolesService {
 			.await
 	}
 
+	async fn assign_role(
+		&self,
+		user_id: i32,
+		role_id: i32,
+		granted_by_user_id: i32,
+		valid_from: Option<DateTime<Utc>>,
+		valid_until: Option<DateTime<Utc>>,
+	) -> Result<UserRoleModel, AppError> {
+		let _existing_user = self.users_repository.find_by_id(user_id).await?;
+		let _existing_role = self.roles_repository.find_by_id(role_id).await?;
+
+		if let Some(valid_until) = valid_until
+			&& (valid_until <= Utc::now() || valid_from.is_some_and(|valid_from| valid_until <= valid_from))
+		{
+			let mut errors = validator::ValidationErrors::new();
+			errors.add(
+				"valid_until",
+				validator::ValidationError::new("invalid")
+					.with_message(translate("roles.errors.invalid_valid_until").into()),
+			);
+			return Err(AppError::ValidationError(errors));
+		}
+
+		// Grants not yet started count too, a user holds each role at most once
+		let existing_user_role = self.user_roles_repository.find_user_role(user_id, role_id).await?;
+		if existing_user_role.is_some() {
+			let mut errors = validator::ValidationErrors::new();
+			errors.add(
+				"role_id",
+				validator::ValidationError::new("already_assigned")
+					.with_message(translate("roles.errors.role_already_assigned").into()),
+			);
+			return Err(AppError::ValidationError(errors));
+		}
+
+		self.user_roles_repository
+			.assign_role(user_id, role_id, Some(granted_by_user_id), valid_from, valid_until)
+			.await
+	}
+
 	async fn remove_role(&self, user_id: i32, role_id: i32) -> Result<(), AppError> {
-		self.user_roles_repository.remove_role(user_id, role_id).await
+		let _existing_role = self.roles_repository.find_by_id(role_id).await?;
+
+		let transaction = self.user_roles_repository.get_db().begin().await?;
+
+		self.ensure_admin_remains_in_transaction(&transaction, RoleChange::RemoveGrant { user_id, role_id })
+			.await?;
+
+		self.user_roles_repository
+			.remove_role_in_transaction(&transaction, user_id, role_id)
+			.await?;
+		transaction.commit().await?;
+
+		// Access tokens still list the removed role, refreshing picks up the current ones
+		self.token_revocation_service.revoke_user_access_tokens(user_id).await
+	}
+
+	async fn expire_role_assignments(&self) -> Result<(), AppError> {
+		let user_ids = self.user_roles_repository.delete_expired().await?;
+
+		for user_id in user_ids {
+			self.token_revocation_service.revoke_user_access_tokens(user_id).await?;
+		}
+
+		Ok(())
+	}
+
+	async fn has_role(&self, user_id: i32, role_name: &str) -> Result<bool, AppError> {
+		let effective_roles = self.get_effective_roles(user_id).await?;
+		Ok(effective_roles.iter().any(|role| role.name == role_name))
+	}
+
+	async fn ensure_admin_remains_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		change: RoleChange,
+	) -> Result<(), AppError> {
+		let all_roles = self.roles_repository.find_all_for_update(transaction).await?;
+
+		let mut roles_after = all_roles.clone();
+		change.apply(&mut roles_after, &mut Vec::new());
+		let admin_role_ids: Vec<i32> = Self::admin_role_ids(&all_roles)
+			.union(&Self::admin_role_ids(&roles_after))
+			.copied()
+			.collect();
+
+		let grants: Vec<(i32, i32)> = self
+			.user_roles_repository
+			.find_active_by_roles_for_update(transaction, admin_role_ids)
+			.await?
+			.into_iter()
+			.map(|user_role| (user_role.user_id, user_role.role_id))
+			.collect();
+
+		if Self::removes_last_admin(&all_roles, &grants, change) {
+			return Err(AppError::BadRequest(translate("roles.errors.last_admin")));
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ids(roles: Vec<RoleModel>) -> Vec<i32> {
+		roles.into_iter().map(|role| role.id).collect()
+	}
+
+	#[test]
+	fn expand_hierarchy_adds_inherited_roles_once() {
+		// admin (1) > manager (2) > user (3)
+		let roles = vec![
+			RoleModel::fixture(1, Some(2)),
+			RoleModel::fixture(2, Some(3)),
+			RoleModel::fixture(3, None),
+			RoleModel::fixture(4, None),
+		];
+
+		assert_eq!(ids(UserRolesService::expand_hierarchy(&roles, [1])), vec![1, 2, 3]);
+		assert_eq!(
+			ids(UserRolesService::expand_hierarchy(&roles, [2, 1, 4])),
+			vec![2, 3, 1, 4]
+		);
+		assert!(UserRolesService::expand_hierarchy(&roles, []).is_empty());
+	}
+
+	#[test]
+	fn expand_hierarchy_skips_unknown_roles() {
+		let roles = vec![RoleModel::fixture(1, Some(99)), RoleModel::fixture(2, None)];
+
+		assert_eq!(ids(UserRolesService::expand_hierarchy(&roles, [98, 1])), vec![1]);
+	}
+
+	// admin (1) > manager (2) > user (3), owner (4) inherits admin
+	fn admin_hierarchy() -> Vec<RoleModel> {
+		let mut roles = vec![
+			RoleModel::fixture(1, Some(2)),
+			RoleModel::fixture(2, Some(3)),
+			RoleModel::fixture(3, None),
+			RoleModel::fixture(4, Some(1)),
+		];
+		roles[0].name = RoleEnum::Admin.as_str().to_string();
+		roles
 	}
 
-	async fn has_role(&self, user_id: i32, role_name: RoleEnum) -> Result<bool, AppError> {
-		self.user_roles_repository.has_role(user_id, role_name).await
+	#[test]
+	fn admin_role_ids_include_roles_inheriting_admin() {
+		assert_eq!(
+			UserRolesService::admin_role_ids(&admin_hierarchy()),
+			HashSet::from([1, 4])
+		);
+	}
+
+	#[test]
+	fn removes_last_admin_counts_inherited_admins() {
+		let roles = admin_hierarchy();
+		let grants = [(10, 4), (11, 2)];
+
+		assert!(UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::RemoveGrant {
+				user_id: 10,
+				role_id: 4
+			}
+		));
+		assert!(UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::DeleteUser { user_id: 10 }
+		));
+		assert!(!UserRolesService::removes_last_admin(
+			&roles,
+			&[(10, 4), (12, 1)],
+			RoleChange::RemoveGrant {
+				user_id: 10,
+				role_id: 4
+			}
+		));
+		assert!(!UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::RemoveGrant {
+				user_id: 11,
+				role_id: 2
+			}
+		));
+	}
+
+	#[test]
+	fn removes_last_admin_checks_hierarchy_changes() {
+		let roles = admin_hierarchy();
+		let grants = [(10, 4)];
+
+		assert!(UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::DeleteRole { role_id: 4 }
+		));
+		assert!(UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::SetParent {
+				role_id: 4,
+				parent_id: Some(2)
+			}
+		));
+		// The owner role keeps admin through its new parent
+		assert!(!UserRolesService::removes_last_admin(
+			&roles,
+			&grants,
+			RoleChange::SetParent {
+				role_id: 1,
+				parent_id: None
+			}
+		));
+		assert!(!UserRolesService::removes_last_admin(
+			&roles,
+			&[(10, 4), (11, 1)],
+			RoleChange::DeleteRole { role_id: 4 }
+		));
+	}
+
+	#[test]
+	fn removes_last_admin_allows_changes_without_admins() {
+		assert!(!UserRolesService::removes_last_admin(
+			&admin_hierarchy(),
+			&[(10, 2)],
+			RoleChange::DeleteUser { user_id: 10 }
+		));
+	}
+
+	#[test]
+	fn expand_hierarchy_stops_on_cycles() {
+		let roles = vec![
+			RoleModel::fixture(1, Some(2)),
+			RoleModel::fixture(2, Some(3)),
+			RoleModel::fixture(3, Some(1)),
+		];
+
+		assert_eq!(ids(UserRolesService::expand_hierarchy(&roles, [2])), vec![2, 3, 1]);
+		assert_eq!(ids(UserRolesService::expand_hierarchy(&roles, [1, 3])), vec![1, 2, 3]);
 	}
 }
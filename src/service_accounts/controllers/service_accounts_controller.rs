@@ -0,0 +1,89 @@
+use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
+use crate::auth::extractor::permission_extractor::{RequirePermission, ServiceAccountsManage};
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
+use crate::common::error::app_error::AppError;
+use crate::service_accounts::dto::create_service_account_dto::CreateServiceAccountDto;
+use crate::service_accounts::dto::update_service_account_dto::UpdateServiceAccountDto;
+use crate::service_accounts::services::service_accounts_service::{
+	ServiceAccountCredentialsResponse, ServiceAccountResponse, ServiceAccountsServiceTrait,
+};
+use axum::{
+	Extension, Json, Router,
+	extract::Path,
+	routing::{get, post},
+};
+use std::sync::Arc;
+use validator::Validate;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route("/", get(get_all_service_accounts).post(create_service_account))
+		.route(
+			"/{id}",
+			get(get_service_account_by_id)
+				.put(update_service_account)
+				.delete(delete_service_account),
+		)
+		.route("/{id}/secret", post(rotate_service_account_secret))
+}
+
+async fn get_all_service_accounts(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+) -> Result<Json<Vec<ServiceAccountResponse>>, AppError> {
+	let service_accounts = service_accounts_service.find_all().await?;
+	Ok(Json(service_accounts))
+}
+
+async fn get_service_account_by_id(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<Json<ServiceAccountResponse>, AppError> {
+	let service_account = service_accounts_service.find_by_id(id).await?;
+	Ok(Json(service_account))
+}
+
+async fn create_service_account(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	Json(dto): Json<CreateServiceAccountDto>,
+) -> Result<Json<ServiceAccountCredentialsResponse>, AppError> {
+	dto.validate()?;
+	let service_account = service_accounts_service.create(dto).await?;
+	Ok(Json(service_account))
+}
+
+async fn update_service_account(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	Path(id): Path<i32>,
+	Json(dto): Json<UpdateServiceAccountDto>,
+) -> Result<Json<ServiceAccountResponse>, AppError> {
+	dto.validate()?;
+	let service_account = service_accounts_service.update(id, dto).await?;
+	Ok(Json(service_account))
+}
+
+async fn rotate_service_account_secret(
+	JwtAuth(_claims): JwtAuth,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<Json<ServiceAccountCredentialsResponse>, AppError> {
+	let service_account = service_accounts_service.rotate_secret(id).await?;
+	Ok(Json(service_account))
+}
+
+async fn delete_service_account(
+	RecentAuth(_claims): RecentAuth<300>,
+	_permission: RequirePermission<ServiceAccountsManage>,
+	Extension(service_accounts_service): Extension<Arc<dyn ServiceAccountsServiceTrait>>,
+	Path(id): Path<i32>,
+) -> Result<(), AppError> {
+	service_accounts_service.delete(id).await
+}
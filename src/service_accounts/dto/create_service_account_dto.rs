@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CreateServiceAccountDto {
+	#[validate(length(min = 1, max = 100))]
+	pub name: String,
+	#[validate(length(max = 255))]
+	pub description: Option<String>,
+	/// Names of the roles from the `roles` table
+	pub roles: Vec<String>,
+}
@@ -0,0 +1,2 @@
+pub mod create_service_account_dto;
+pub mod update_service_account_dto;
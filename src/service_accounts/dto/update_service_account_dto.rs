@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateServiceAccountDto {
+	#[validate(length(min = 1, max = 100))]
+	pub name: Option<String>,
+	#[validate(length(max = 255))]
+	pub description: Option<String>,
+	pub is_active: Option<bool>,
+	/// Replaces the assigned roles when present
+	pub roles: Option<Vec<String>>,
+}
@@ -0,0 +1,2 @@
+pub mod service_account_roles;
+pub mod service_accounts;
@@ -0,0 +1,42 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "service_account_roles")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub service_account_id: i32,
+	pub role_id: i32,
+	pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(
+		belongs_to = "super::service_accounts::Entity",
+		from = "Column::ServiceAccountId",
+		to = "super::service_accounts::Column::Id"
+	)]
+	ServiceAccount,
+	#[sea_orm(
+		belongs_to = "crate::roles::entities::roles::Entity",
+		from = "Column::RoleId",
+		to = "crate::roles::entities::roles::Column::Id"
+	)]
+	Role,
+}
+
+impl Related<super::service_accounts::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::ServiceAccount.def()
+	}
+}
+
+impl Related<crate::roles::entities::roles::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Role.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
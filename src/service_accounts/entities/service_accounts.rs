@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "service_accounts")]
+pub struct Model {
+	#[sea_orm(primary_key)]
+	pub id: i32,
+	pub name: String,
+	pub description: Option<String>,
+	#[sea_orm(unique)]
+	pub client_id: String,
+	#[serde(skip_serializing)]
+	pub client_secret_hash: String,
+	pub is_active: bool,
+	/// Access tokens issued at or before this moment are rejected, set when the secret is rotated
+	#[serde(skip_serializing)]
+	pub tokens_valid_after: Option<DateTimeWithTimeZone>,
+	pub created_at: DateTimeWithTimeZone,
+	pub updated_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+	#[sea_orm(has_many = "super::service_account_roles::Entity")]
+	ServiceAccountRole,
+}
+
+impl Related<super::service_account_roles::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::ServiceAccountRole.def()
+	}
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,6 @@
+pub mod controllers;
+pub mod dto;
+pub mod entities;
+pub mod repositories;
+pub mod service_accounts_module;
+pub mod services;
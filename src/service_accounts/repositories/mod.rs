@@ -0,0 +1 @@
+pub mod service_accounts_repository;
@@ -0,0 +1,209 @@
+use crate::common::error::app_error::AppError;
+use crate::di::DatabaseConnectionTrait;
+use crate::roles::entities::roles::{Entity as Role, Model as RoleModel};
+use crate::service_accounts::entities::service_account_roles::{
+	self, ActiveModel as ServiceAccountRoleActiveModel, Entity as ServiceAccountRole,
+};
+use crate::service_accounts::entities::service_accounts::{
+	self, ActiveModel as ServiceAccountActiveModel, Entity as ServiceAccount, Model as ServiceAccountModel,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, Set,
+	TransactionTrait,
+};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ServiceAccountsRepository {
+	db_connection: Arc<dyn DatabaseConnectionTrait>,
+}
+
+impl ServiceAccountsRepository {
+	pub fn new(db_connection: Arc<dyn DatabaseConnectionTrait>) -> Self {
+		Self { db_connection }
+	}
+
+	async fn replace_roles_in_transaction(
+		&self,
+		transaction: &DatabaseTransaction,
+		service_account_id: i32,
+		role_ids: &[i32],
+	) -> Result<(), AppError> {
+		ServiceAccountRole::delete_many()
+			.filter(service_account_roles::Column::ServiceAccountId.eq(service_account_id))
+			.exec(transaction)
+			.await?;
+
+		let now = Utc::now();
+		for role_id in role_ids {
+			let service_account_role = ServiceAccountRoleActiveModel {
+				service_account_id: Set(service_account_id),
+				role_id: Set(*role_id),
+				created_at: Set(now.into()),
+				..Default::default()
+			};
+			service_account_role.insert(transaction).await?;
+		}
+
+		Ok(())
+	}
+}
+
+#[async_trait]
+pub trait ServiceAccountsRepositoryTrait: Send + Sync {
+	fn get_db(&self) -> &DatabaseConnection;
+	async fn find_all(&self) -> Result<Vec<ServiceAccountModel>, AppError>;
+	async fn find_by_id(&self, id: i32) -> Result<ServiceAccountModel, AppError>;
+	async fn find_by_client_id(&self, client_id: &str) -> Result<Option<ServiceAccountModel>, AppError>;
+	async fn find_roles(&self, service_account_id: i32) -> Result<Vec<RoleModel>, AppError>;
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		client_id: String,
+		client_secret_hash: String,
+		role_ids: Vec<i32>,
+	) -> Result<ServiceAccountModel, AppError>;
+	async fn update(
+		&self,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		is_active: Option<bool>,
+		role_ids: Option<Vec<i32>>,
+	) -> Result<ServiceAccountModel, AppError>;
+	/// Replaces the secret and revokes the access tokens issued with the old one.
+	async fn update_client_secret_hash(&self, id: i32, client_secret_hash: String) -> Result<(), AppError>;
+	async fn delete(&self, id: i32) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl ServiceAccountsRepositoryTrait for ServiceAccountsRepository {
+	fn get_db(&self) -> &DatabaseConnection {
+		self.db_connection.get_connection()
+	}
+
+	async fn find_all(&self) -> Result<Vec<ServiceAccountModel>, AppError> {
+		let service_accounts = ServiceAccount::find().all(self.get_db()).await?;
+		Ok(service_accounts)
+	}
+
+	async fn find_by_id(&self, id: i32) -> Result<ServiceAccountModel, AppError> {
+		let service_account = ServiceAccount::find_by_id(id)
+			.one(self.get_db())
+			.await?
+			.ok_or(AppError::NotFound)?;
+		Ok(service_account)
+	}
+
+	async fn find_by_client_id(&self, client_id: &str) -> Result<Option<ServiceAccountModel>, AppError> {
+		let service_account = ServiceAccount::find()
+			.filter(service_accounts::Column::ClientId.eq(client_id))
+			.one(self.get_db())
+			.await?;
+		Ok(service_account)
+	}
+
+	async fn find_roles(&self, service_account_id: i32) -> Result<Vec<RoleModel>, AppError> {
+		let service_account_roles = ServiceAccountRole::find()
+			.filter(service_account_roles::Column::ServiceAccountId.eq(service_account_id))
+			.find_with_related(Role)
+			.all(self.get_db())
+			.await?;
+
+		let roles = service_account_roles.into_iter().flat_map(|(_, roles)| roles).collect();
+
+		Ok(roles)
+	}
+
+	async fn create(
+		&self,
+		name: String,
+		description: Option<String>,
+		client_id: String,
+		client_secret_hash: String,
+		role_ids: Vec<i32>,
+	) -> Result<ServiceAccountModel, AppError> {
+		let transaction = self.get_db().begin().await?;
+		let now = Utc::now();
+
+		let service_account_active_model = ServiceAccountActiveModel {
+			name: Set(name),
+			description: Set(description),
+			client_id: Set(client_id),
+			client_secret_hash: Set(client_secret_hash),
+			is_active: Set(true),
+			created_at: Set(now.into()),
+			updated_at: Set(None),
+			..Default::default()
+		};
+
+		let service_account = service_account_active_model.insert(&transaction).await?;
+		self.replace_roles_in_transaction(&transaction, service_account.id, &role_ids)
+			.await?;
+
+		transaction.commit().await?;
+
+		Ok(service_account)
+	}
+
+	async fn update(
+		&self,
+		id: i32,
+		name: Option<String>,
+		description: Option<String>,
+		is_active: Option<bool>,
+		role_ids: Option<Vec<i32>>,
+	) -> Result<ServiceAccountModel, AppError> {
+		let service_account = self.find_by_id(id).await?;
+		let transaction = self.get_db().begin().await?;
+
+		let mut service_account_active_model: ServiceAccountActiveModel = service_account.into();
+
+		if let Some(name) = name {
+			service_account_active_model.name = Set(name);
+		}
+
+		if let Some(description) = description {
+			service_account_active_model.description = Set(Some(description));
+		}
+
+		if let Some(is_active) = is_active {
+			service_account_active_model.is_active = Set(is_active);
+		}
+
+		service_account_active_model.updated_at = Set(Some(Utc::now().into()));
+		let updated_service_account = service_account_active_model.update(&transaction).await?;
+
+		if let Some(role_ids) = role_ids {
+			self.replace_roles_in_transaction(&transaction, id, &role_ids).await?;
+		}
+
+		transaction.commit().await?;
+
+		Ok(updated_service_account)
+	}
+
+	async fn update_client_secret_hash(&self, id: i32, client_secret_hash: String) -> Result<(), AppError> {
+		let now = Utc::now();
+		let service_account_active_model = ServiceAccountActiveModel {
+			id: Set(id),
+			client_secret_hash: Set(client_secret_hash),
+			tokens_valid_after: Set(Some(now.into())),
+			updated_at: Set(Some(now.into())),
+			..Default::default()
+		};
+
+		service_account_active_model.update(self.get_db()).await?;
+		Ok(())
+	}
+
+	async fn delete(&self, id: i32) -> Result<(), AppError> {
+		let service_account = self.find_by_id(id).await?;
+		let service_account_active_model: ServiceAccountActiveModel = service_account.into();
+		service_account_active_model.delete(self.get_db()).await?;
+		Ok(())
+	}
+}
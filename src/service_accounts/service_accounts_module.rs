@@ -0,0 +1,6 @@
+use crate::service_accounts::controllers::service_accounts_controller;
+use axum::Router;
+
+pub fn configure() -> Router {
+	Router::new().nest("/api/service-accounts", service_accounts_controller::routes())
+}
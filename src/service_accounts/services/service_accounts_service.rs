@@ -0,0 +1,281 @@
+use crate::auth::services::auth_service::Claims;
+use crate::auth::services::jwt_keys_service::JwtKeysServiceTrait;
+use crate::common::error::app_error::AppError;
+use crate::common::utils::token_utils::{generate_opaque_token, hash_token, verify_token_hash};
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use crate::roles::repositories::roles_repository::RolesRepositoryTrait;
+use crate::service_accounts::dto::create_service_account_dto::CreateServiceAccountDto;
+use crate::service_accounts::dto::update_service_account_dto::UpdateServiceAccountDto;
+use crate::service_accounts::entities::service_accounts::Model as ServiceAccountModel;
+use crate::service_accounts::repositories::service_accounts_repository::ServiceAccountsRepositoryTrait;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct ServiceAccountResponse {
+	pub id: i32,
+	pub name: String,
+	pub description: Option<String>,
+	pub client_id: String,
+	pub is_active: bool,
+	pub roles: Vec<String>,
+	pub created_at: DateTimeWithTimeZone,
+	pub updated_at: Option<DateTimeWithTimeZone>,
+}
+
+/// Returned on creation and secret rotation, the plain secret cannot be retrieved afterwards.
+#[derive(Debug, Serialize)]
+pub struct ServiceAccountCredentialsResponse {
+	#[serde(flatten)]
+	pub service_account: ServiceAccountResponse,
+	pub client_secret: String,
+}
+
+/// Successful response of the OAuth2 client credentials grant (RFC 6749, section 4.4.3).
+#[derive(Debug, Serialize)]
+pub struct ClientCredentialsTokenResponse {
+	pub access_token: String,
+	pub token_type: &'static str,
+	pub expires_in: i64,
+	pub scope: String,
+}
+
+#[derive(Clone)]
+pub struct ServiceAccountsService {
+	service_accounts_repository: Arc<dyn ServiceAccountsRepositoryTrait>,
+	roles_repository: Arc<dyn RolesRepositoryTrait>,
+	jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
+	client_secret_hash_secret: String,
+	access_token_expires_in: i64,
+}
+
+impl ServiceAccountsService {
+	pub fn new(
+		service_accounts_repository: Arc<dyn ServiceAccountsRepositoryTrait>,
+		roles_repository: Arc<dyn RolesRepositoryTrait>,
+		jwt_keys_service: Arc<dyn JwtKeysServiceTrait>,
+		app_config: Arc<dyn AppConfigTrait>,
+	) -> Self {
+		let client_credentials_config = &app_config.get_config().security.tokens.client_credentials;
+		Self {
+			service_accounts_repository,
+			roles_repository,
+			jwt_keys_service,
+			client_secret_hash_secret: client_credentials_config.secret.clone(),
+			access_token_expires_in: client_credentials_config.expires_in,
+		}
+	}
+
+	fn hash_client_secret(&self, client_secret: &str) -> String {
+		hash_token(&self.client_secret_hash_secret, client_secret)
+	}
+
+	fn verify_client_secret(&self, client_secret: &str, client_secret_hash: &str) -> bool {
+		verify_token_hash(&self.client_secret_hash_secret, client_secret, client_secret_hash)
+	}
+
+	fn invalid_client_error() -> AppError {
+		AppError::AuthenticationError(translate("auth.errors.invalid_client"))
+	}
+
+	async fn resolve_role_ids(&self, role_names: &[String]) -> Result<Vec<i32>, AppError> {
+		let mut role_ids = Vec::with_capacity(role_names.len());
+
+		for role_name in role_names {
+			let role = match self.roles_repository.find_by_name(role_name).await {
+				Ok(role) => role,
+				Err(AppError::NotFound) => {
+					let mut errors = validator::ValidationErrors::new();
+					errors.add(
+						"roles",
+						validator::ValidationError::new("not_found")
+							.with_message(translate("service_accounts.errors.role_not_found").into()),
+					);
+					return Err(AppError::ValidationError(errors));
+				}
+				Err(e) => return Err(e),
+			};
+
+			if !role_ids.contains(&role.id) {
+				role_ids.push(role.id);
+			}
+		}
+
+		Ok(role_ids)
+	}
+
+	async fn to_response(&self, service_account: ServiceAccountModel) -> Result<ServiceAccountResponse, AppError> {
+		let roles = self
+			.service_accounts_repository
+			.find_roles(service_account.id)
+			.await?
+			.into_iter()
+			.map(|role| role.name)
+			.collect();
+
+		Ok(ServiceAccountResponse {
+			id: service_account.id,
+			name: service_account.name,
+			description: service_account.description,
+			client_id: service_account.client_id,
+			is_active: service_account.is_active,
+			roles,
+			created_at: service_account.created_at,
+			updated_at: service_account.updated_at,
+		})
+	}
+}
+
+#[async_trait]
+pub trait ServiceAccountsServiceTrait: Send + Sync {
+	async fn find_all(&self) -> Result<Vec<ServiceAccountResponse>, AppError>;
+	async fn find_by_id(&self, id: i32) -> Result<ServiceAccountResponse, AppError>;
+	async fn create(&self, dto: CreateServiceAccountDto) -> Result<ServiceAccountCredentialsResponse, AppError>;
+	async fn update(&self, id: i32, dto: UpdateServiceAccountDto) -> Result<ServiceAccountResponse, AppError>;
+	async fn rotate_secret(&self, id: i32) -> Result<ServiceAccountCredentialsResponse, AppError>;
+	async fn delete(&self, id: i32) -> Result<(), AppError>;
+	async fn issue_token(
+		&self,
+		client_id: &str,
+		client_secret: &str,
+		scope: Option<&str>,
+	) -> Result<ClientCredentialsTokenResponse, AppError>;
+}
+
+#[async_trait]
+impl ServiceAccountsServiceTrait for ServiceAccountsService {
+	async fn find_all(&self) -> Result<Vec<ServiceAccountResponse>, AppError> {
+		let service_accounts = self.service_accounts_repository.find_all().await?;
+
+		let mut responses = Vec::with_capacity(service_accounts.len());
+		for service_account in service_accounts {
+			responses.push(self.to_response(service_account).await?);
+		}
+
+		Ok(responses)
+	}
+
+	async fn find_by_id(&self, id: i32) -> Result<ServiceAccountResponse, AppError> {
+		let service_account = self.service_accounts_repository.find_by_id(id).await?;
+		self.to_response(service_account).await
+	}
+
+	async fn create(&self, dto: CreateServiceAccountDto) -> Result<ServiceAccountCredentialsResponse, AppError> {
+		let role_ids = self.resolve_role_ids(&dto.roles).await?;
+		let client_id = Uuid::new_v4().simple().to_string();
+		let client_secret = generate_opaque_token();
+
+		let service_account = self
+			.service_accounts_repository
+			.create(
+				dto.name,
+				dto.description,
+				client_id,
+				self.hash_client_secret(&client_secret),
+				role_ids,
+			)
+			.await?;
+
+		Ok(ServiceAccountCredentialsResponse {
+			service_account: self.to_response(service_account).await?,
+			client_secret,
+		})
+	}
+
+	async fn update(&self, id: i32, dto: UpdateServiceAccountDto) -> Result<ServiceAccountResponse, AppError> {
+		let role_ids = match dto.roles {
+			Some(roles) => Some(self.resolve_role_ids(&roles).await?),
+			None => None,
+		};
+
+		let service_account = self
+			.service_accounts_repository
+			.update(id, dto.name, dto.description, dto.is_active, role_ids)
+			.await?;
+
+		self.to_response(service_account).await
+	}
+
+	async fn rotate_secret(&self, id: i32) -> Result<ServiceAccountCredentialsResponse, AppError> {
+		let service_account = self.service_accounts_repository.find_by_id(id).await?;
+		let client_secret = generate_opaque_token();
+
+		self.service_accounts_repository
+			.update_client_secret_hash(id, self.hash_client_secret(&client_secret))
+			.await?;
+
+		Ok(ServiceAccountCredentialsResponse {
+			service_account: self.to_response(service_account).await?,
+			client_secret,
+		})
+	}
+
+	async fn delete(&self, id: i32) -> Result<(), AppError> {
+		self.service_accounts_repository.delete(id).await
+	}
+
+	async fn issue_token(
+		&self,
+		client_id: &str,
+		client_secret: &str,
+		scope: Option<&str>,
+	) -> Result<ClientCredentialsTokenResponse, AppError> {
+		let service_account = self
+			.service_accounts_repository
+			.find_by_client_id(client_id)
+			.await?
+			.ok_or_else(Self::invalid_client_error)?;
+
+		if !service_account.is_active || !self.verify_client_secret(client_secret, &service_account.client_secret_hash)
+		{
+			return Err(Self::invalid_client_error());
+		}
+
+		let mut roles: Vec<String> = self
+			.service_accounts_repository
+			.find_roles(service_account.id)
+			.await?
+			.into_iter()
+			.map(|role| role.name)
+			.collect();
+
+		// The requested scope can only narrow down the assigned roles
+		if let Some(scope) = scope.filter(|scope| !scope.trim().is_empty()) {
+			let requested: Vec<&str> = scope.split_whitespace().collect();
+			roles.retain(|role| requested.contains(&role.as_str()));
+		}
+
+		let now = Utc::now();
+		let claims = Claims {
+			sub: service_account.id,
+			username: service_account.name,
+			email: String::new(),
+			roles,
+			exp: (now + Duration::seconds(self.access_token_expires_in)).timestamp(),
+			iat: now.timestamp(),
+			jti: Uuid::new_v4().to_string(),
+			client_id: Some(service_account.client_id),
+			act: None,
+			auth_time: None,
+		};
+
+		let access_token = self.jwt_keys_service.encode(&claims)?;
+
+		Ok(ClientCredentialsTokenResponse {
+			access_token,
+			token_type: "Bearer",
+			expires_in: self.access_token_expires_in,
+			scope: claims
+				.roles
+				.iter()
+				.map(|role| role.as_str())
+				.collect::<Vec<_>>()
+				.join(" "),
+		})
+	}
+}
@@ -1,5 +1,6 @@
 use crate::auth::extractor::jwt_auth_extractor::JwtAuth;
-use crate::auth::extractor::role_extractor::AdminRole;
+use crate::auth::extractor::permission_extractor::{RequirePermission, UsersDelete, UsersWrite};
+use crate::auth::extractor::recent_auth_extractor::RecentAuth;
 use crate::common::error::app_error::AppError;
 use crate::users::dto::create_user_dto::CreateUserDto;
 use crate::users::dto::update_user_dto::UpdateUserDto;
@@ -34,7 +35,7 @@ async fn get_user_by_id(
 
 async fn create_user(
 	JwtAuth(_claims): JwtAuth,
-	_admin_role: AdminRole,
+	_permission: RequirePermission<UsersWrite>,
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Json(dto): Json<CreateUserDto>,
 ) -> Result<Json<users::Model>, AppError> {
@@ -45,7 +46,7 @@ async fn create_user(
 
 async fn update_user(
 	JwtAuth(_claims): JwtAuth,
-	_admin_role: AdminRole,
+	_permission: RequirePermission<UsersWrite>,
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Path(id): Path<i32>,
 	Json(dto): Json<UpdateUserDto>,
@@ -56,8 +57,8 @@ async fn update_user(
 }
 
 async fn delete_user(
-	JwtAuth(_claims): JwtAuth,
-	_admin_role: AdminRole,
+	RecentAuth(_claims): RecentAuth<300>,
+	_permission: RequirePermission<UsersDelete>,
 	Extension(users_service): Extension<Arc<dyn UsersServiceTrait>>,
 	Path(id): Path<i32>,
 ) -> Result<(), AppError> {
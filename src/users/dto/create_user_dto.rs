@@ -10,6 +10,7 @@ pub struct CreateUserDto {
 	#[validate(email)]
 	pub email: String,
 
-	#[validate(length(min = 8))]
+	// Checked against the password policy by the service
+	#[validate(length(min = 1))]
 	pub password: String,
 }
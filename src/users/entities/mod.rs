@@ -1,2 +1,3 @@
+pub mod password_history;
 pub mod users;
 pub mod users_email_history;
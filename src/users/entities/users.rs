@@ -30,6 +30,27 @@ pub struct Model {
 	pub password_reset_token_expiry: Option<DateTimeWithTimeZone>,
 	#[serde(skip_serializing)]
 	pub pending_email: Option<String>,
+	#[serde(skip_serializing)]
+	pub two_factor_secret: Option<String>,
+	pub is_two_factor_enabled: bool,
+	#[serde(skip_serializing)]
+	#[sea_orm(column_type = "Json", nullable)]
+	pub two_factor_recovery_codes: Option<Json>,
+	#[serde(skip_serializing)]
+	pub two_factor_last_used_step: Option<i64>,
+	#[serde(skip_serializing)]
+	pub tokens_valid_after: Option<DateTimeWithTimeZone>,
+	/// Like `tokens_valid_after`, but only for JWT access tokens, e.g. after a role change
+	#[serde(skip_serializing)]
+	pub access_tokens_valid_after: Option<DateTimeWithTimeZone>,
+	#[serde(skip_serializing)]
+	pub magic_login_token_jti: Option<String>,
+	#[serde(skip_serializing)]
+	pub magic_login_token_expiry: Option<DateTimeWithTimeZone>,
+	#[serde(skip_serializing)]
+	pub mfa_token_jti: Option<String>,
+	#[serde(skip_serializing)]
+	pub mfa_failed_attempts: i32,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: Option<DateTimeWithTimeZone>,
 }
@@ -31,7 +31,7 @@ pub trait UsersRepositoryTrait: Send + Sync {
 		password_hash: String,
 	) -> Result<UserModel, AppError>;
 	async fn update(&self, id: i32, dto: UpdateUserDto) -> Result<UserModel, AppError>;
-	async fn delete(&self, id: i32) -> Result<(), AppError>;
+	async fn delete_in_transaction(&self, transaction: &DatabaseTransaction, id: i32) -> Result<(), AppError>;
 	async fn find_by_email(&self, email: &str) -> Result<UserModel, AppError>;
 }
 
@@ -108,15 +108,15 @@ impl UsersRepositoryTrait for UsersRepository {
 		Ok(updated_user)
 	}
 
-	async fn delete(&self, id: i32) -> Result<(), AppError> {
-		let user = self.find_by_id(id).await?;
+	async fn delete_in_transaction(&self, transaction: &DatabaseTransaction, id: i32) -> Result<(), AppError> {
+		let user = User::find_by_id(id).one(transaction).await?.ok_or(AppError::NotFound)?;
 		let mut user_active_model: UserActiveModel = user.into();
 
 		user_active_model.is_active = Set(false);
 		let now = chrono::Utc::now();
 		user_active_model.updated_at = Set(Some(now.into()));
 
-		user_active_model.update(self.get_db()).await?;
+		user_active_model.update(transaction).await?;
 
 		Ok(())
 	}
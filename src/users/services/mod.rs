@@ -1 +1,2 @@
+pub mod password_policy_service;
 pub mod users_service;
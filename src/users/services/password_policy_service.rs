@@ -0,0 +1,237 @@
+use crate::common::error::app_error::AppError;
+use crate::config::app_config::PasswordPolicyConfig;
+use crate::di::AppConfigTrait;
+use crate::i18n::setup::translate;
+use async_trait::async_trait;
+use sha1::{Digest, Sha1};
+use std::path::Path;
+use std::sync::Arc;
+use validator::{ValidationError, ValidationErrors};
+
+/// Rules every new password has to satisfy, whether it is set at registration, by an admin,
+/// on a password change or on a password reset.
+pub struct PasswordPolicyService {
+	policy: PasswordPolicyConfig,
+}
+
+impl PasswordPolicyService {
+	pub fn new(app_config: Arc<dyn AppConfigTrait>) -> Self {
+		let policy = app_config.get_config().security.password_policy.clone();
+		Self { policy }
+	}
+
+	fn error(code: &'static str, message: String) -> ValidationError {
+		ValidationError::new(code).with_message(message.into())
+	}
+
+	fn rule_errors(&self, password: &str, username: &str, email: &str) -> Vec<ValidationError> {
+		let mut errors = Vec::new();
+		let length = password.chars().count();
+
+		if length < self.policy.min_length {
+			errors.push(Self::error(
+				"too_short",
+				translate("users.validators.password.too_short").replace("%{min}", &self.policy.min_length.to_string()),
+			));
+		}
+
+		if length > self.policy.max_length {
+			errors.push(Self::error(
+				"too_long",
+				translate("users.validators.password.too_long").replace("%{max}", &self.policy.max_length.to_string()),
+			));
+		}
+
+		if self.policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+			errors.push(Self::error(
+				"no_lowercase",
+				translate("users.validators.password.no_lowercase"),
+			));
+		}
+
+		if self.policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+			errors.push(Self::error(
+				"no_uppercase",
+				translate("users.validators.password.no_uppercase"),
+			));
+		}
+
+		if self.policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+			errors.push(Self::error("no_digit", translate("users.validators.password.no_digit")));
+		}
+
+		if self.policy.require_symbol && password.chars().all(char::is_alphanumeric) {
+			errors.push(Self::error(
+				"no_symbol",
+				translate("users.validators.password.no_symbol"),
+			));
+		}
+
+		if self.policy.reject_user_info && Self::contains_user_info(password, username, email) {
+			errors.push(Self::error(
+				"contains_user_info",
+				translate("users.validators.password.contains_user_info"),
+			));
+		}
+
+		errors
+	}
+
+	fn contains_user_info(password: &str, username: &str, email: &str) -> bool {
+		let password = password.to_lowercase();
+		let email_local_part = email.split('@').next().unwrap_or_default();
+
+		// Very short fragments would reject too many unrelated passwords
+		[username, email_local_part]
+			.iter()
+			.map(|part| part.trim().to_lowercase())
+			.any(|part| part.chars().count() >= 3 && password.contains(&part))
+	}
+
+	/// Looks the password up in a local copy of a k-anonymity breach corpus. Only the range file
+	/// for the first five characters of the SHA-1 hash is read, in the `SUFFIX:COUNT` line format
+	/// served by the Pwned Passwords range API.
+	async fn is_breached(&self, dir: &str, password: &str) -> Result<bool, AppError> {
+		let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+		let (prefix, suffix) = hash.split_at(5);
+
+		let range_path = Path::new(dir).join(format!("{}.txt", prefix));
+		let range = match tokio::fs::read_to_string(&range_path).await {
+			Ok(range) => range,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+				tracing::warn!("Breached password range file {} is missing", range_path.display());
+				return Ok(false);
+			}
+			Err(e) => {
+				tracing::error!("Error reading breached password range {}: {}", range_path.display(), e);
+				return Err(AppError::InternalError);
+			}
+		};
+
+		Ok(Self::range_contains(&range, suffix))
+	}
+
+	/// Padding entries with a count of 0 do not count as breached, a missing count does.
+	fn range_contains(range: &str, suffix: &str) -> bool {
+		range.lines().any(|line| {
+			let mut parts = line.trim().splitn(2, ':');
+			let line_suffix = parts.next().unwrap_or_default();
+			let count = parts
+				.next()
+				.and_then(|count| count.trim().parse::<u64>().ok())
+				.unwrap_or(1);
+			count > 0 && line_suffix.eq_ignore_ascii_case(suffix)
+		})
+	}
+}
+
+#[async_trait]
+pub trait PasswordPolicyServiceTrait: Send + Sync {
+	/// Reports every broken rule under `field`, so the caller can point at the right input.
+	async fn validate(&self, field: &'static str, password: &str, username: &str, email: &str) -> Result<(), AppError>;
+	fn history_size(&self) -> u64;
+}
+
+#[async_trait]
+impl PasswordPolicyServiceTrait for PasswordPolicyService {
+	async fn validate(&self, field: &'static str, password: &str, username: &str, email: &str) -> Result<(), AppError> {
+		let mut errors = self.rule_errors(password, username, email);
+
+		// The corpus is only consulted for passwords that pass the cheap checks
+		if errors.is_empty()
+			&& let Some(dir) = &self.policy.breached_passwords_dir
+			&& self.is_breached(dir, password).await?
+		{
+			errors.push(Self::error("breached", translate("users.validators.password.breached")));
+		}
+
+		if errors.is_empty() {
+			return Ok(());
+		}
+
+		let mut validation_errors = ValidationErrors::new();
+		for error in errors {
+			validation_errors.add(field, error);
+		}
+
+		Err(AppError::ValidationError(validation_errors))
+	}
+
+	fn history_size(&self) -> u64 {
+		self.policy.history_size
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// SHA-1 of "password" is 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+	const SUFFIX: &str = "1E4C9B93F3F0682250B6CF8331B7EE68FD8";
+
+	#[test]
+	fn range_file_is_named_after_the_hash_prefix() {
+		let hash = hex::encode_upper(Sha1::digest(b"password"));
+
+		assert_eq!(hash.split_at(5), ("5BAA6", SUFFIX));
+	}
+
+	#[test]
+	fn contains_user_info_matches_the_username_and_email_case_insensitively() {
+		assert!(PasswordPolicyService::contains_user_info(
+			"MyJohnDoe!2024",
+			"johndoe",
+			"other@example.com"
+		));
+		assert!(PasswordPolicyService::contains_user_info(
+			"secret-JANE.SMITH-1",
+			"someone",
+			"Jane.Smith@example.com"
+		));
+		assert!(!PasswordPolicyService::contains_user_info(
+			"Correct-Horse-42",
+			"johndoe",
+			"jane@example.com"
+		));
+	}
+
+	#[test]
+	fn contains_user_info_ignores_short_fragments_and_the_email_domain() {
+		assert!(!PasswordPolicyService::contains_user_info(
+			"Jo-Password-1",
+			"jo",
+			"jo@example.com"
+		));
+		assert!(!PasswordPolicyService::contains_user_info(
+			"example.com-1",
+			"johndoe",
+			"jane@example.com"
+		));
+		assert!(!PasswordPolicyService::contains_user_info("Password-1", "  ", ""));
+	}
+
+	#[test]
+	fn range_contains_finds_the_suffix() {
+		let range = format!(
+			"0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n{}:9545824\r\n",
+			SUFFIX.to_lowercase()
+		);
+
+		assert!(PasswordPolicyService::range_contains(&range, SUFFIX));
+		assert!(!PasswordPolicyService::range_contains(
+			&range,
+			"00D4F6E8FA6EECAD2A3AA415EEC418D38EC"
+		));
+		assert!(!PasswordPolicyService::range_contains("", SUFFIX));
+	}
+
+	#[test]
+	fn range_contains_skips_padding_entries() {
+		assert!(!PasswordPolicyService::range_contains(&format!("{SUFFIX}:0"), SUFFIX));
+		assert!(PasswordPolicyService::range_contains(SUFFIX, SUFFIX));
+		assert!(PasswordPolicyService::range_contains(
+			&format!("{SUFFIX}:not a count"),
+			SUFFIX
+		));
+	}
+}
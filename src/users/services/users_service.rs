@@ -2,30 +2,39 @@ use crate::auth::dto::change_email_dto::ChangeEmailDto;
 use crate::auth::dto::change_password_dto::ChangePasswordDto;
 use crate::auth::dto::forgot_password_dto::ForgotPasswordDto;
 use crate::auth::dto::register_dto::RegisterDto;
+use crate::auth::dto::resend_confirmation_dto::ResendConfirmationDto;
 use crate::auth::dto::reset_password_dto::ResetPasswordDto;
 use crate::auth::services::auth_service::AuthResponse;
 use crate::auth::services::confirmation_token_service::{
 	ConfirmationTokenService, ConfirmationTokenServiceTrait, TokenType,
 };
+use crate::auth::services::login_protection_service::LoginProtectionServiceTrait;
+use crate::auth::services::refresh_token_service::RefreshTokenServiceTrait;
+use crate::auth::services::token_revocation_service::TokenRevocationServiceTrait;
 use crate::common::error::app_error::AppError;
-use crate::config::app_config::AppConfig;
+use crate::config::app_config::{AppConfig, PasswordHashingConfig};
 use crate::di::AppConfigTrait;
 use crate::emails::services::emails_service::{EmailsService, EmailsServiceTrait};
 use crate::i18n::setup::translate;
-use crate::roles::services::user_roles_service::{UserRolesService, UserRolesServiceTrait};
+use crate::roles::services::user_roles_service::{RoleChange, UserRolesService, UserRolesServiceTrait};
 use crate::users::dto::create_user_dto::CreateUserDto;
 use crate::users::dto::update_user_dto::UpdateUserDto;
+use crate::users::entities::password_history::{self, Entity as PasswordHistory};
 use crate::users::entities::users::{self, Entity as User, Model as UserModel};
 use crate::users::entities::users_email_history::{self, ActiveModel as UserEmailHistoryActiveModel};
 use crate::users::repositories::users_repository::{UsersRepository, UsersRepositoryTrait};
+use crate::users::services::password_policy_service::PasswordPolicyServiceTrait;
 use argon2::{
-	Argon2,
+	Algorithm, Argon2, KeyId, Params, ParamsBuilder, Version,
 	password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use async_trait::async_trait;
 use chrono::Utc;
-use sea_orm::EntityTrait;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, DatabaseTransaction, Set, TransactionTrait};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder,
+	QuerySelect, Set, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -34,32 +43,81 @@ pub struct UsersService {
 	user_roles_service: Arc<dyn UserRolesServiceTrait>,
 	email_service: Arc<dyn EmailsServiceTrait>,
 	confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+	token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
+	refresh_token_service: Arc<dyn RefreshTokenServiceTrait>,
+	login_protection_service: Arc<dyn LoginProtectionServiceTrait>,
+	password_policy_service: Arc<dyn PasswordPolicyServiceTrait>,
 	app_config: Arc<dyn AppConfigTrait>,
 	confirmation_token_expires_in: i64,
+	password_hashing: PasswordHashingConfig,
 }
 
 impl UsersService {
+	#[allow(clippy::too_many_arguments)]
 	pub fn new(
 		users_repository: Arc<dyn UsersRepositoryTrait>,
 		user_roles_service: Arc<dyn UserRolesServiceTrait>,
 		email_service: Arc<dyn EmailsServiceTrait>,
 		confirmation_token_service: Arc<dyn ConfirmationTokenServiceTrait>,
+		token_revocation_service: Arc<dyn TokenRevocationServiceTrait>,
+		refresh_token_service: Arc<dyn RefreshTokenServiceTrait>,
+		login_protection_service: Arc<dyn LoginProtectionServiceTrait>,
+		password_policy_service: Arc<dyn PasswordPolicyServiceTrait>,
 		app_config: Arc<dyn AppConfigTrait>,
 	) -> Self {
 		let confirmation_token_expires_in = app_config.get_config().security.tokens.confirmation_token.expires_in;
+		let password_hashing = app_config.get_config().security.password_hashing.clone();
 		Self {
 			users_repository,
 			user_roles_service,
 			email_service,
 			confirmation_token_service,
+			token_revocation_service,
+			refresh_token_service,
+			login_protection_service,
+			password_policy_service,
 			app_config,
 			confirmation_token_expires_in,
+			password_hashing,
+		}
+	}
+
+	/// Identifies the pepper a hash was made with, without revealing the pepper itself.
+	fn pepper_key_id(pepper: &str) -> Result<KeyId, AppError> {
+		KeyId::new(&Sha256::digest(pepper.as_bytes())[..4]).map_err(|_| AppError::InternalError)
+	}
+
+	fn argon2_params(&self) -> Result<Params, AppError> {
+		let mut builder = ParamsBuilder::new();
+		builder
+			.m_cost(self.password_hashing.memory_cost)
+			.t_cost(self.password_hashing.time_cost)
+			.p_cost(self.password_hashing.parallelism);
+
+		if let Some(pepper) = &self.password_hashing.pepper {
+			builder.keyid(Self::pepper_key_id(pepper)?);
+		}
+
+		builder.build().map_err(|e| {
+			tracing::error!("Invalid password hashing parameters: {}", e);
+			AppError::InternalError
+		})
+	}
+
+	fn argon2(&self, params: Params) -> Result<Argon2<'_>, AppError> {
+		match &self.password_hashing.pepper {
+			Some(pepper) => Argon2::new_with_secret(pepper.as_bytes(), Algorithm::Argon2id, Version::V0x13, params)
+				.map_err(|e| {
+					tracing::error!("Invalid password pepper: {}", e);
+					AppError::InternalError
+				}),
+			None => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params)),
 		}
 	}
 
 	fn hash_password(&self, password: &str) -> Result<String, AppError> {
 		let salt = SaltString::generate(&mut OsRng);
-		let argon2 = Argon2::default();
+		let argon2 = self.argon2(self.argon2_params()?)?;
 
 		let password_hash = argon2
 			.hash_password(password.as_bytes(), &salt)
@@ -78,9 +136,175 @@ impl UsersService {
 			AppError::InternalError
 		})?;
 
-		Ok(Argon2::default()
-			.verify_password(password.as_bytes(), &parsed_hash)
-			.is_ok())
+		let hash_params = Params::try_from(&parsed_hash).map_err(|_| AppError::InternalError)?;
+
+		// The parameters are read from the hash, only the pepper has to be picked here
+		let verified = if hash_params.keyid().is_empty() {
+			Argon2::default()
+				.verify_password(password.as_bytes(), &parsed_hash)
+				.is_ok()
+		} else {
+			match &self.password_hashing.pepper {
+				Some(pepper) if Self::pepper_key_id(pepper)?.as_bytes() == hash_params.keyid() => self
+					.argon2(Params::default())?
+					.verify_password(password.as_bytes(), &parsed_hash)
+					.is_ok(),
+				_ => {
+					tracing::error!("Password hash was made with a pepper that is no longer configured");
+					false
+				}
+			}
+		};
+
+		Ok(verified)
+	}
+
+	/// Whether the hash was made with other parameters or another pepper than the configured ones.
+	fn needs_rehash(&self, hash: &str) -> Result<bool, AppError> {
+		let parsed_hash = PasswordHash::new(hash).map_err(|_| AppError::InternalError)?;
+		let hash_params = Params::try_from(&parsed_hash).map_err(|_| AppError::InternalError)?;
+		let expected_params = self.argon2_params()?;
+
+		Ok(parsed_hash.algorithm != Algorithm::Argon2id.ident()
+			|| parsed_hash.version != Some(Version::V0x13.into())
+			|| hash_params.m_cost() != expected_params.m_cost()
+			|| hash_params.t_cost() != expected_params.t_cost()
+			|| hash_params.p_cost() != expected_params.p_cost()
+			|| hash_params.keyid() != expected_params.keyid())
+	}
+
+	/// Replaces an outdated hash after a successful login, while the plain password is at hand.
+	async fn upgrade_password_hash(&self, user: &UserModel, password: &str) -> Result<(), AppError> {
+		if !self.needs_rehash(&user.password_hash)? {
+			return Ok(());
+		}
+
+		let user_active_model = users::ActiveModel {
+			id: Set(user.id),
+			password_hash: Set(self.hash_password(password)?),
+			..Default::default()
+		};
+		user_active_model.update(self.users_repository.get_db()).await?;
+
+		Ok(())
+	}
+
+	/// Rejects the current password and the ones kept in the history.
+	async fn ensure_password_not_reused(
+		&self,
+		field: &'static str,
+		user: &UserModel,
+		password: &str,
+	) -> Result<(), AppError> {
+		let history_size = self.password_policy_service.history_size();
+		if history_size == 0 {
+			return Ok(());
+		}
+
+		let previous_hashes = PasswordHistory::find()
+			.filter(password_history::Column::UserId.eq(user.id))
+			.order_by_desc(password_history::Column::CreatedAt)
+			.order_by_desc(password_history::Column::Id)
+			.limit(history_size - 1)
+			.all(self.users_repository.get_db())
+			.await?
+			.into_iter()
+			.map(|entry| entry.password_hash);
+
+		for hash in std::iter::once(user.password_hash.clone()).chain(previous_hashes) {
+			if self.verify_password(password, &hash)? {
+				let mut errors = validator::ValidationErrors::new();
+				errors.add(
+					field,
+					validator::ValidationError::new("reused")
+						.with_message(translate("users.validators.password.reused").into()),
+				);
+				return Err(AppError::ValidationError(errors));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Keeps the replaced hash, trimming entries that fall outside the configured history.
+	async fn record_password_history(
+		&self,
+		transaction: &DatabaseTransaction,
+		user_id: i32,
+		old_password_hash: &str,
+	) -> Result<(), AppError> {
+		let history_size = self.password_policy_service.history_size();
+		if history_size == 0 {
+			return Ok(());
+		}
+
+		let entry = password_history::ActiveModel {
+			user_id: Set(user_id),
+			password_hash: Set(old_password_hash.to_string()),
+			created_at: Set(Utc::now().into()),
+			..Default::default()
+		};
+		entry.insert(transaction).await?;
+
+		// Together with the current password the history covers `history_size` passwords
+		let outdated_ids: Vec<i32> = PasswordHistory::find()
+			.select_only()
+			.column(password_history::Column::Id)
+			.filter(password_history::Column::UserId.eq(user_id))
+			.order_by_desc(password_history::Column::CreatedAt)
+			.order_by_desc(password_history::Column::Id)
+			.offset(history_size - 1)
+			.into_tuple()
+			.all(transaction)
+			.await?;
+
+		if !outdated_ids.is_empty() {
+			PasswordHistory::delete_many()
+				.filter(password_history::Column::Id.is_in(outdated_ids))
+				.exec(transaction)
+				.await?;
+		}
+
+		Ok(())
+	}
+
+	async fn send_password_reset_link(&self, user: &UserModel) -> Result<(), AppError> {
+		let token = self
+			.confirmation_token_service
+			.generate_password_reset_token(user.id, &user.email)
+			.await?;
+
+		let expiry = Utc::now() + chrono::Duration::seconds(self.confirmation_token_expires_in);
+		let expiry_sea_orm = expiry.into();
+
+		let db = self.users_repository.get_db();
+		let transaction = db.begin().await?;
+
+		let now = Utc::now();
+		let mut user_active_model = users::ActiveModel {
+			id: Set(user.id),
+			password_reset_token: Set(Some(token.clone())),
+			password_reset_token_expiry: Set(Some(expiry_sea_orm)),
+			updated_at: Set(Some(now.into())),
+			..Default::default()
+		};
+
+		user_active_model.update(&transaction).await?;
+
+		match self
+			.email_service
+			.send_password_reset(&user.email, &user.username, &token)
+			.await
+		{
+			Ok(_) => {
+				transaction.commit().await?;
+				Ok(())
+			}
+			Err(e) => {
+				transaction.rollback().await?;
+				Err(e)
+			}
+		}
 	}
 }
 
@@ -97,8 +321,10 @@ pub trait UsersServiceTrait: Send + Sync {
 	) -> Result<UserModel, AppError>;
 	async fn update(&self, id: i32, dto: UpdateUserDto) -> Result<UserModel, AppError>;
 	async fn delete(&self, id: i32) -> Result<(), AppError>;
-	async fn login(&self, username: &str, password: &str) -> Result<UserModel, AppError>;
+	async fn login(&self, username: &str, password: &str, ip_address: Option<&str>) -> Result<UserModel, AppError>;
 	async fn confirm_email(&self, token: &str) -> Result<(), AppError>;
+	async fn resend_confirmation_email(&self, dto: ResendConfirmationDto) -> Result<(), AppError>;
+	async fn notify_registration_attempt(&self, email: &str) -> Result<bool, AppError>;
 	async fn request_reset_password(&self, dto: ForgotPasswordDto) -> Result<(), AppError>;
 	async fn reset_password(&self, dto: ResetPasswordDto) -> Result<(), AppError>;
 	async fn change_password(&self, user_id: i32, dto: ChangePasswordDto) -> Result<(), AppError>;
@@ -135,6 +361,10 @@ impl UsersServiceTrait for UsersService {
 	}
 
 	async fn create(&self, dto: CreateUserDto) -> Result<UserModel, AppError> {
+		self.password_policy_service
+			.validate("password", &dto.password, &dto.username, &dto.email)
+			.await?;
+
 		let db = self.users_repository.get_db();
 		let transaction = db.begin().await?;
 
@@ -176,7 +406,7 @@ impl UsersServiceTrait for UsersService {
 		let mut has_errors = false;
 
 		let user_email = dto.email.clone();
-		if let Ok(_) = self.users_repository.find_by_email(&user_email).await {
+		if self.users_repository.find_by_email(&user_email).await.is_ok() {
 			has_errors = true;
 			validation_errors.add(
 				"email",
@@ -186,7 +416,7 @@ impl UsersServiceTrait for UsersService {
 		}
 
 		let username = dto.username.clone();
-		if let Ok(_) = self.users_repository.find_by_username(&username).await {
+		if self.users_repository.find_by_username(&username).await.is_ok() {
 			has_errors = true;
 			validation_errors.add(
 				"username",
@@ -226,11 +456,49 @@ impl UsersServiceTrait for UsersService {
 	async fn delete(&self, id: i32) -> Result<(), AppError> {
 		let _existing_user = self.users_repository.find_by_id(id).await?;
 
-		self.users_repository.delete(id).await
+		let transaction = self.users_repository.get_db().begin().await?;
+		self.user_roles_service
+			.ensure_admin_remains_in_transaction(&transaction, RoleChange::DeleteUser { user_id: id })
+			.await?;
+		self.users_repository.delete_in_transaction(&transaction, id).await?;
+		transaction.commit().await?;
+
+		self.token_revocation_service.revoke_all_user_tokens(id).await
 	}
 
-	async fn login(&self, username: &str, password: &str) -> Result<UserModel, AppError> {
-		let user = self.users_repository.find_by_username(username).await?;
+	async fn login(&self, username: &str, password: &str, ip_address: Option<&str>) -> Result<UserModel, AppError> {
+		self.login_protection_service
+			.check_login_allowed(username, ip_address)
+			.await?;
+
+		let user = match self.users_repository.find_by_username(username).await {
+			Ok(user) => user,
+			Err(AppError::NotFound) => {
+				self.login_protection_service
+					.record_failed_login(username, ip_address)
+					.await?;
+
+				if self.app_config.get_config().security.enumeration_safe {
+					// Hashing takes as long as verifying, so unknown usernames answer as slowly as wrong passwords
+					self.hash_password(password)?;
+					return Err(AppError::AuthenticationError(translate(
+						"auth.errors.invalid_credentials",
+					)));
+				}
+				return Err(AppError::NotFound);
+			}
+			Err(e) => return Err(e),
+		};
+
+		// The password is checked first, so the account state is only revealed to whoever knows it
+		if !self.verify_password(password, &user.password_hash)? {
+			self.login_protection_service
+				.record_failed_login(username, ip_address)
+				.await?;
+			return Err(AppError::AuthenticationError(translate(
+				"auth.errors.invalid_credentials",
+			)));
+		}
 
 		if !user.is_active {
 			return Err(AppError::AuthenticationError(translate("auth.errors.account_inactive")));
@@ -242,13 +510,16 @@ impl UsersServiceTrait for UsersService {
 			)));
 		}
 
-		if self.verify_password(password, &user.password_hash)? {
-			Ok(user)
-		} else {
-			Err(AppError::AuthenticationError(translate(
-				"auth.errors.invalid_credentials",
-			)))
+		// With two-factor enabled the lockout is cleared only after the code, so wrong codes keep counting
+		if !user.is_two_factor_enabled {
+			self.login_protection_service.record_successful_login(username).await?;
 		}
+
+		if let Err(e) = self.upgrade_password_hash(&user, password).await {
+			tracing::error!("Failed to upgrade password hash of user {}: {:?}", user.id, e);
+		}
+
+		Ok(user)
 	}
 
 	async fn confirm_email(&self, token: &str) -> Result<(), AppError> {
@@ -295,34 +566,19 @@ impl UsersServiceTrait for UsersService {
 		Ok(())
 	}
 
-	async fn request_reset_password(&self, dto: ForgotPasswordDto) -> Result<(), AppError> {
-		let user = self.users_repository.find_by_email(&dto.email).await?;
-
-		let token = self
-			.confirmation_token_service
-			.generate_password_reset_token(user.id, &user.email)
-			.await?;
-
-		let expiry = Utc::now() + chrono::Duration::seconds(self.confirmation_token_expires_in);
-		let expiry_sea_orm = expiry.into();
-
-		let db = self.users_repository.get_db();
-		let transaction = db.begin().await?;
-
-		let now = Utc::now();
-		let mut user_active_model = users::ActiveModel {
-			id: Set(user.id),
-			password_reset_token: Set(Some(token.clone())),
-			password_reset_token_expiry: Set(Some(expiry_sea_orm)),
-			updated_at: Set(Some(now.into())),
-			..Default::default()
+	async fn resend_confirmation_email(&self, dto: ResendConfirmationDto) -> Result<(), AppError> {
+		// Unknown and already confirmed addresses get the same empty response
+		let user = match self.users_repository.find_by_email(&dto.email).await {
+			Ok(user) if user.is_active && !user.is_email_confirmed => user,
+			Ok(_) | Err(AppError::NotFound) => return Ok(()),
+			Err(e) => return Err(e),
 		};
 
-		user_active_model.update(&transaction).await?;
+		// Storing the new token replaces the old one, so earlier links stop working
+		let transaction = self.users_repository.get_db().begin().await?;
 
 		match self
-			.email_service
-			.send_password_reset(&user.email, &user.username, &token)
+			.send_confirmation_email(&transaction, user.id, &user.email, &user.username)
 			.await
 		{
 			Ok(_) => {
@@ -336,6 +592,41 @@ impl UsersServiceTrait for UsersService {
 		}
 	}
 
+	async fn notify_registration_attempt(&self, email: &str) -> Result<bool, AppError> {
+		let user = match self.users_repository.find_by_email(email).await {
+			Ok(user) => user,
+			Err(AppError::NotFound) => return Ok(false),
+			Err(e) => return Err(e),
+		};
+
+		self.email_service
+			.send_registration_attempt(&user.email, &user.username)
+			.await?;
+
+		Ok(true)
+	}
+
+	async fn request_reset_password(&self, dto: ForgotPasswordDto) -> Result<(), AppError> {
+		let user = match self.users_repository.find_by_email(&dto.email).await {
+			Ok(user) => user,
+			Err(AppError::NotFound) if self.app_config.get_config().security.enumeration_safe => return Ok(()),
+			Err(e) => return Err(e),
+		};
+
+		// Sent in the background, so a known email is answered as fast as an unknown one
+		if self.app_config.get_config().security.enumeration_safe {
+			let users_service = self.clone();
+			tokio::spawn(async move {
+				if let Err(e) = users_service.send_password_reset_link(&user).await {
+					tracing::error!("Failed to send password reset email: {:?}", e);
+				}
+			});
+			return Ok(());
+		}
+
+		self.send_password_reset_link(&user).await
+	}
+
 	async fn reset_password(&self, dto: ResetPasswordDto) -> Result<(), AppError> {
 		let claims = self.confirmation_token_service.validate_token(&dto.token).await?;
 		let user_id = claims.sub;
@@ -352,6 +643,12 @@ impl UsersServiceTrait for UsersService {
 			)
 			.await?;
 
+		self.password_policy_service
+			.validate("password", &dto.password, &user.username, &user.email)
+			.await?;
+		self.ensure_password_not_reused("password", &user, &dto.password)
+			.await?;
+
 		let password_hash = self.hash_password(&dto.password)?;
 
 		let db = self.users_repository.get_db();
@@ -368,8 +665,14 @@ impl UsersServiceTrait for UsersService {
 		};
 
 		user_active_model.update(&transaction).await?;
+		self.record_password_history(&transaction, user_id, &user.password_hash)
+			.await?;
 		transaction.commit().await?;
 
+		// A stolen refresh token must not outlive the password it was issued for
+		self.refresh_token_service.invalidate_all_user_tokens(user_id).await?;
+		self.token_revocation_service.revoke_all_user_tokens(user_id).await?;
+
 		Ok(())
 	}
 
@@ -385,6 +688,12 @@ impl UsersServiceTrait for UsersService {
 			return Err(AppError::ValidationError(errors));
 		}
 
+		self.password_policy_service
+			.validate("new_password", &dto.new_password, &user.username, &user.email)
+			.await?;
+		self.ensure_password_not_reused("new_password", &user, &dto.new_password)
+			.await?;
+
 		let password_hash = self.hash_password(&dto.new_password)?;
 
 		let db = self.users_repository.get_db();
@@ -400,9 +709,15 @@ impl UsersServiceTrait for UsersService {
 		user_active_model.id = Set(user_id);
 
 		user_active_model.update(&transaction).await?;
+		self.record_password_history(&transaction, user_id, &user.password_hash)
+			.await?;
 
 		transaction.commit().await?;
 
+		// A stolen refresh token must not outlive the password it was issued for
+		self.refresh_token_service.invalidate_all_user_tokens(user_id).await?;
+		self.token_revocation_service.revoke_all_user_tokens(user_id).await?;
+
 		Ok(())
 	}
 
@@ -419,16 +734,16 @@ impl UsersServiceTrait for UsersService {
 			return Err(AppError::ValidationError(errors));
 		}
 
-		if let Ok(existing_user) = self.users_repository.find_by_email(&dto.email).await {
-			if existing_user.id != user_id {
-				let mut errors = validator::ValidationErrors::new();
-				errors.add(
-					"email",
-					validator::ValidationError::new("already_exists")
-						.with_message(translate("users.errors.email_already_exists").into()),
-				);
-				return Err(AppError::ValidationError(errors));
-			}
+		if let Ok(existing_user) = self.users_repository.find_by_email(&dto.email).await
+			&& existing_user.id != user_id
+		{
+			let mut errors = validator::ValidationErrors::new();
+			errors.add(
+				"email",
+				validator::ValidationError::new("already_exists")
+					.with_message(translate("users.errors.email_already_exists").into()),
+			);
+			return Err(AppError::ValidationError(errors));
 		}
 
 		let token = self
@@ -480,7 +795,7 @@ impl UsersServiceTrait for UsersService {
 		let new_email = claims
 			.new_email
 			.as_ref()
-			.ok_or_else(|| AppError::AuthorizationError(translate("auth.errors.invalid_token").into()))?;
+			.ok_or_else(|| AppError::AuthorizationError(translate("auth.errors.invalid_token")))?;
 
 		let db = self.users_repository.get_db();
 		let transaction = db.begin().await?;